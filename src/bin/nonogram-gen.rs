@@ -0,0 +1,288 @@
+//! Generates black and white puzzles without opening a window, and writes them as `.non` files or as a single
+//! JSON pack.
+//!
+//! ```text
+//! nonogram-gen [--count N] [--size WxH]... [--seed S] [--density D] [--unique] [--out DIR | --pack FILE]
+//! ```
+//!
+//! `--count` puzzles are generated for every `--size` (10x10 by default, and no bigger than the game's biggest
+//! board size). Every puzzle is generated from its own
+//! seed, counting up from `--seed`, so running the same command again generates the same puzzles. `--density` is
+//! the chance of each cell being filled (0.5 by default). With `--unique`, puzzles with more than one solution are
+//! thrown away and the next seed is tried. Every puzzle is rated easy, medium or hard by how far the solver gets
+//! without guessing. Puzzles are written to `--out` (the current folder by default) unless `--pack` is given.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use nonogram::common::{DIMENSIONS_CHOICES, MAX_BOARD_DIMENSION};
+use nonogram::nonogram_solver::{rate, solve};
+use nonogram::{Cell, Difficulty, NonogramBoard, Puzzle, Ruleset};
+
+/// Usage shown when the arguments can't be read.
+const USAGE: &str = "Usage: nonogram-gen [--count N] [--size WxH]... [--seed S] [--density D] \
+    [--unique] [--out DIR | --pack FILE]";
+
+/// How many seeds are tried for each puzzle with `--unique` before giving up.
+const MAX_ATTEMPTS: u64 = 1000;
+
+/// Everything read from the command line.
+struct Options {
+    count: usize,
+    sizes: Vec<[usize; 2]>,
+    seed: u64,
+    density: f64,
+    unique: bool,
+    out: PathBuf,
+    pack: Option<PathBuf>,
+}
+
+/// A generated puzzle, along with what's needed to describe it. Also how every puzzle is written in a pack.
+#[derive(Serialize)]
+struct Generated {
+    name: String,
+    dimensions: [usize; 2],
+    seed: u64,
+    density: f64,
+    difficulty: String,
+    unique: bool,
+
+    /// Hint numbers laid out just like `NonogramBoard::goal_nums`, so they can be read by `nonogram-solve`.
+    goal_nums: Vec<Vec<Vec<i8>>>,
+
+    /// The solution, one string per row, with `1` for filled cells and `0` for empty ones.
+    goal: Vec<String>,
+}
+
+/// Everything written to a pack file.
+#[derive(Serialize)]
+struct Pack {
+    puzzles: Vec<Generated>,
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(why) => {
+            eprintln!("{}", why);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    // Seeds count up from `--seed`, and run out after the biggest one.
+    let mut seed = Some(options.seed);
+    let mut puzzles = vec![];
+    for &dimensions in options.sizes.iter() {
+        for _ in 0..options.count {
+            let first_seed = seed.unwrap_or_else(|| {
+                eprintln!("Ran out of seeds after {}. Try a lower --seed.", u64::MAX);
+                process::exit(1);
+            });
+            match generate(&options, dimensions, first_seed) {
+                Some(puzzle) => {
+                    seed = puzzle.seed.checked_add(1);
+                    puzzles.push(puzzle);
+                }
+                None => {
+                    eprintln!(
+                        "Couldn't find a unique {}x{} puzzle in {} tries. Try a higher density.",
+                        dimensions[0], dimensions[1], MAX_ATTEMPTS
+                    );
+                    process::exit(1);
+                }
+            }
+        }
+    }
+
+    if let Err(why) = write(&options, puzzles) {
+        eprintln!("{}", why);
+        process::exit(1);
+    }
+}
+
+/// Read the command line arguments.
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut options = Options {
+        count: 1,
+        sizes: vec![],
+        seed: rand::random::<u32>() as u64,
+        density: 0.5,
+        unique: false,
+        out: PathBuf::from("."),
+        pack: None,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--unique" {
+            options.unique = true;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing a value after {}", arg))?;
+        let bad_value = || format!("Bad value for {}: {}", arg, value);
+        match arg.as_str() {
+            "--count" => options.count = value.parse().map_err(|_| bad_value())?,
+            "--seed" => options.seed = value.parse().map_err(|_| bad_value())?,
+            "--density" => {
+                options.density = value.parse().map_err(|_| bad_value())?;
+                if !(0.0..=1.0).contains(&options.density) {
+                    return Err(bad_value());
+                }
+            }
+            "--size" => {
+                let size: Vec<usize> = value
+                    .split('x')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| bad_value())?;
+                if size.len() != 2 || size.contains(&0) {
+                    return Err(bad_value());
+                }
+                let largest = largest_dimension();
+                if size.iter().any(|&dimension| dimension > largest) {
+                    return Err(format!(
+                        "Bad value for {}: {} (at most {}x{})",
+                        arg, value, largest, largest
+                    ));
+                }
+                options.sizes.push([size[0], size[1]]);
+            }
+            "--out" => options.out = PathBuf::from(value),
+            "--pack" => options.pack = Some(PathBuf::from(value)),
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    if options.sizes.is_empty() {
+        options.sizes.push([10, 10]);
+    }
+    Ok(options)
+}
+
+/// The most columns or rows a generated puzzle can have, the biggest of any of the game's board sizes.
+fn largest_dimension() -> usize {
+    DIMENSIONS_CHOICES
+        .iter()
+        .flat_map(|dimensions| dimensions.iter().copied())
+        .max()
+        .unwrap_or(MAX_BOARD_DIMENSION)
+}
+
+/// Generate a puzzle, starting with `seed`. With `--unique`, later seeds are tried until a puzzle with a unique
+/// solution is found. Returns `None` if none is found.
+fn generate(options: &Options, dimensions: [usize; 2], seed: u64) -> Option<Generated> {
+    let mut board = NonogramBoard::empty(dimensions, 1, Ruleset::Standard);
+    board.init_ratio = options.density;
+
+    for seed in (0..MAX_ATTEMPTS).map_while(|attempt| seed.checked_add(attempt)) {
+        board.generate_from(&mut StdRng::seed_from_u64(seed));
+        let puzzle =
+            Puzzle::from_goal_nums(&board.goal_nums).expect("boards have columns and rows");
+        let unique = solve(&puzzle, 2).is_unique();
+        if options.unique && !unique {
+            continue;
+        }
+
+        let difficulty = if unique {
+            rate(&puzzle)
+        } else {
+            Difficulty::Hard
+        };
+        return Some(Generated {
+            name: format!("{}x{}-{}", dimensions[0], dimensions[1], seed),
+            dimensions,
+            seed,
+            density: options.density,
+            difficulty: difficulty.to_string(),
+            unique,
+            goal_nums: board.goal_nums.clone(),
+            goal: (0..dimensions[1])
+                .map(|row| {
+                    (0..dimensions[0])
+                        .map(|col| match board.goal_data[col][row] {
+                            Cell::Empty => '0',
+                            _ => '1',
+                        })
+                        .collect()
+                })
+                .collect(),
+        });
+    }
+    None
+}
+
+/// Write every puzzle to its own `.non` file, or all of them to a single pack.
+fn write(options: &Options, puzzles: Vec<Generated>) -> Result<(), String> {
+    if let Some(pack) = &options.pack {
+        let count = puzzles.len();
+        let contents = serde_json::to_string_pretty(&Pack { puzzles })
+            .map_err(|why| format!("Couldn't write the pack: {}", why))?;
+        fs::write(pack, contents)
+            .map_err(|why| format!("Couldn't write {}: {}", pack.display(), why))?;
+        println!("Wrote {} puzzles to {}", count, pack.display());
+        return Ok(());
+    }
+
+    fs::create_dir_all(&options.out)
+        .map_err(|why| format!("Couldn't create {}: {}", options.out.display(), why))?;
+    for puzzle in puzzles.iter() {
+        let path = options.out.join(format!("{}.non", puzzle.name));
+        fs::write(&path, format_non(puzzle))
+            .map_err(|why| format!("Couldn't write {}: {}", path.display(), why))?;
+        println!(
+            "Wrote {} ({}, {})",
+            path.display(),
+            puzzle.difficulty,
+            if puzzle.unique {
+                "unique"
+            } else {
+                "not unique"
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Write a puzzle in the `.non` format, with the difficulty and how it was generated before the hint numbers.
+fn format_non(puzzle: &Generated) -> String {
+    let hint_line = |line: &Vec<i8>| -> String {
+        let nums: Vec<String> = line
+            .iter()
+            .filter(|&&num| num != 0)
+            .map(|num| num.to_string())
+            .collect();
+        if nums.is_empty() {
+            "0".to_string()
+        } else {
+            nums.join(",")
+        }
+    };
+
+    let mut non = String::new();
+    non.push_str("catalogue \"nonogram-gen\"\n");
+    non.push_str(&format!("title \"{}\"\n", puzzle.name));
+    non.push_str(&format!("width {}\n", puzzle.dimensions[0]));
+    non.push_str(&format!("height {}\n", puzzle.dimensions[1]));
+    non.push_str(&format!("difficulty {}\n", puzzle.difficulty));
+    non.push_str(&format!("unique {}\n", puzzle.unique));
+    non.push_str(&format!("density {}\n", puzzle.density));
+    non.push_str(&format!("seed {}\n", puzzle.seed));
+    non.push_str("\nrows\n");
+    for line in puzzle.goal_nums[1].iter() {
+        non.push_str(&format!("{}\n", hint_line(line)));
+    }
+    non.push_str("\ncolumns\n");
+    for line in puzzle.goal_nums[0].iter() {
+        non.push_str(&format!("{}\n", hint_line(line)));
+    }
+    non.push_str(&format!("\ngoal \"{}\"\n", puzzle.goal.concat()));
+    non
+}
@@ -0,0 +1,50 @@
+//! Runs scripts of synthetic input events against the controller, without opening a window.
+//!
+//! ```text
+//! nonogram-script SCRIPT...
+//! ```
+//!
+//! Every script starts on its own 5x5 board and is run on its own controller, which keeps its save files in memory
+//! and uses the default keybindings. See the `nonogram_script` module for what a script can do. Prints whether each
+//! script passed, and exits with an error if any of them failed.
+
+use std::env;
+use std::fs;
+use std::process;
+
+use nonogram::nonogram_script::Script;
+use nonogram::{MemoryStorage, NonogramBoard, NonogramController};
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("Usage: nonogram-script SCRIPT...");
+        process::exit(2);
+    }
+
+    let mut failed = 0;
+    for path in paths.iter() {
+        match run(path) {
+            Ok(()) => println!("ok {}", path),
+            Err(why) => {
+                println!("FAILED {}: {}", path, why);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        eprintln!("{} of {} scripts failed", failed, paths.len());
+        process::exit(1);
+    }
+}
+
+/// Read a script and run it on a fresh controller.
+fn run(path: &str) -> Result<(), String> {
+    let text =
+        fs::read_to_string(path).map_err(|why| format!("Couldn't read {}: {}", path, why))?;
+    let script = Script::parse(&text)?;
+    let mut controller =
+        NonogramController::new(NonogramBoard::new([5, 5]), Box::new(MemoryStorage::new()));
+    script.run(&mut controller)
+}
@@ -0,0 +1,79 @@
+//! Solves a puzzle file without opening a window, and prints the solution, whether it's unique, and how long
+//! solving took.
+//!
+//! ```text
+//! nonogram-solve <puzzle file> [--all]
+//! ```
+//!
+//! The puzzle file can be a `.non` file, a JSON file of `goal_nums`, or a savedata.json file. Only black and white
+//! puzzles are supported. Every solution is printed with `--all`, otherwise only the first one is. Puzzles with a
+//! unique solution are also rated easy, medium or hard. The exit code is 0 for a unique solution, 1 for several
+//! solutions, and 2 for no solution or an unreadable file.
+
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Instant;
+
+use nonogram::nonogram_solver::{format_grid, is_line_solvable, rate, solve};
+use nonogram::Puzzle;
+
+/// How many solutions to search for when not printing all of them. 2 is enough to tell whether a solution is
+/// unique.
+const UNIQUENESS_LIMIT: usize = 2;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let all = args.iter().any(|arg| arg == "--all");
+    let path = match args.iter().find(|arg| !arg.starts_with("--")) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: nonogram-solve <puzzle file> [--all]");
+            process::exit(2);
+        }
+    };
+
+    let puzzle = match fs::read_to_string(path)
+        .map_err(|why| format!("Couldn't read {}: {}", path, why))
+        .and_then(|contents| Puzzle::parse(&contents))
+    {
+        Ok(puzzle) => puzzle,
+        Err(why) => {
+            eprintln!("{}", why);
+            process::exit(2);
+        }
+    };
+
+    let start = Instant::now();
+    let limit = if all { usize::MAX } else { UNIQUENESS_LIMIT };
+    let solutions = solve(&puzzle, limit);
+    let elapsed = start.elapsed();
+
+    let shown = if all { solutions.grids.len() } else { 1 };
+    for (it, grid) in solutions.grids.iter().take(shown).enumerate() {
+        if it > 0 {
+            println!();
+        }
+        println!("{}", format_grid(grid));
+    }
+    if !solutions.grids.is_empty() {
+        println!();
+    }
+
+    let [cols, rows] = puzzle.dimensions();
+    println!("size: {}x{}", cols, rows);
+    println!("solutions: {}", solutions);
+    println!("line solvable: {}", is_line_solvable(&puzzle));
+    if solutions.is_unique() {
+        println!("difficulty: {}", rate(&puzzle));
+    }
+    println!("time: {:.3} ms", elapsed.as_secs_f64() * 1000.0);
+
+    if solutions.is_unique() {
+        process::exit(0);
+    } else if solutions.grids.is_empty() {
+        process::exit(2);
+    } else {
+        process::exit(1);
+    }
+}
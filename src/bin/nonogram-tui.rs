@@ -0,0 +1,595 @@
+//! Plays the game inside a terminal, without opening a window. Useful over SSH, or anywhere OpenGL isn't available.
+//!
+//! ```text
+//! nonogram-tui
+//! ```
+//!
+//! The board, its hint numbers and the timer are drawn as text. Every key is handed to the same controller the
+//! window uses, so the keybindings in controls.json work just the same, and so do savedata.json, clipdata.json and
+//! statsdata.json. The mouse can be used to fill and mark cells too. `Ctrl+C` saves and quits.
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    self, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture, KeyCode,
+    KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind,
+};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use piston::input::{Button, ButtonArgs, ButtonState, CloseArgs, Event, Input, Key, Motion};
+use std::io::{self, Write};
+use std::time::Duration;
+
+use nonogram::common::{
+    CLUE_HALF_END, CLUE_HALF_START, CLUE_MEGA, INITIAL_BOARD_DIMENSIONS, PALETTE,
+};
+use nonogram::nonogram_controller::{button_name, Control};
+use nonogram::{Cell, Corner, FileStorage, NonogramBoard, NonogramController, WindowLayout};
+
+/// Width of a cell, and of a hint number, in terminal columns.
+const CELL_WIDTH: usize = 3;
+
+/// How often the timer is redrawn while no keys are being pressed.
+const TICK: Duration = Duration::from_millis(200);
+
+/// Where the cursor is sent when the mouse isn't over the board, so nothing the controller hit-tests is under it.
+const NOWHERE: [f64; 2] = [-1.0e6, -1.0e6];
+
+/// Keyboard keys for every function key, from F1 to F24.
+const FUNCTION_KEYS: [Key; 24] = [
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::F13,
+    Key::F14,
+    Key::F15,
+    Key::F16,
+    Key::F17,
+    Key::F18,
+    Key::F19,
+    Key::F20,
+    Key::F21,
+    Key::F22,
+    Key::F23,
+    Key::F24,
+];
+
+/// Puts the terminal into raw mode on an alternate screen, and puts it back once dropped, even after a panic.
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            io::stdout(),
+            EnterAlternateScreen,
+            Hide,
+            EnableMouseCapture,
+            EnableFocusChange
+        )?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(
+            io::stdout(),
+            DisableFocusChange,
+            DisableMouseCapture,
+            Show,
+            LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Where the board was last drawn in the terminal, so mouse clicks can be turned into cells.
+#[derive(Clone, Copy, Default)]
+struct Layout {
+    /// Terminal column and row of the top left cell.
+    origin: [u16; 2],
+
+    /// Columns and rows of the board.
+    dimensions: [usize; 2],
+}
+
+impl Layout {
+    /// The position handed to the controller for a mouse cursor at a terminal column and row. The board is
+    /// treated as if it were drawn at the top left of the window with cells 1 pixel wide, so the middle of each
+    /// cell is used.
+    fn cursor(&self, column: u16, row: u16) -> [f64; 2] {
+        if column < self.origin[0] || row < self.origin[1] {
+            return NOWHERE;
+        }
+        let cell = [
+            (column - self.origin[0]) as usize / CELL_WIDTH,
+            (row - self.origin[1]) as usize,
+        ];
+        if cell[0] >= self.dimensions[0] || cell[1] >= self.dimensions[1] {
+            return NOWHERE;
+        }
+        [cell[0] as f64 + 0.5, cell[1] as f64 + 0.5]
+    }
+}
+
+fn main() {
+    let storage = FileStorage::default();
+    let nonogram = NonogramBoard::load(&storage)
+        .unwrap_or_else(|why| panic!("{}", why))
+        .unwrap_or_else(|| NonogramBoard::new(INITIAL_BOARD_DIMENSIONS));
+    let mut controller = NonogramController::new(nonogram, Box::new(storage));
+
+    let screen = match Screen::enter() {
+        Ok(screen) => screen,
+        Err(why) => {
+            eprintln!("Couldn't set up the terminal: {}", why);
+            std::process::exit(1);
+        }
+    };
+    let result = run(&mut controller);
+    drop(screen);
+
+    // The controller saves everything when the window closes, so quitting works just the same.
+    send(&mut controller, Input::Close(CloseArgs), Layout::default());
+    if let Err(why) = result {
+        eprintln!("{}", why);
+        std::process::exit(1);
+    }
+}
+
+/// Draw the game and hand every key and mouse event to the controller until the player quits.
+fn run(controller: &mut NonogramController) -> io::Result<()> {
+    let mut out = io::stdout();
+    let mut layout = Layout::default();
+    loop {
+        if controller.nonogram.reset_board {
+            controller.start_next_board();
+        }
+        if !controller.nonogram.end_game_screen {
+            controller.nonogram.update_duration();
+        }
+        layout = draw(&mut out, controller, layout)?;
+
+        if !event::poll(TICK)? {
+            continue;
+        }
+        match event::read()? {
+            event::Event::Key(key) if key.kind != KeyEventKind::Release => {
+                // Every other key is handed to the controller, so only Ctrl+C quits.
+                if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                    return Ok(());
+                }
+                press_key(controller, key, layout);
+            }
+            event::Event::Mouse(mouse) => mouse_event(controller, mouse, layout),
+            event::Event::FocusLost => send(controller, Input::Focus(false), layout),
+            event::Event::FocusGained => send(controller, Input::Focus(true), layout),
+            event::Event::Resize(..) => execute!(out, Clear(ClearType::All))?,
+            _ => (),
+        }
+    }
+}
+
+/// Hand a single input event to the controller.
+fn send(controller: &mut NonogramController, input: Input, layout: Layout) {
+    let size = [layout.dimensions[0] as f64, layout.dimensions[1] as f64];
+    controller.event(
+        &WindowLayout::board_only([0.0, 0.0], size),
+        &Event::Input(input, None),
+    );
+}
+
+/// Press or release a button.
+fn send_button(
+    controller: &mut NonogramController,
+    button: Button,
+    state: ButtonState,
+    layout: Layout,
+) {
+    let args = ButtonArgs {
+        state,
+        button,
+        scancode: None,
+    };
+    send(controller, Input::Button(args), layout);
+}
+
+/// Turn a terminal key into the keyboard key the window would have seen. Printable characters have the same codes
+/// as the window's keys, except that letters are always lowercase.
+fn key_of(code: KeyCode) -> Option<Key> {
+    let key = match code {
+        KeyCode::Char(c) if c.is_ascii_graphic() || c == ' ' => {
+            Key::from(c.to_ascii_lowercase() as u32)
+        }
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Enter => Key::Return,
+        KeyCode::Tab | KeyCode::BackTab => Key::Tab,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Insert => Key::Insert,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Esc => Key::Escape,
+        KeyCode::F(n) => *FUNCTION_KEYS.get((n as usize).checked_sub(1)?)?,
+        KeyCode::CapsLock => Key::CapsLock,
+        KeyCode::ScrollLock => Key::ScrollLock,
+        KeyCode::NumLock => Key::NumLockClear,
+        KeyCode::PrintScreen => Key::PrintScreen,
+        KeyCode::Pause => Key::Pause,
+        KeyCode::Menu => Key::Menu,
+        _ => return None,
+    };
+    if key == Key::Unknown {
+        None
+    } else {
+        Some(key)
+    }
+}
+
+/// Hand a key press to the controller.
+///
+/// Terminals only report keys being pressed, so every key is released right away. `Shift`, `Ctrl` and `Alt` are
+/// only reported along with another key, so they're held down around it, which is enough for the jump and skip
+/// keybindings.
+fn press_key(controller: &mut NonogramController, key: KeyEvent, layout: Layout) {
+    let key_code = match key_of(key.code) {
+        Some(key_code) => key_code,
+        None => return,
+    };
+    let modifiers: Vec<Key> = [
+        (KeyModifiers::SHIFT, Key::LShift),
+        (KeyModifiers::CONTROL, Key::LCtrl),
+        (KeyModifiers::ALT, Key::LAlt),
+    ]
+    .iter()
+    .filter(|(flag, _)| key.modifiers.contains(*flag))
+    .map(|&(_, modifier)| modifier)
+    .collect();
+
+    for &modifier in modifiers.iter() {
+        send_button(
+            controller,
+            Button::Keyboard(modifier),
+            ButtonState::Press,
+            layout,
+        );
+    }
+    send_button(
+        controller,
+        Button::Keyboard(key_code),
+        ButtonState::Press,
+        layout,
+    );
+    send_button(
+        controller,
+        Button::Keyboard(key_code),
+        ButtonState::Release,
+        layout,
+    );
+    for &modifier in modifiers.iter().rev() {
+        send_button(
+            controller,
+            Button::Keyboard(modifier),
+            ButtonState::Release,
+            layout,
+        );
+    }
+}
+
+/// Hand a mouse event to the controller. The cursor is moved first, just like it would be in the window.
+fn mouse_event(controller: &mut NonogramController, mouse: MouseEvent, layout: Layout) {
+    let cursor = layout.cursor(mouse.column, mouse.row);
+    send(controller, Input::Move(Motion::MouseCursor(cursor)), layout);
+
+    let mouse_button = |button: event::MouseButton| {
+        Button::Mouse(match button {
+            event::MouseButton::Left => piston::input::MouseButton::Left,
+            event::MouseButton::Right => piston::input::MouseButton::Right,
+            event::MouseButton::Middle => piston::input::MouseButton::Middle,
+        })
+    };
+    match mouse.kind {
+        MouseEventKind::Down(button) => {
+            send_button(controller, mouse_button(button), ButtonState::Press, layout)
+        }
+        MouseEventKind::Up(button) => send_button(
+            controller,
+            mouse_button(button),
+            ButtonState::Release,
+            layout,
+        ),
+        _ => (),
+    }
+}
+
+/// Format a duration the same way the timer displays it: `HH:MM:SS`.
+fn format_duration(duration: Duration) -> String {
+    let total_seconds = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        total_seconds / 3600,
+        (total_seconds / 60) % 60,
+        total_seconds % 60
+    )
+}
+
+/// Terminal color of a palette color. Palette colors are hex strings like `"d64541"`.
+fn palette_color(index: usize) -> Color {
+    // The first palette color is nearly black, which can't be seen on most terminals, so the terminal's own
+    // text color is used instead.
+    if index == 0 {
+        return Color::Reset;
+    }
+    let hex = PALETTE[index % PALETTE.len()];
+    let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).unwrap_or(255);
+    Color::Rgb {
+        r: channel(0),
+        g: channel(2),
+        b: channel(4),
+    }
+}
+
+/// Text and color of a cell.
+fn cell_text(cell: Cell) -> (&'static str, Color) {
+    match cell {
+        Cell::Empty => (" · ", Color::DarkGrey),
+        Cell::Filled => ("███", palette_color(0)),
+        Cell::Colored(index) => ("███", palette_color(index as usize)),
+        Cell::Marked => (" x ", Color::DarkGrey),
+        Cell::Maybe => (" ? ", Color::Reset),
+        Cell::Half(index, corner) => (
+            match corner {
+                Corner::TopLeft => " ◤ ",
+                Corner::TopRight => " ◥ ",
+                Corner::BottomRight => " ◢ ",
+                Corner::BottomLeft => " ◣ ",
+            },
+            palette_color(index as usize),
+        ),
+    }
+}
+
+/// Queue a hint number right aligned in a cell's width. Crossed out numbers are grayed out, numbers covering both
+/// lines of a merged pair are bold, and numbers starting or ending with a half filled cell are underlined.
+fn queue_hint<W: Write>(
+    out: &mut W,
+    nonogram: &NonogramBoard,
+    axis: usize,
+    line: usize,
+    index: usize,
+) -> io::Result<()> {
+    let num = nonogram.goal_nums[axis][line][index];
+    let ends = nonogram.goal_ends[axis][line][index];
+    let color = if nonogram.is_crossed_out(axis, line, index) {
+        Color::DarkGrey
+    } else {
+        palette_color(nonogram.goal_colors[axis][line][index] as usize)
+    };
+    queue!(out, SetForegroundColor(color))?;
+    if ends & CLUE_MEGA != 0 {
+        queue!(out, SetAttribute(Attribute::Bold))?;
+    }
+    if ends & (CLUE_HALF_START | CLUE_HALF_END) != 0 {
+        queue!(out, SetAttribute(Attribute::Underlined))?;
+    }
+    queue!(
+        out,
+        Print(format!("{:>width$}", num.abs(), width = CELL_WIDTH)),
+        SetAttribute(Attribute::Reset),
+        ResetColor
+    )
+}
+
+/// Hint numbers of a line that aren't padding, as indices into `goal_nums`. The second line of a merged pair has
+/// none, because its hint numbers belong to the first line. Lines without any groupings show a 0.
+fn hint_indices(nonogram: &NonogramBoard, axis: usize, line: usize) -> Vec<usize> {
+    if line > 0 && nonogram.is_merged(axis, line - 1) {
+        return vec![];
+    }
+    let nums = &nonogram.goal_nums[axis][line];
+    let indices: Vec<usize> = (0..nums.len()).filter(|&index| nums[index] != 0).collect();
+    if indices.is_empty() {
+        vec![0]
+    } else {
+        indices
+    }
+}
+
+/// Short list of every keybinding, so players don't have to remember them.
+fn help_lines(controller: &NonogramController, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for &control in Control::ALL.iter() {
+        let keys: Vec<String> = controller
+            .controls
+            .get(control)
+            .iter()
+            .filter(|button| matches!(button, Button::Keyboard(_)))
+            .map(|&button| button_name(button))
+            .collect();
+        if keys.is_empty() {
+            continue;
+        }
+        let entry = format!("{} {}", keys.join("/"), control.name().to_lowercase());
+        let last = lines.last_mut().expect("there's always a line");
+        if !last.is_empty() && last.len() + entry.len() + 3 > width {
+            lines.push(entry);
+        } else {
+            if !last.is_empty() {
+                last.push_str(" · ");
+            }
+            last.push_str(&entry);
+        }
+    }
+    lines.push("Ctrl+C save and quit".to_string());
+    lines
+}
+
+/// Draw the whole game. Returns where the board ended up.
+fn draw<W: Write>(
+    out: &mut W,
+    controller: &NonogramController,
+    mut layout: Layout,
+) -> io::Result<Layout> {
+    let nonogram = &controller.nonogram;
+    let [cols, rows] = nonogram.dimensions;
+    let width = terminal::size().map(|size| size.0 as usize).unwrap_or(80);
+    queue!(out, MoveTo(0, 0))?;
+
+    let timer = match nonogram.time_limit() {
+        Some(time_limit) => format_duration(time_limit.saturating_sub(nonogram.duration)),
+        None => format_duration(nonogram.duration),
+    };
+    let mut status = format!(
+        "NONOGRAM  {}x{}  {}  {}  TIME {}",
+        cols,
+        rows,
+        nonogram.mode.name(),
+        nonogram.ruleset.name(),
+        timer
+    );
+    if let Some(clip) = &controller.clip {
+        status.push_str(&format!(
+            "  PANEL {}/{} ({} SOLVED)",
+            clip.current + 1,
+            clip.panel_count(),
+            clip.solved_count(nonogram)
+        ));
+    }
+    let next = format!(
+        "NEXT {}x{} {} {} COLORS {}  TOOL {}  PENCIL {}  COLOR {}",
+        nonogram.next_dimensions[0],
+        nonogram.next_dimensions[1],
+        nonogram.next_mode.name(),
+        nonogram.next_ruleset.name(),
+        nonogram.next_colors,
+        controller.tool.name(),
+        if controller.pencil { "ON" } else { "OFF" },
+        controller.color + 1
+    );
+    queue!(
+        out,
+        SetAttribute(Attribute::Bold),
+        Print(status),
+        SetAttribute(Attribute::Reset),
+        Clear(ClearType::UntilNewLine),
+        Print("\r\n"),
+        Print(next),
+        Clear(ClearType::UntilNewLine),
+        Print("\r\n\r\n")
+    )?;
+    let mut row_at = 3;
+
+    if nonogram.end_game_screen {
+        let message = if nonogram.time_up {
+            "TIME'S UP!".to_string()
+        } else if controller.new_personal_best {
+            format!(
+                "SOLVED IN {}! NEW PERSONAL BEST!",
+                format_duration(controller.duration())
+            )
+        } else {
+            format!("SOLVED IN {}!", format_duration(controller.duration()))
+        };
+        queue!(
+            out,
+            Print(message),
+            Clear(ClearType::UntilNewLine),
+            Print("\r\n")
+        )?;
+        row_at += 1;
+    } else if nonogram.is_paused() {
+        queue!(
+            out,
+            Print("PAUSED. The board is hidden until the game is resumed."),
+            Clear(ClearType::UntilNewLine),
+            Print("\r\n")
+        )?;
+        row_at += 1;
+    }
+
+    let col_hints: Vec<Vec<usize>> = (0..cols)
+        .map(|col| hint_indices(nonogram, 0, col))
+        .collect();
+    let row_hints: Vec<Vec<usize>> = (0..rows)
+        .map(|row| hint_indices(nonogram, 1, row))
+        .collect();
+    let col_hint_height = col_hints.iter().map(Vec::len).max().unwrap_or(0);
+    let row_hint_width = row_hints.iter().map(Vec::len).max().unwrap_or(0) * CELL_WIDTH + 1;
+
+    // Column hint numbers are stacked upwards from just above the board.
+    for depth in (0..col_hint_height).rev() {
+        queue!(out, Print(" ".repeat(row_hint_width)))?;
+        for (col, indices) in col_hints.iter().enumerate() {
+            match indices.len().checked_sub(depth + 1) {
+                Some(it) => queue_hint(out, nonogram, 0, col, indices[it])?,
+                None => queue!(out, Print(" ".repeat(CELL_WIDTH)))?,
+            }
+        }
+        queue!(out, Clear(ClearType::UntilNewLine), Print("\r\n"))?;
+        row_at += 1;
+    }
+
+    // Row hint numbers are lined up leftwards from just left of the board.
+    layout.origin = [row_hint_width as u16, row_at];
+    layout.dimensions = nonogram.dimensions;
+    let hidden = nonogram.is_paused() && !nonogram.end_game_screen;
+    let tool_cells = controller.tool_cells();
+    for (row, indices) in row_hints.iter().enumerate() {
+        let padding = row_hint_width - 1 - indices.len() * CELL_WIDTH;
+        queue!(out, Print(" ".repeat(padding)))?;
+        for &index in indices.iter() {
+            queue_hint(out, nonogram, 1, row, index)?;
+        }
+        queue!(out, Print(" "))?;
+        for col in 0..cols {
+            let (text, color) = if hidden {
+                (" · ", Color::DarkGrey)
+            } else {
+                cell_text(nonogram.data[col][row])
+            };
+            let selected =
+                nonogram.selected_cell == Some([col, row]) || tool_cells.contains(&[col, row]);
+            if selected {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(
+                out,
+                SetForegroundColor(color),
+                Print(text),
+                SetAttribute(Attribute::Reset),
+                ResetColor
+            )?;
+        }
+        queue!(out, Clear(ClearType::UntilNewLine), Print("\r\n"))?;
+    }
+
+    queue!(out, Clear(ClearType::UntilNewLine), Print("\r\n"))?;
+    for line in help_lines(controller, width) {
+        queue!(
+            out,
+            SetForegroundColor(Color::DarkGrey),
+            Print(line),
+            ResetColor,
+            Clear(ClearType::UntilNewLine),
+            Print("\r\n")
+        )?;
+    }
+    queue!(out, Clear(ClearType::FromCursorDown))?;
+    out.flush()?;
+    Ok(layout)
+}
@@ -1,18 +1,69 @@
 //! Contains enums and constants frequently used throughout the program.
 //!
 //! The following files use these parts:
-//! - [main], utilizes [INITIAL_WINDOW_SIZE] and [INITIAL_BOARD_DIMENSIONS] for window and board initialization.
-//! - [nonogram_board_view], utilizes [BOARD_SIZE], [DIMENSIONS_CHOICES], [IMAGE_PRE], [IMAGE_NAMES], and [ButtonInteraction].
+//! - `main`, utilizes [INITIAL_WINDOW_SIZE] and [INITIAL_BOARD_DIMENSIONS] for window and board initialization.
+//! - `nonogram_board_view`, utilizes [BOARD_SIZE], [PALETTE], [DIMENSIONS_CHOICES], [IMAGE_PRE], [IMAGE_NAMES], [STATS_HISTORY_SHOWN], [CONTROLS_ROW_OFFSET], [CONTROLS_ROW_HEIGHT], [CONTROLS_ROWS_SHOWN], [Cell], [Corner], [Ruleset], the `CLUE_*` hint number flags, and [ButtonInteraction].
+//! - [nonogram_board], utilizes [Cell], [Corner], [Ruleset], [GameMode], [PALETTE], [CLUE_HALF_START], [CLUE_HALF_END], [CLUE_MEGA], [CLUE_SECOND], [COUNTDOWN_SECONDS_PER_CELL], and [TIME_ATTACK_PENALTY_SECONDS].
+//! - [nonogram_stats], utilizes [STATS_HISTORY_LENGTH] and [STATS_FILE].
+//! - [nonogram_clip], utilizes [Cell], [GameMode], [Ruleset], [MAX_BOARD_DIMENSION], [CLIP_PANEL_SIZE], and [CLIP_FILE].
+//! - [nonogram_solver], utilizes [Ruleset] to turn down puzzles it can't solve.
+//! - [nonogram_board] and [nonogram_controller], utilize [SAVE_FILE].
+//! - [nonogram_action] and [nonogram_controller], utilize [Directions].
+//! - [nonogram_replay], utilizes [REPLAY_SPEEDS].
+//! - [nonogram_picture], utilizes [Cell], [Corner], [PALETTE], and [PICTURE_BACKGROUND].
+//! - `main` and [nonogram_controller], utilize [PICTURE_SCALES] and [PICTURE_FILE_PREFIX].
+//! - `nonogram_board_view` and [nonogram_controller], utilize [column_hint_y], [row_hint_x], [column_hint_at], and [row_hint_at] to agree on where hint numbers are.
+//! - [nonogram_controller], utilizes [Tool], [EditPolicy], [Corner], [Ruleset], [PALETTE], [STICK_DEADZONE], the `PAD_*` controller buttons, [CONTROLS_FILE], [CONTROLS_ROW_OFFSET], [CONTROLS_ROW_HEIGHT], and [CONTROLS_ROWS_SHOWN].
 //!
-//! [main]: ../fn.main.html
-//! [nonogram_board_view]: ../nonogram_board_view/index.html
+//! [nonogram_board]: ../nonogram_board/index.html
+//! [nonogram_stats]: ../nonogram_stats/index.html
+//! [nonogram_clip]: ../nonogram_clip/index.html
+//! [nonogram_solver]: ../nonogram_solver/index.html
+//! [nonogram_controller]: ../nonogram_controller/index.html
+//! [nonogram_action]: ../nonogram_action/index.html
+//! [nonogram_replay]: ../nonogram_replay/index.html
+//! [nonogram_picture]: ../nonogram_picture/index.html
 //! [ButtonInteraction]: enum.ButtonInteraction.html
+//! [Cell]: enum.Cell.html
+//! [Corner]: enum.Corner.html
+//! [Directions]: enum.Directions.html
+//! [EditPolicy]: enum.EditPolicy.html
+//! [GameMode]: enum.GameMode.html
+//! [Tool]: enum.Tool.html
+//! [column_hint_at]: fn.column_hint_at.html
+//! [column_hint_y]: fn.column_hint_y.html
+//! [row_hint_at]: fn.row_hint_at.html
+//! [row_hint_x]: fn.row_hint_x.html
+//! [CLIP_FILE]: constant.CLIP_FILE.html
+//! [CLIP_PANEL_SIZE]: constant.CLIP_PANEL_SIZE.html
+//! [CLUE_HALF_END]: constant.CLUE_HALF_END.html
+//! [CLUE_HALF_START]: constant.CLUE_HALF_START.html
+//! [CLUE_MEGA]: constant.CLUE_MEGA.html
+//! [CLUE_SECOND]: constant.CLUE_SECOND.html
+//! [CONTROLS_FILE]: constant.CONTROLS_FILE.html
+//! [CONTROLS_ROW_HEIGHT]: constant.CONTROLS_ROW_HEIGHT.html
+//! [CONTROLS_ROW_OFFSET]: constant.CONTROLS_ROW_OFFSET.html
+//! [CONTROLS_ROWS_SHOWN]: constant.CONTROLS_ROWS_SHOWN.html
+//! [COUNTDOWN_SECONDS_PER_CELL]: constant.COUNTDOWN_SECONDS_PER_CELL.html
 //! [BOARD_SIZE]: constant.BOARD_SIZE.html
 //! [DIMENSIONS_CHOICES]: constant.DIMENSIONS_CHOICES.html
 //! [IMAGE_NAMES]: constant.IMAGE_NAMES.html
 //! [IMAGE_PRE]: constant.IMAGE_PRE.html
 //! [INITIAL_BOARD_DIMENSIONS]: constant.INITIAL_BOARD_DIMENSIONS.html
 //! [INITIAL_WINDOW_SIZE]: constant.INITIAL_WINDOW_SIZE.html
+//! [MAX_BOARD_DIMENSION]: constant.MAX_BOARD_DIMENSION.html
+//! [PALETTE]: constant.PALETTE.html
+//! [PICTURE_BACKGROUND]: constant.PICTURE_BACKGROUND.html
+//! [PICTURE_FILE_PREFIX]: constant.PICTURE_FILE_PREFIX.html
+//! [PICTURE_SCALES]: constant.PICTURE_SCALES.html
+//! [Ruleset]: enum.Ruleset.html
+//! [SAVE_FILE]: constant.SAVE_FILE.html
+//! [REPLAY_SPEEDS]: constant.REPLAY_SPEEDS.html
+//! [STATS_FILE]: constant.STATS_FILE.html
+//! [STICK_DEADZONE]: constant.STICK_DEADZONE.html
+//! [STATS_HISTORY_LENGTH]: constant.STATS_HISTORY_LENGTH.html
+//! [STATS_HISTORY_SHOWN]: constant.STATS_HISTORY_SHOWN.html
+//! [TIME_ATTACK_PENALTY_SECONDS]: constant.TIME_ATTACK_PENALTY_SECONDS.html
 
 use serde::{Deserialize, Serialize};
 
@@ -29,7 +80,8 @@ pub enum ButtonInteraction {
     Select,
 }
 
-/// Directions. Used for keyboard controls. Self explanatory.
+/// Directions. Used for keyboard and controller controls. Self explanatory.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Directions {
     Up,
     Down,
@@ -43,12 +95,307 @@ pub enum Cell {
     /// The cell appears white. This is a cell's default state.
     Empty,
 
-    /// The cell appears black-ish.
+    /// The cell appears black-ish. In color puzzles, this is the first color of the palette.
     Filled,
 
+    /// The cell is filled with a color other than the first one of the palette. Only used in color puzzles.
+    ///
+    /// Holds an index into `PALETTE`, which is never 0.
+    Colored(u8),
+
     /// The cell has a big orange-ish X in it. These are not required to reach a win state.
     /// These exist solely to assist the user in identifying cells they know aren't going to be filled.
     Marked,
+
+    /// The cell appears as a smaller gray square. This is a tentative fill made in pencil mode while trying
+    /// out a guess. It doesn't count towards the hint numbers until it's committed.
+    Maybe,
+
+    /// Half of the cell is filled, split diagonally. Only used in triangle puzzles.
+    ///
+    /// Holds an index into `PALETTE` and the corner that the filled half covers.
+    Half(u8, Corner),
+}
+
+/// A corner of a cell. Used for half filled cells in triangle puzzles.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomRight,
+    BottomLeft,
+}
+
+impl Corner {
+    /// Whether or not a half filled cell covering this corner covers the whole edge on one side.
+    pub fn touches(self, side: Directions) -> bool {
+        matches!(
+            (self, side),
+            (Corner::TopLeft, Directions::Up)
+                | (Corner::TopLeft, Directions::Left)
+                | (Corner::TopRight, Directions::Up)
+                | (Corner::TopRight, Directions::Right)
+                | (Corner::BottomRight, Directions::Down)
+                | (Corner::BottomRight, Directions::Right)
+                | (Corner::BottomLeft, Directions::Down)
+                | (Corner::BottomLeft, Directions::Left)
+        )
+    }
+
+    /// The corner that comes after this one going clockwise.
+    pub fn next(self) -> Corner {
+        match self {
+            Corner::TopLeft => Corner::TopRight,
+            Corner::TopRight => Corner::BottomRight,
+            Corner::BottomRight => Corner::BottomLeft,
+            Corner::BottomLeft => Corner::TopLeft,
+        }
+    }
+
+    /// The triangle covering this corner of a cell, for drawing half filled cells. `rect` is the whole cell.
+    pub fn triangle(self, rect: [f64; 4]) -> [[f64; 2]; 3] {
+        let [x, y, w, h] = rect;
+        match self {
+            Corner::TopLeft => [[x, y], [x + w, y], [x, y + h]],
+            Corner::TopRight => [[x, y], [x + w, y], [x + w, y + h]],
+            Corner::BottomRight => [[x + w, y], [x + w, y + h], [x, y + h]],
+            Corner::BottomLeft => [[x, y], [x + w, y + h], [x, y + h]],
+        }
+    }
+}
+
+/// Determines which cells can be filled and how hint numbers are read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Ruleset {
+    /// Every cell is either filled or not. This is the default ruleset.
+    #[default]
+    Standard,
+
+    /// Cells can also be half filled, split diagonally. A half filled cell always starts or ends a grouping, and
+    /// hint numbers show which ends of the grouping are half filled.
+    Triangles,
+
+    /// Some pairs of neighboring columns and rows share their hint numbers, just like Mega Picross. A grouping
+    /// in a merged pair is every filled cell connected to each other within the pair, and its hint number is
+    /// merged across both lines if it covers both of them.
+    Mega,
+}
+
+impl Ruleset {
+    /// Name of the ruleset as shown to the user.
+    pub fn name(self) -> &'static str {
+        match self {
+            Ruleset::Standard => "STANDARD",
+            Ruleset::Triangles => "TRIANGLES",
+            Ruleset::Mega => "MEGA",
+        }
+    }
+
+    /// The ruleset that comes after this one when cycling through them.
+    pub fn next(self) -> Ruleset {
+        match self {
+            Ruleset::Standard => Ruleset::Triangles,
+            Ruleset::Triangles => Ruleset::Mega,
+            Ruleset::Mega => Ruleset::Standard,
+        }
+    }
+}
+
+/// Determines how the timer behaves and how the game is lost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum GameMode {
+    /// The timer counts up and the game can't be lost. This is the default mode.
+    #[default]
+    Classic,
+
+    /// The timer counts down from a limit based on the board dimensions. If it hits zero, the game is lost.
+    Countdown,
+
+    /// The timer counts up, and filling a cell that isn't filled in the solution marks it instead and adds a
+    /// time penalty, just like in Picross.
+    TimeAttack,
+}
+
+impl GameMode {
+    /// Name of the game mode as shown to the user.
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Classic => "CLASSIC",
+            GameMode::Countdown => "COUNTDOWN",
+            GameMode::TimeAttack => "TIME ATTACK",
+        }
+    }
+
+    /// The game mode that comes after this one when cycling through them.
+    pub fn next(self) -> GameMode {
+        match self {
+            GameMode::Classic => GameMode::Countdown,
+            GameMode::Countdown => GameMode::TimeAttack,
+            GameMode::TimeAttack => GameMode::Classic,
+        }
+    }
+}
+
+impl Cell {
+    /// Palette index of a filled cell. `None` if the cell isn't filled.
+    pub fn color(self) -> Option<usize> {
+        match self {
+            Cell::Filled => Some(0),
+            Cell::Colored(index) | Cell::Half(index, _) => Some(index as usize),
+            _ => None,
+        }
+    }
+
+    /// Whether or not the filled part of the cell covers its whole edge on one side.
+    ///
+    /// Two neighboring filled cells of the same color are part of the same grouping only if they both cover the
+    /// edge they share. This is what lets every ruleset share the same hint number logic.
+    pub fn connects(self, side: Directions) -> bool {
+        match self {
+            Cell::Half(_, corner) => corner.touches(side),
+            _ => self.color().is_some(),
+        }
+    }
+
+    /// A cell filled with a palette index.
+    pub fn from_color(index: usize) -> Cell {
+        if index == 0 {
+            Cell::Filled
+        } else {
+            Cell::Colored(index as u8)
+        }
+    }
+}
+
+/// Determines what filling or marking a cell that isn't empty does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum EditPolicy {
+    /// Filling or marking any cell that isn't empty clears it. This is how the game has always worked.
+    #[default]
+    Toggle,
+
+    /// Filling or marking a cell replaces whatever is in it. Only filling a filled cell or marking a marked cell
+    /// clears it.
+    Overwrite,
+}
+
+impl EditPolicy {
+    /// Name of the edit policy as shown to the user.
+    pub fn name(self) -> &'static str {
+        match self {
+            EditPolicy::Toggle => "TOGGLE",
+            EditPolicy::Overwrite => "OVERWRITE",
+        }
+    }
+
+    /// The edit policy that comes after this one when switching between them.
+    pub fn next(self) -> EditPolicy {
+        match self {
+            EditPolicy::Toggle => EditPolicy::Overwrite,
+            EditPolicy::Overwrite => EditPolicy::Toggle,
+        }
+    }
+
+    /// What a cell turns into when the player tries to change it to `requested`.
+    pub fn apply(self, current: Cell, requested: Cell) -> Cell {
+        match self {
+            EditPolicy::Toggle if current != Cell::Empty => Cell::Empty,
+            EditPolicy::Overwrite if current == requested => Cell::Empty,
+            _ => requested,
+        }
+    }
+}
+
+/// Determines what dragging the mouse across the board does.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Tool {
+    /// Every cell the mouse cursor passes over is filled or marked. This is the default tool.
+    #[default]
+    Pen,
+
+    /// Every cell on the row or column segment between where the drag started and ended is filled or marked
+    /// once the mouse button is released.
+    Line,
+
+    /// Every cell in the rectangle between where the drag started and ended is filled or marked once the mouse
+    /// button is released.
+    Rectangle,
+}
+
+impl Tool {
+    /// Name of the tool as shown to the user.
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Pen => "PEN",
+            Tool::Line => "LINE",
+            Tool::Rectangle => "RECTANGLE",
+        }
+    }
+
+    /// The tool that comes after this one when cycling through them.
+    pub fn next(self) -> Tool {
+        match self {
+            Tool::Pen => Tool::Line,
+            Tool::Line => Tool::Rectangle,
+            Tool::Rectangle => Tool::Pen,
+        }
+    }
+}
+
+/// Colors that cells can be filled with in color puzzles. The first color is the usual black-ish fill, so a black
+/// and white puzzle is just a color puzzle with a palette of one color.
+pub const PALETTE: [&str; 5] = ["353235", "d64541", "3a7bd5", "3fa34d", "e8b21c"];
+
+/// Flag on a hint number in a triangle puzzle meaning its grouping starts with a half filled cell. Columns start
+/// at the top, and rows start on the left.
+pub const CLUE_HALF_START: u8 = 1;
+
+/// Flag on a hint number in a triangle puzzle meaning its grouping ends with a half filled cell.
+pub const CLUE_HALF_END: u8 = 2;
+
+/// Flag on a hint number in a mega puzzle meaning its grouping covers both lines of a merged pair.
+pub const CLUE_MEGA: u8 = 4;
+
+/// Flag on a hint number in a mega puzzle meaning its grouping only covers the second line of a merged pair.
+/// Hint numbers without this flag or `CLUE_MEGA` only cover the first line.
+pub const CLUE_SECOND: u8 = 8;
+
+/// Distance in pixels between neighbouring hint numbers of the same column or row.
+pub const HINT_SPACING: f64 = 20.0;
+
+/// Baseline of a column hint number, `num_pos` places up from the one nearest the board. Like it always has been,
+/// it's measured from the board's x position.
+pub fn column_hint_y(board_pos: [f64; 2], num_pos: usize) -> f64 {
+    board_pos[0] - num_pos as f64 * HINT_SPACING - 80.0
+}
+
+/// Left edge of a row hint number, `num_pos` places left of the one nearest the board.
+pub fn row_hint_x(board_pos: [f64; 2], num_pos: usize) -> f64 {
+    board_pos[0] - num_pos as f64 * HINT_SPACING - 25.0
+}
+
+/// Which column hint number is at height `y`, counting up from the one nearest the board. `None` if `y` is below
+/// every column hint number.
+pub fn column_hint_at(board_pos: [f64; 2], y: f64) -> Option<usize> {
+    // Hint numbers hang a few pixels below their baseline.
+    let bottom = column_hint_y(board_pos, 0) + 5.0;
+    if y > bottom {
+        None
+    } else {
+        Some(((bottom - y) / HINT_SPACING) as usize)
+    }
+}
+
+/// Which row hint number is at `x`, counting left from the one nearest the board. `None` if `x` is right of every
+/// row hint number.
+pub fn row_hint_at(board_pos: [f64; 2], x: f64) -> Option<usize> {
+    // Hint numbers are up to two digits wide.
+    let right = row_hint_x(board_pos, 0) + 18.0;
+    if x > right {
+        None
+    } else {
+        Some(((right - x) / HINT_SPACING) as usize)
+    }
 }
 
 /// Determines the columns and rows in a fresh run of the program.
@@ -66,7 +413,9 @@ pub const BOARD_SIZE: f64 = 1200.0;
 
 /// The options that will show up when the user clicks on the dropdown menu for selecting the board
 /// dimensions. This can be manipulated, and the game will dynamically take the adjustment into account.
-pub const DIMENSIONS_CHOICES: [[usize; 2]; 11] = [
+///
+/// Dimensions bigger than `MAX_BOARD_DIMENSION` are split into panels, see `CLIP_PANEL_SIZE`.
+pub const DIMENSIONS_CHOICES: [[usize; 2]; 13] = [
     [5, 5],
     [10, 5],
     [10, 10],
@@ -78,8 +427,102 @@ pub const DIMENSIONS_CHOICES: [[usize; 2]; 11] = [
     [25, 25],
     [30, 25],
     [30, 30],
+    [45, 30],
+    [60, 40],
 ];
 
+/// The most columns or rows a single board can have. Anything bigger is a clip, which is split into panels that are
+/// solved one at a time.
+pub const MAX_BOARD_DIMENSION: usize = 30;
+
+/// The columns and rows of every panel of a clip. Panels on the right and bottom edges of the picture can be
+/// smaller.
+pub const CLIP_PANEL_SIZE: usize = 15;
+
+/// The file that the board being solved is saved to.
+pub const SAVE_FILE: &str = "savedata.json";
+
+/// The file that the panels of an unfinished clip are saved to. The panel being solved is in savedata.json
+/// instead.
+pub const CLIP_FILE: &str = "clipdata.json";
+
+/// Seconds given per cell of the board in countdown mode. A 5x5 board gets 150 seconds.
+pub const COUNTDOWN_SECONDS_PER_CELL: u64 = 6;
+
+/// Seconds added to the timer for every wrong fill in time attack mode.
+pub const TIME_ATTACK_PENALTY_SECONDS: u64 = 30;
+
+/// Width and height in pixels of every cell of a picture saved from the win screen, to choose from. Starts at 20.
+pub const PICTURE_SCALES: [u32; 5] = [5, 10, 20, 40, 80];
+
+/// Background color of pictures saved from the win screen, the same as the board's.
+pub const PICTURE_BACKGROUND: &str = "f7f5f6";
+
+/// Start of the names of pictures saved from the win screen. They're numbered so older pictures aren't
+/// overwritten, like picture-1.png and picture-1.svg.
+pub const PICTURE_FILE_PREFIX: &str = "picture";
+
+/// File that keybindings are loaded from and saved to.
+pub const CONTROLS_FILE: &str = "controls.json";
+
+/// Distance between the top of the controls screen and its first row.
+pub const CONTROLS_ROW_OFFSET: f64 = 50.0;
+
+/// Height of a single row on the controls screen. Each row lists the keybindings of one action.
+pub const CONTROLS_ROW_HEIGHT: f64 = 36.0;
+
+/// Most rows shown on the controls screen at once. The rest are reached by scrolling.
+pub const CONTROLS_ROWS_SHOWN: usize = 16;
+
+/// How far a controller stick needs to be tilted before it counts as a movement. Stick positions range from
+/// -1.0 to 1.0.
+pub const STICK_DEADZONE: f64 = 0.5;
+
+/// Controller axis of the left stick's horizontal movement.
+pub const STICK_X_AXIS: u8 = 0;
+
+/// Controller axis of the left stick's vertical movement.
+pub const STICK_Y_AXIS: u8 = 1;
+
+/// Controller button number of the A button on a standard (Xbox style) game controller, as reported by SDL.
+pub const PAD_A: u8 = 0;
+
+/// Controller button number of the B button.
+pub const PAD_B: u8 = 1;
+
+/// Controller button number of the Y button.
+pub const PAD_Y: u8 = 3;
+
+/// Controller button number of the back/select button.
+pub const PAD_BACK: u8 = 4;
+
+/// Controller button number of the start button.
+pub const PAD_START: u8 = 6;
+
+/// Controller button number of up on the d-pad.
+pub const PAD_DPAD_UP: u8 = 11;
+
+/// Controller button number of down on the d-pad.
+pub const PAD_DPAD_DOWN: u8 = 12;
+
+/// Controller button number of left on the d-pad.
+pub const PAD_DPAD_LEFT: u8 = 13;
+
+/// Controller button number of right on the d-pad.
+pub const PAD_DPAD_RIGHT: u8 = 14;
+
+/// Speeds a replay can be played back at, from slowest to fastest. 1.0 is as fast as it was recorded.
+pub const REPLAY_SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+/// File that statistics and personal bests are saved to in between sessions.
+pub const STATS_FILE: &str = "statsdata.json";
+
+/// The maximum number of games kept in the stats history. Older games are dropped first.
+pub const STATS_HISTORY_LENGTH: usize = 50;
+
+/// The number of most recent games listed on the stats screen.
+pub const STATS_HISTORY_SHOWN: usize = 8;
+
 /// Part 1 of random string generation for the win screen. Nonogram puzzles usually result in an image of
 /// something being produced. Due to the random generation I use to produce my puzzles, it's a miracle if
 /// anything's actually produced in the end. This is just a joke.
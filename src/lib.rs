@@ -0,0 +1,52 @@
+//! The game logic of the nonogram game, without any window or graphics.
+//!
+//! [NonogramBoard] holds a board along with its goal state and hint numbers, and [SavedBoard] is what gets written
+//! to savedata.json. [NonogramClip] splits pictures too big for a single board into panels, [NonogramStats] keeps
+//! track of every game played, and [nonogram_solver] solves black and white puzzles from their hint numbers alone.
+//! Boards and clips are saved to a [Storage], which is either a directory or, for tests, memory. None of these
+//! depend on Piston.
+//!
+//! Everything a player can do is an [Action]. The controller, which applies actions to the board and turns Piston
+//! input events into actions, is only included with the `input` feature. Every front end turns on that feature.
+//! A [Replay] records every action taken during a session, so it can be played back later, and scripts of synthetic
+//! input events in [nonogram_script] test the controller the same way the window uses it. A solved board can be
+//! saved as a [Picture].
+//!
+//! [NonogramBoard]: nonogram_board/struct.NonogramBoard.html
+//! [SavedBoard]: nonogram_board/struct.SavedBoard.html
+//! [NonogramClip]: nonogram_clip/struct.NonogramClip.html
+//! [NonogramStats]: nonogram_stats/struct.NonogramStats.html
+//! [nonogram_solver]: nonogram_solver/index.html
+//! [Storage]: nonogram_storage/trait.Storage.html
+//! [Action]: nonogram_action/enum.Action.html
+//! [Replay]: nonogram_replay/struct.Replay.html
+//! [nonogram_script]: nonogram_script/index.html
+//! [Picture]: nonogram_picture/struct.Picture.html
+
+pub mod common;
+pub mod nonogram_action;
+pub mod nonogram_board;
+pub mod nonogram_clip;
+#[cfg(feature = "input")]
+pub mod nonogram_controller;
+pub mod nonogram_picture;
+pub mod nonogram_replay;
+#[cfg(feature = "input")]
+pub mod nonogram_script;
+pub mod nonogram_solver;
+pub mod nonogram_stats;
+pub mod nonogram_storage;
+
+pub use common::{Cell, Corner, Directions, GameMode, Ruleset};
+pub use nonogram_action::Action;
+pub use nonogram_board::{NonogramBoard, SavedBoard};
+pub use nonogram_clip::NonogramClip;
+#[cfg(feature = "input")]
+pub use nonogram_controller::{NonogramController, WindowLayout};
+pub use nonogram_picture::Picture;
+pub use nonogram_replay::Replay;
+#[cfg(feature = "input")]
+pub use nonogram_replay::ReplayPlayer;
+pub use nonogram_solver::{Difficulty, Puzzle, Solutions};
+pub use nonogram_stats::NonogramStats;
+pub use nonogram_storage::{FileStorage, MemoryStorage, Storage};
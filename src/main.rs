@@ -2,24 +2,104 @@ use glutin_window::GlutinWindow;
 use graphics::color::hex;
 use opengl_graphics::{Filter, GlGraphics, GlyphCache, OpenGL, TextureSettings};
 use piston::event_loop::{EventSettings, Events};
-use piston::input::RenderEvent;
+use piston::input::{Button, Key, PressEvent, RenderEvent, UpdateEvent};
 use piston::window::WindowSettings;
-use std::time::{Duration, Instant};
+use std::env;
+use std::path::Path;
+use std::process;
 
-pub use nonogram_board::NonogramBoard;
-pub use nonogram_board_view::{NonogramView, NonogramViewSettings};
-pub use nonogram_controller::NonogramController;
+use nonogram::common::{INITIAL_BOARD_DIMENSIONS, INITIAL_WINDOW_SIZE};
+use nonogram::{FileStorage, NonogramBoard, NonogramController, Replay, ReplayPlayer};
+
+use crate::nonogram_board_view::{NonogramView, NonogramViewSettings};
 
-mod common;
-mod nonogram_board;
 mod nonogram_board_view;
-mod nonogram_controller;
+mod nonogram_offscreen;
+
+/// Usage shown when the arguments can't be read.
+const USAGE: &str = "Usage: nonogram [--record FILE | --replay FILE] [--screenshot FILE.png]";
+
+/// What was asked for on the command line.
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    /// Replay file to record every action to.
+    record: Option<String>,
 
-use crate::common::{INITIAL_BOARD_DIMENSIONS, INITIAL_WINDOW_SIZE};
+    /// Replay file to play back instead of playing the game.
+    replay: Option<String>,
+
+    /// PNG file to draw the game into instead of opening the window.
+    screenshot: Option<String>,
+}
 
 /// Does everything necessary to run the game. Creates the initial classes, window, and sits in a
 /// while loop that's constantly redrawing the contents of the window and checking for events.
+///
+/// With `--record FILE`, every action taken is written to a replay file when the game is closed. With
+/// `--replay FILE`, that replay is played back instead of playing the game. Space pauses the replay, and the up and
+/// down arrow keys change how fast it's played back.
+///
+/// With `--screenshot FILE.png`, the saved game is drawn into a PNG file without opening a window. Along with
+/// `--replay FILE`, the whole replay is played back first, which draws how it ended up.
 fn main() {
+    let Args {
+        record,
+        replay,
+        screenshot,
+    } = match parse_args(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(why) => {
+            eprintln!("{}", why);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let storage = FileStorage::default();
+
+    // Replays are played back on a controller of their own, which doesn't touch any save files.
+    let mut replay_player = replay.map(|name| {
+        let replay = Replay::load(&storage, &name).unwrap_or_else(|why| {
+            eprintln!("{}", why);
+            process::exit(1);
+        });
+        ReplayPlayer::new(replay).unwrap_or_else(|| {
+            eprintln!("{} doesn't have anything to play back.", name);
+            process::exit(1);
+        })
+    });
+    let mut nonogram_controller = match &mut replay_player {
+        Some(player) => player.controller(),
+        None => {
+            let nonogram = NonogramBoard::load(&storage)
+                .unwrap_or_else(|why| panic!("{}", why))
+                .unwrap_or_else(|| NonogramBoard::new(INITIAL_BOARD_DIMENSIONS));
+            NonogramController::new(nonogram, Box::new(storage))
+        }
+    };
+    if let Some(name) = &record {
+        nonogram_controller.start_recording(name);
+    }
+
+    // Everything necessary for the variants fonts to work.
+    let assets = find_folder::Search::ParentsThenKids(3, 3)
+        .for_folder("assets")
+        .unwrap();
+
+    if let Some(path) = screenshot {
+        if let Some(player) = &mut replay_player {
+            player.finish(&mut nonogram_controller);
+        }
+        let result =
+            nonogram_offscreen::screenshot(&nonogram_controller, &assets, INITIAL_WINDOW_SIZE)
+                .and_then(|g| g.save(Path::new(&path)));
+        if let Err(why) = result {
+            eprintln!("{}", why);
+            process::exit(1);
+        }
+        return;
+    }
+
     let opengl = OpenGL::V3_2;
     let settings = WindowSettings::new("Nonogram", INITIAL_WINDOW_SIZE)
         .graphics_api(opengl)
@@ -27,16 +107,10 @@ fn main() {
     let mut window: GlutinWindow = settings.build().expect("Could not create window");
     let mut events = Events::new(EventSettings::new());
     let mut gl = GlGraphics::new(opengl);
-    let nonogram = NonogramBoard::new(INITIAL_BOARD_DIMENSIONS, false);
-    let mut nonogram_controller = NonogramController::new(nonogram);
     let mut nonogram_view_settings =
         NonogramViewSettings::new(nonogram_controller.nonogram.dimensions);
     let mut nonogram_view = NonogramView::new(nonogram_view_settings);
 
-    // Everything necessary for the variants fonts to work.
-    let assets = find_folder::Search::ParentsThenKids(3, 3)
-        .for_folder("assets")
-        .unwrap();
     let texture_settings = TextureSettings::new().filter(Filter::Nearest);
     let font = &assets.join("FiraSans-Regular.ttf");
     let glyphs = &mut GlyphCache::new(font, (), texture_settings)
@@ -51,26 +125,24 @@ fn main() {
     println!("Nonogram game started.");
 
     while let Some(e) = events.next(&mut window) {
-        nonogram_controller.event(
-            nonogram_view.settings.position,
-            nonogram_view.settings.board_dimensions,
-            nonogram_view.settings.dimensions_dropdown_menu_box,
-            nonogram_view.settings.restart_box,
-            nonogram_view.settings.new_game_box,
-            &e,
-        );
+        if let Some(player) = &mut replay_player {
+            if let Some(args) = e.update_args() {
+                player.advance(args.dt, &mut nonogram_controller);
+            }
+            match e.press_args() {
+                Some(Button::Keyboard(Key::Space)) => player.paused = !player.paused,
+                Some(Button::Keyboard(Key::Up)) => player.faster(),
+                Some(Button::Keyboard(Key::Down)) => player.slower(),
+                _ => (),
+            }
+        } else {
+            nonogram_controller.event(&nonogram_view.settings.layout, &e);
+        }
         if let Some(args) = e.render_args() {
             gl.draw(args.viewport(), |c, g| {
                 use graphics::clear;
                 if !nonogram_controller.nonogram.end_game_screen {
-                    nonogram_controller.nonogram.duration =
-                        match nonogram_controller.nonogram.game_start {
-                            Some(game_start) => match nonogram_controller.nonogram.game_end {
-                                Some(game_end) => game_end - game_start,
-                                None => Instant::now() - game_start,
-                            },
-                            None => Duration::from_secs(0),
-                        };
+                    nonogram_controller.nonogram.update_duration();
                 }
                 clear(hex("222222"), g);
                 nonogram_view.draw(
@@ -81,22 +153,67 @@ fn main() {
                     &c,
                     g,
                 );
+                if let Some(player) = &replay_player {
+                    nonogram_view.draw_replay(player, glyphs, &c, g);
+                }
             });
         }
 
         // The board is reset when pressing the key bound to "restart", clicking the "restart" button
         // while a board is loaded, or when clicking the "new game" button when on the win screen.
         //
-        // Resetting the board causes it to wipe the current state, potentially create a new board with different
-        // dimensions than the current board depending on the user's choice, and generate a new goal state.
-        if nonogram_controller.nonogram.reset_board {
-            nonogram_controller.nonogram = nonogram_board::NonogramBoard::new(
-                nonogram_controller.nonogram.next_dimensions,
-                true,
-            );
+        // Replays start every new board from the board that was recorded instead.
+        if nonogram_controller.nonogram.reset_board && replay_player.is_none() {
+            nonogram_controller.start_next_board();
+            nonogram_view_settings =
+                NonogramViewSettings::new(nonogram_controller.nonogram.dimensions);
+            nonogram_view = NonogramView::new(nonogram_view_settings);
+        }
+
+        // Pictures are drawn offscreen when saved from the win screen, the same way as screenshots. Replays don't
+        // save anything.
+        if nonogram_controller.save_picture {
+            nonogram_controller.save_picture = false;
+            if replay_player.is_none() {
+                let picture = nonogram_controller.picture_to_save();
+                let message = nonogram_offscreen::save_picture(&picture, &assets, Path::new("."))
+                    .unwrap_or_else(|why| why);
+                println!("{}", message);
+                nonogram_controller.picture_message = Some(message);
+            }
+        }
+
+        // Panels of a clip can have different dimensions, so the view has to follow along when switching panels.
+        if nonogram_view.settings.cell_dimensions != nonogram_controller.nonogram.dimensions {
             nonogram_view_settings =
                 NonogramViewSettings::new(nonogram_controller.nonogram.dimensions);
             nonogram_view = NonogramView::new(nonogram_view_settings);
         }
     }
 }
+
+/// Read the command line arguments.
+fn parse_args(args: Vec<String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing a value after {}", arg))?;
+        match arg.as_str() {
+            "--record" => parsed.record = Some(value),
+            "--replay" => parsed.replay = Some(value),
+            "--screenshot" => parsed.screenshot = Some(value),
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    if parsed.record.is_some() && parsed.replay.is_some() {
+        return Err("Replays can't be recorded while playing one back.".to_string());
+    }
+    if parsed.record.is_some() && parsed.screenshot.is_some() {
+        return Err("Nothing can be recorded while taking a screenshot.".to_string());
+    }
+    Ok(parsed)
+}
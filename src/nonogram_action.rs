@@ -0,0 +1,152 @@
+//! Responsible for the actions a player can take, no matter where their input comes from.
+//!
+//! Front ends turn their input into actions, and the controller applies them with `NonogramController::apply`.
+//! The window turns Piston events into actions, but a script or a test can build them by hand just as well.
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::Directions;
+
+/// Everything a player can do to the game.
+///
+/// Cells are given as `[which_column, which_row]`, just like the indices of `NonogramBoard::data`. Actions that
+/// don't make sense at the moment, like filling a cell while the game is paused, are ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    /// Start filling cells with the mouse, beginning with this one. Every cell hovered over afterwards is filled
+    /// too, until the drag is ended.
+    StartFill([usize; 2]),
+
+    /// Start marking cells with the mouse, beginning with this one. Every cell hovered over afterwards is marked
+    /// too, until the drag is ended.
+    StartMark([usize; 2]),
+
+    /// The mouse cursor moved over a cell, or off the board. Hovering over a cell selects it, and continues any
+    /// drag in progress.
+    Hover(Option<[usize; 2]>),
+
+    /// Finish the drag in progress. The line and rectangle tools fill or mark their cells now.
+    EndDrag,
+
+    /// Drop the drag in progress without finishing it.
+    CancelDrag,
+
+    /// Start or stop holding down the keybinding for filling the selected cell. Moving while it's held down fills
+    /// every cell moved onto.
+    HoldFill(bool),
+
+    /// Start or stop holding down the keybinding for marking the selected cell. Moving while it's held down marks
+    /// every cell moved onto.
+    HoldMark(bool),
+
+    /// Start moving the selected cell. It keeps moving on its own after a while, until it's stopped.
+    Move(Directions),
+
+    /// Stop moving the selected cell in a direction.
+    StopMove(Directions),
+
+    /// Start or stop holding down the keybinding that makes moving jump to an edge of the board.
+    HoldJump(bool),
+
+    /// Start or stop holding down the keybinding that makes moving skip to the next unsolved row or column.
+    HoldSkip(bool),
+
+    /// Move the selected cell once, just like a held down movement repeating.
+    Step(Directions),
+
+    /// Time has passed. Keeps a held down movement going by stepping it once it's been held down long enough.
+    /// Ticks aren't recorded in replays, only the steps they cause.
+    Tick,
+
+    /// Stop selecting any cell.
+    Deselect,
+
+    /// Cross out a hint number by hand, or bring it back. Given as `[column_or_row, which_column_or_row,
+    /// which_number]`, just like the indices of `goal_nums`.
+    CrossOut([usize; 3]),
+
+    /// Turn every tentative fill into a real fill.
+    CommitPencil,
+
+    /// Clear every tentative fill.
+    DiscardPencil,
+
+    /// Cycle through the tools used for dragging the mouse across the board.
+    NextTool,
+
+    /// Turn pencil mode on or off.
+    TogglePencil,
+
+    /// Fill cells with a palette color.
+    SelectColor(usize),
+
+    /// Cycle through the palette colors of a color puzzle.
+    NextColor,
+
+    /// Cycle through the shapes that cells are filled with in a triangle puzzle.
+    NextShape,
+
+    /// Set the dimensions of the next board to be generated. Only dimensions from `DIMENSIONS_CHOICES` are used.
+    SetDimensions([usize; 2]),
+
+    /// Increase the dimensions of the next board to be generated.
+    BiggerBoard,
+
+    /// Decrease the dimensions of the next board to be generated.
+    SmallerBoard,
+
+    /// Cycle through the game modes of the next board to be generated.
+    NextMode,
+
+    /// Cycle through the number of palette colors of the next board to be generated.
+    NextPalette,
+
+    /// Cycle through the rulesets of the next board to be generated.
+    NextRuleset,
+
+    /// Generate a new board and start from scratch.
+    Restart,
+
+    /// Move on to the next unsolved panel of a clip.
+    NextPanel,
+
+    /// Switch to a panel of a clip that hasn't been solved yet.
+    SwitchPanel(usize),
+
+    /// Pause the game if it's running, or resume it if it's paused.
+    TogglePause,
+
+    /// Pause the game.
+    Pause,
+
+    /// Resume the game.
+    Resume,
+
+    /// Go to the stats screen.
+    ShowStats,
+
+    /// Go to the controls screen.
+    ShowControls,
+
+    /// Go back to the board from the stats or controls screen.
+    CloseMenu,
+
+    /// Switch to the next edit policy, which decides what filling or marking a cell that isn't empty does. Only works
+    /// on the controls screen.
+    NextEditPolicy,
+
+    /// Save the game, so it can be picked back up in the next session.
+    Save,
+
+    /// Save the solved picture as an image. Only works on the win screen.
+    SavePicture,
+
+    /// Save pictures with bigger cells, up to the biggest size in `PICTURE_SCALES`.
+    BiggerPicture,
+
+    /// Save pictures with smaller cells, down to the smallest size in `PICTURE_SCALES`.
+    SmallerPicture,
+
+    /// Switch whether or not pictures are saved with their hint numbers.
+    TogglePictureClues,
+}
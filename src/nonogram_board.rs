@@ -1,14 +1,31 @@
 //! Responsible for everything that isn't input or graphics.
 
 use rand::distributions::{Bernoulli, Distribution};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::fs;
 use std::time::{Duration, Instant};
 
-use crate::common::{Cell, Directions};
+use crate::common::{
+    Cell, Corner, Directions, GameMode, Ruleset, CLUE_HALF_END, CLUE_HALF_START, CLUE_MEGA,
+    CLUE_SECOND, COUNTDOWN_SECONDS_PER_CELL, PALETTE, SAVE_FILE, TIME_ATTACK_PENALTY_SECONDS,
+};
+use crate::nonogram_picture::PictureClues;
+use crate::nonogram_storage::Storage;
+
+/// Every part of the hint numbers of a board, laid out just like `goal_nums`.
+struct Clues {
+    /// Length of every grouping.
+    nums: Vec<Vec<Vec<i8>>>,
+
+    /// Palette index of every grouping.
+    colors: Vec<Vec<Vec<u8>>>,
+
+    /// Flags describing the shape of every grouping. See the `CLUE_*` constants.
+    ends: Vec<Vec<Vec<u8>>>,
+}
 
 /// Contains the information we're going to save in between each session.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SavedBoard {
     pub dimensions: [usize; 2],
     pub next_dimensions: [usize; 2],
@@ -18,6 +35,32 @@ pub struct SavedBoard {
     pub goal_black: u64,
     pub duration: Duration,
     pub end_game_screen: bool,
+    #[serde(default)]
+    pub goal_data: Vec<Vec<Cell>>,
+    #[serde(default)]
+    pub mode: GameMode,
+    #[serde(default)]
+    pub next_mode: GameMode,
+    #[serde(default)]
+    pub penalty_duration: Duration,
+    #[serde(default)]
+    pub time_up: bool,
+    #[serde(default)]
+    pub manual_crossouts: Vec<Vec<Vec<bool>>>,
+    #[serde(default)]
+    pub colors: usize,
+    #[serde(default)]
+    pub next_colors: usize,
+    #[serde(default)]
+    pub goal_colors: Vec<Vec<Vec<u8>>>,
+    #[serde(default)]
+    pub ruleset: Ruleset,
+    #[serde(default)]
+    pub next_ruleset: Ruleset,
+    #[serde(default)]
+    pub goal_ends: Vec<Vec<Vec<u8>>>,
+    #[serde(default)]
+    pub mega_lines: Vec<Vec<bool>>,
 }
 
 /// Contains all logic pertaining to the nonogram board.
@@ -33,27 +76,79 @@ pub struct NonogramBoard {
     /// A cell can be empty, filled, or marked.
     pub data: Vec<Vec<Cell>>,
 
+    /// Contains the randomly generated solution that the goal hint numbers were made from.
+    ///
+    /// The win condition doesn't use this, because a board might have more than one solution. It's only
+    /// used to catch wrong fills in time attack mode.
+    pub goal_data: Vec<Vec<Cell>>,
+
+    /// Current game mode.
+    pub mode: GameMode,
+
+    /// Game mode of the next board.
+    pub next_mode: GameMode,
+
+    /// Number of palette colors used by the current board. 1 for a black and white puzzle.
+    pub colors: usize,
+
+    /// Number of palette colors used by the next board.
+    pub next_colors: usize,
+
+    /// Ruleset of the current board.
+    pub ruleset: Ruleset,
+
+    /// Ruleset of the next board.
+    pub next_ruleset: Ruleset,
+
     /// The maximum hint numbers for the columns and rows depending on the board dimensions.
     ///
     /// This is equal to the number of cells in that column or row divided by 2 and rounded up.
     ///
     /// Example: The maximum hint numbers in a column of 5 cells is 3.
     ///
+    /// In color, triangle, and mega puzzles, groupings don't always need a gap between them, so every cell can be
+    /// its own hint number.
+    ///
     /// This is calculated on initialization like this:
-    /// ```
+    /// ```text
     /// self.nums_per[0] = (self.dimensions[1] as f64 / 2.0_f64).round() as u64;
     /// self.nums_per[1] = (self.dimensions[0] as f64 / 2.0_f64).round() as u64;
     /// ```
     pub nums_per: [u64; 2],
 
     /// The goal hint numbers. If a value is negative, it's crossed out.
-    /// ```
+    /// ```text
     /// goal_nums[column_or_row][which_column_or_row][which_number];
     /// goal_nums[0][1][2] => Third hint number in second column
     /// goal_nums[1][2][3] => Fourth hint number in third row
     /// ```
     pub goal_nums: Vec<Vec<Vec<i8>>>,
 
+    /// Palette index of each goal hint number, laid out just like `goal_nums`.
+    pub goal_colors: Vec<Vec<Vec<u8>>>,
+
+    /// Palette index of each current hint number, laid out just like `current_nums`.
+    pub current_colors: Vec<Vec<Vec<u8>>>,
+
+    /// Flags describing the shape of each goal hint number's grouping, laid out just like `goal_nums`. See the
+    /// `CLUE_*` constants. Always 0 in standard puzzles.
+    pub goal_ends: Vec<Vec<Vec<u8>>>,
+
+    /// Flags describing the shape of each current hint number's grouping, laid out just like `current_nums`.
+    pub current_ends: Vec<Vec<Vec<u8>>>,
+
+    /// Which columns and rows share their hint numbers with the next one in a mega puzzle.
+    /// ```text
+    /// mega_lines[column_or_row][which_column_or_row];
+    /// mega_lines[0][2] => Third and fourth columns are merged
+    /// ```
+    /// The hint numbers of a merged pair are all stored in the first line, and the second line has none.
+    pub mega_lines: Vec<Vec<bool>>,
+
+    /// Hint numbers the player has crossed out by hand, laid out just like `goal_nums`. These are kept separate
+    /// from the negative values in `goal_nums`, so `update_crossouts` never undoes them.
+    pub manual_crossouts: Vec<Vec<Vec<bool>>>,
+
     /// The current hint numbers.
     ///
     /// Because we don't generate unique goal states, this is what we compare with the goal_nums in order
@@ -69,6 +164,22 @@ pub struct NonogramBoard {
     /// Time that the most recent game ended at.
     pub game_end: Option<Instant>,
 
+    /// Time that the current pause started at. `None` if the game isn't paused.
+    pub pause_start: Option<Instant>,
+
+    /// Total amount of time the current game has spent paused, not counting the current pause.
+    pub paused_duration: Duration,
+
+    /// Stands in for the current time when set, so the timer only moves when it's changed. Replays set it to keep
+    /// the timer in step with the replay, no matter how fast it's played back. `None` uses the real time.
+    pub clock: Option<Instant>,
+
+    /// Total amount of time added to the timer by wrong fills in time attack mode.
+    pub penalty_duration: Duration,
+
+    /// True if the countdown ran out before the board was solved.
+    pub time_up: bool,
+
     /// True if we're at the end game screen.
     /// False if we're not at the end game screen.
     pub end_game_screen: bool,
@@ -94,114 +205,335 @@ pub struct NonogramBoard {
 
 /// NonogramBoard functionality.
 impl NonogramBoard {
-    pub fn new(next_dimensions: [usize; 2], reset_board: bool) -> NonogramBoard {
-        let mut board = NonogramBoard {
+    /// Create a brand-new board with a randomly generated goal state. Save data is never read here, see `load`.
+    pub fn new(next_dimensions: [usize; 2]) -> NonogramBoard {
+        NonogramBoard::with_variant(next_dimensions, 1, Ruleset::Standard)
+    }
+
+    /// Create a brand-new board with some number of palette colors and a ruleset, generating its goal state just
+    /// once.
+    pub fn with_variant(
+        next_dimensions: [usize; 2],
+        colors: usize,
+        ruleset: Ruleset,
+    ) -> NonogramBoard {
+        let mut board = NonogramBoard::empty(next_dimensions, colors, ruleset);
+        board.generate();
+        board
+    }
+
+    /// Create a board with no goal state at all. Nothing is generated, so it has to be generated or restored into
+    /// before it can be played.
+    pub fn empty(next_dimensions: [usize; 2], colors: usize, ruleset: Ruleset) -> NonogramBoard {
+        let colors = colors.clamp(1, PALETTE.len());
+        NonogramBoard {
             dimensions: next_dimensions,
             next_dimensions,
             data: vec![vec![]],
+            goal_data: vec![],
+            mode: GameMode::Classic,
+            next_mode: GameMode::Classic,
+            colors,
+            next_colors: colors,
             nums_per: [0; 2],
             goal_nums: vec![vec![vec![]]],
+            goal_colors: vec![],
+            current_colors: vec![],
+            goal_ends: vec![],
+            current_ends: vec![],
+            mega_lines: vec![],
+            ruleset,
+            next_ruleset: ruleset,
+            manual_crossouts: vec![],
             current_nums: vec![vec![vec![]]],
             game_start: None,
             game_end: None,
+            pause_start: None,
+            paused_duration: Duration::from_secs(0),
+            clock: None,
+            penalty_duration: Duration::from_secs(0),
+            time_up: false,
             end_game_screen: false,
             duration: Duration::from_secs(0),
-            reset_board,
+            reset_board: false,
             last_time: None,
             count_black: 0,
             goal_black: 0,
             init_ratio: 0.5,
             selected_cell: None,
+        }
+    }
+
+    /// Load the board saved by `save`. Returns `None` if no board has been saved, and an error if the save data
+    /// can't be read.
+    pub fn load<S: Storage + ?Sized>(storage: &S) -> Result<Option<NonogramBoard>, String> {
+        let save_data = match storage.read(SAVE_FILE) {
+            Some(save_data) => save_data,
+            None => return Ok(None),
         };
-        board.init_new();
+        let v: SavedBoard = serde_json::from_str(&save_data)
+            .map_err(|_| format!("Your {} file is incompatible. Delete it.", SAVE_FILE))?;
+        Ok(Some(NonogramBoard::from_saved(v)))
+    }
+
+    /// Save this board, so it can be picked back up by `load` in the next session.
+    pub fn save<S: Storage + ?Sized>(&self, storage: &mut S) -> Result<(), String> {
+        let save_data = serde_json::to_string_pretty(&self.saved())
+            .map_err(|why| format!("Couldn't write {}: {}", SAVE_FILE, why))?;
+        storage.write(SAVE_FILE, &save_data)
+    }
+
+    /// Create a board from save data.
+    pub fn from_saved(v: SavedBoard) -> NonogramBoard {
+        let mut board = NonogramBoard::empty(v.dimensions, 1, Ruleset::Standard);
+        board.restore(v);
         board
     }
 
-    /// Initialize values that cannot be initialized in the constructor.
-    /// This function is called within the constructor.
-    fn init_new(&mut self) {
-        self.data.clear();
-        self.goal_nums.clear();
-        self.current_nums.clear();
+    /// Replace the state of this board with save data.
+    fn restore(&mut self, v: SavedBoard) {
+        self.dimensions = v.dimensions;
+        self.next_dimensions = v.next_dimensions;
+        self.data = v.data;
+        self.goal_nums = v.goal_nums;
+        self.count_black = v.count_black;
+        self.goal_black = v.goal_black;
+        self.duration = v.duration;
+        self.goal_data = v.goal_data;
+        self.mode = v.mode;
+        self.next_mode = v.next_mode;
+        self.penalty_duration = v.penalty_duration;
+        self.time_up = v.time_up;
+        self.manual_crossouts = v.manual_crossouts;
+        self.colors = v.colors.max(1);
+        self.next_colors = v.next_colors.max(1);
+        self.goal_colors = v.goal_colors;
+        self.ruleset = v.ruleset;
+        self.next_ruleset = v.next_ruleset;
+        self.goal_ends = v.goal_ends;
+        self.mega_lines = v.mega_lines;
+        self.update_nums_per();
+        self.game_start = Some(self.now() - self.duration.saturating_sub(self.penalty_duration));
+        self.end_game_screen = v.end_game_screen;
+        if self.end_game_screen {
+            // The timer of a finished game stays where it ended.
+            self.game_end = Some(self.now());
+        }
+        self.update_current_clues();
+        if !self.manual_crossouts_fit() {
+            self.reset_manual_crossouts();
+        }
 
-        let save_data = fs::read_to_string("savedata.json").unwrap_or_else(|_| "".to_string());
+        // Save data from before color and triangle puzzles existed only has black hint numbers with square ends.
+        if self.goal_colors.len() != self.goal_nums.len() {
+            self.goal_colors = self.empty_clues();
+        }
+        if self.goal_ends.len() != self.goal_nums.len() {
+            self.goal_ends = self.empty_clues();
+        }
+    }
 
-        // If there is no save data file or if we're generating a brand-new board.
-        if save_data.is_empty() || self.reset_board {
-            for _col in 0..self.dimensions[0] {
-                self.data.push(vec![Cell::Empty; self.dimensions[1]]);
-            }
+    /// Everything about this board that's kept in between sessions.
+    pub fn saved(&self) -> SavedBoard {
+        SavedBoard {
+            dimensions: self.dimensions,
+            next_dimensions: self.next_dimensions,
+            data: self.data.clone(),
+            goal_nums: self.goal_nums.clone(),
+            count_black: self.count_black,
+            goal_black: self.goal_black,
+            duration: self.duration,
+            end_game_screen: self.end_game_screen,
+            goal_data: self.goal_data.clone(),
+            mode: self.mode,
+            next_mode: self.next_mode,
+            penalty_duration: self.penalty_duration,
+            time_up: self.time_up,
+            manual_crossouts: self.manual_crossouts.clone(),
+            colors: self.colors,
+            next_colors: self.next_colors,
+            goal_colors: self.goal_colors.clone(),
+            ruleset: self.ruleset,
+            next_ruleset: self.next_ruleset,
+            goal_ends: self.goal_ends.clone(),
+            mega_lines: self.mega_lines.clone(),
+        }
+    }
 
-            self.nums_per[0] = (self.dimensions[1] as f64 / 2.0_f64).round() as u64;
-            self.nums_per[1] = (self.dimensions[0] as f64 / 2.0_f64).round() as u64;
+    /// Generate a brand-new board with a new goal state.
+    fn generate(&mut self) {
+        self.generate_from(&mut rand::thread_rng());
+    }
 
-            for i in 0..2 {
-                self.goal_nums
-                    .push(vec![vec![0; self.nums_per[i] as usize]; self.dimensions[i]]);
-                self.current_nums
-                    .push(vec![vec![0; self.nums_per[i] as usize]; self.dimensions[i]]);
-            }
+    /// Generate a brand-new board with a new goal state picked by `rng`. The same seed always generates the same
+    /// board. Used by the puzzle generator.
+    pub fn generate_from<R: Rng>(&mut self, rng: &mut R) {
+        self.data = vec![vec![Cell::Empty; self.dimensions[1]]; self.dimensions[0]];
+        self.count_black = 0;
+        self.goal_black = 0;
+        self.update_nums_per();
+        self.initialize_from(rng);
+    }
 
-            self.initialize();
+    /// Calculate the maximum hint numbers for the columns and rows. See `nums_per`.
+    fn update_nums_per(&mut self) {
+        if self.ruleset == Ruleset::Mega && self.colors > 1 {
+            // Both cells of a merged pair can be groupings of their own when they're different colors.
+            self.nums_per[0] = 2 * self.dimensions[1] as u64;
+            self.nums_per[1] = 2 * self.dimensions[0] as u64;
+        } else if self.colors > 1 || self.ruleset != Ruleset::Standard {
+            self.nums_per[0] = self.dimensions[1] as u64;
+            self.nums_per[1] = self.dimensions[0] as u64;
         } else {
-            let v: SavedBoard = serde_json::from_str(&save_data)
-                .expect("Your savedata.json file is incompatible. Delete it.");
-            self.dimensions = v.dimensions;
-            self.next_dimensions = v.next_dimensions;
-            self.data = v.data;
-            self.goal_nums = v.goal_nums;
-            self.count_black = v.count_black;
-            self.goal_black = v.goal_black;
-            self.duration = v.duration;
             self.nums_per[0] = (self.dimensions[1] as f64 / 2.0_f64).round() as u64;
             self.nums_per[1] = (self.dimensions[0] as f64 / 2.0_f64).round() as u64;
-            self.game_start = Some(Instant::now() - self.duration);
-            self.end_game_screen = v.end_game_screen;
-            for i in 0..2 {
-                self.current_nums
-                    .push(vec![vec![0; self.nums_per[i] as usize]; self.dimensions[i]]);
-            }
-            self.current_nums = self.get_nums();
         }
     }
 
     /// Compare the goal state to the current state of the hint numbers. Return true if the player
     /// has won. Return false if the player hasn't won.
     pub fn check_win(&self) -> bool {
-        for i in 0..2 {
-            for k in 0..self.dimensions[i] {
-                if !self.goal_nums[i][k]
-                    .iter()
-                    .zip(self.current_nums[i][k].iter())
-                    .all(|(a, b)| a.abs() == b.abs())
-                {
-                    return false;
-                }
-            }
-        }
+        (0..2).all(|i| (0..self.dimensions[i]).all(|k| self.is_line_solved(i, k)))
+    }
 
-        true
+    /// Whether or not the hint numbers of a single column (`axis` 0) or row (`axis` 1) match the goal.
+    pub fn is_line_solved(&self, axis: usize, index: usize) -> bool {
+        self.goal_nums[axis][index]
+            .iter()
+            .zip(self.current_nums[axis][index].iter())
+            .all(|(a, b)| a.abs() == b.abs())
+            && self.goal_colors[axis][index] == self.current_colors[axis][index]
+            && self.goal_ends[axis][index] == self.current_ends[axis][index]
     }
 
-    /// Set cell value.
+    /// Set cell value. Whatever was in the cell before is replaced.
+    ///
+    /// In time attack mode, filling a cell that isn't filled in the solution marks it instead.
     pub fn set(&mut self, ind: [usize; 2], val: Cell) {
-        if self.data[ind[0]][ind[1]] != Cell::Empty {
-            if self.data[ind[0]][ind[1]] == Cell::Filled && self.count_black != 0 {
-                self.count_black -= 1;
-            }
-            self.data[ind[0]][ind[1]] = Cell::Empty;
+        let current = self.data[ind[0]][ind[1]];
+        if current == val {
+            return;
+        }
+        if current.color().is_some() && self.count_black != 0 {
+            self.count_black -= 1;
+        }
+        if val.color().is_some() && self.is_wrong_fill(ind, val) {
+            // Picross doesn't let you fill a cell that isn't in the solution. It marks the cell for you instead
+            // and makes you pay for it.
+            self.data[ind[0]][ind[1]] = Cell::Marked;
+            self.penalty_duration += Duration::from_secs(TIME_ATTACK_PENALTY_SECONDS);
         } else {
-            if val == Cell::Filled {
+            if val.color().is_some() {
                 self.count_black += 1;
             }
             self.data[ind[0]][ind[1]] = val;
         }
-        self.current_nums = self.get_nums();
+        self.update_current_clues();
         self.update_crossouts();
 
         self.end_game_screen = self.check_win();
         if self.end_game_screen {
-            self.game_end = Some(Instant::now());
+            self.game_end = Some(self.now());
+            self.update_duration();
+        }
+    }
+
+    /// Clear a cell back to empty.
+    pub fn clear(&mut self, ind: [usize; 2]) {
+        self.set(ind, Cell::Empty);
+    }
+
+    /// Turn every tentative cell into a filled cell.
+    pub fn commit_maybes(&mut self) {
+        for col in 0..self.dimensions[0] {
+            for row in 0..self.dimensions[1] {
+                if self.data[col][row] == Cell::Maybe {
+                    self.set([col, row], Cell::Filled);
+                }
+            }
+        }
+    }
+
+    /// Clear every tentative cell.
+    pub fn discard_maybes(&mut self) {
+        for col in 0..self.dimensions[0] {
+            for row in 0..self.dimensions[1] {
+                if self.data[col][row] == Cell::Maybe {
+                    self.clear([col, row]);
+                }
+            }
+        }
+    }
+
+    /// Pause the timer. Does nothing if the game is already paused or has been won.
+    pub fn pause(&mut self) {
+        if self.pause_start.is_none() && !self.end_game_screen && self.game_start.is_some() {
+            self.pause_start = Some(self.now());
+        }
+    }
+
+    /// Resume the timer. Time spent paused isn't counted towards the final time.
+    pub fn resume(&mut self) {
+        if let Some(pause_start) = self.pause_start.take() {
+            self.paused_duration += self.now() - pause_start;
+        }
+    }
+
+    /// The current time, as far as the timer is concerned. See `clock`.
+    pub fn now(&self) -> Instant {
+        self.clock.unwrap_or_else(Instant::now)
+    }
+
+    /// Whether or not the game is currently paused.
+    pub fn is_paused(&self) -> bool {
+        self.pause_start.is_some()
+    }
+
+    /// Recalculate the amount of time that's passed since the current game has started.
+    ///
+    /// The timer stops once the game ends, and time spent paused is excluded.
+    pub fn update_duration(&mut self) {
+        self.duration = match self.game_start {
+            Some(game_start) => {
+                let now = self
+                    .game_end
+                    .or(self.pause_start)
+                    .unwrap_or_else(|| self.now());
+                (now - game_start).saturating_sub(self.paused_duration) + self.penalty_duration
+            }
+            None => Duration::from_secs(0),
+        };
+
+        // Running out of time in countdown mode ends the game.
+        if let Some(time_limit) = self.time_limit() {
+            if self.duration >= time_limit {
+                self.duration = time_limit;
+                if !self.end_game_screen {
+                    self.time_up = true;
+                    self.end_game_screen = true;
+                    self.game_end = Some(self.now());
+                }
+            }
+        }
+    }
+
+    /// Whether or not filling this cell counts as a mistake.
+    ///
+    /// Only time attack mode punishes mistakes. Boards loaded from save data that predate time attack
+    /// mode don't have a solution to check against, so nothing counts as a mistake there.
+    fn is_wrong_fill(&self, ind: [usize; 2], val: Cell) -> bool {
+        self.mode == GameMode::TimeAttack
+            && !self.goal_data.is_empty()
+            && self.goal_data[ind[0]][ind[1]] != val
+    }
+
+    /// The amount of time the player has to solve the board. `None` if the current game mode has no time limit.
+    pub fn time_limit(&self) -> Option<Duration> {
+        match self.mode {
+            GameMode::Countdown => Some(Duration::from_secs(
+                (self.dimensions[0] * self.dimensions[1]) as u64 * COUNTDOWN_SECONDS_PER_CELL,
+            )),
+            _ => None,
         }
     }
 
@@ -212,11 +544,47 @@ impl NonogramBoard {
 
     /// Setup randomly generated goal nonogram.
     pub fn set_goal(&mut self) {
-        let rng = Bernoulli::new(self.init_ratio).unwrap();
+        self.set_goal_from(&mut rand::thread_rng());
+    }
+
+    /// Setup a goal nonogram picked by `rng`. Each cell is filled with a chance of `init_ratio`.
+    ///
+    /// Mega puzzles also pick which neighboring columns and rows are merged.
+    pub fn set_goal_from<R: Rng>(&mut self, rng: &mut R) {
+        self.mega_lines = (0..2)
+            .map(|axis| {
+                let mut merged = vec![false; self.dimensions[axis]];
+                if self.ruleset == Ruleset::Mega {
+                    let mut line = 0;
+                    while line + 1 < self.dimensions[axis] {
+                        if rng.gen_bool(0.4) {
+                            merged[line] = true;
+                            line += 1;
+                        }
+                        line += 1;
+                    }
+                }
+                merged
+            })
+            .collect();
+
+        let fill = Bernoulli::new(self.init_ratio).unwrap();
         for col in 0..self.dimensions[0] {
             for row in 0..self.dimensions[1] {
-                if rng.sample(&mut rand::thread_rng()) {
-                    self.data[col][row] = Cell::Filled;
+                if fill.sample(rng) {
+                    let color = rng.gen_range(0, self.colors);
+                    self.data[col][row] =
+                        if self.ruleset == Ruleset::Triangles && rng.gen_bool(0.25) {
+                            let corner = [
+                                Corner::TopLeft,
+                                Corner::TopRight,
+                                Corner::BottomRight,
+                                Corner::BottomLeft,
+                            ][rng.gen_range(0, 4)];
+                            Cell::Half(color as u8, corner)
+                        } else {
+                            Cell::from_color(color)
+                        };
                     self.goal_black += 1;
                 }
             }
@@ -234,38 +602,36 @@ impl NonogramBoard {
 
     /// Change the currently selected cell.
     ///
-    /// Used for keyboard controls. Called by `nonogram_controller`.
-    pub fn change_selected(&mut self, direction: Directions, loop_back: bool) {
-        let mut cell = match self.selected_cell {
-            Some(a) => a,
-            None => [0; 2],
-        };
+    /// Used for keyboard controls. Called by `nonogram_controller`. Moving off an edge of the board travels to the
+    /// other side if `wrap_around` is true. Otherwise the selected cell stays at the edge.
+    pub fn change_selected(&mut self, direction: Directions, wrap_around: bool) {
+        let mut cell = self.selected_cell.unwrap_or_default();
         match direction {
             Directions::Up => {
                 if cell[1] > 0 {
                     cell[1] -= 1;
-                } else if loop_back {
+                } else if wrap_around {
                     cell[1] = self.dimensions[1] - 1;
                 }
             }
             Directions::Down => {
                 if cell[1] < self.dimensions[1] - 1 {
                     cell[1] += 1;
-                } else if loop_back {
+                } else if wrap_around {
                     cell[1] = 0;
                 }
             }
             Directions::Left => {
                 if cell[0] > 0 {
                     cell[0] -= 1;
-                } else if loop_back {
+                } else if wrap_around {
                     cell[0] = self.dimensions[0] - 1;
                 }
             }
             Directions::Right => {
                 if cell[0] < self.dimensions[0] - 1 {
                     cell[0] += 1;
-                } else if loop_back {
+                } else if wrap_around {
                     cell[0] = 0;
                 }
             }
@@ -274,47 +640,255 @@ impl NonogramBoard {
         self.selected_cell = Some(cell);
     }
 
+    /// Move the currently selected cell all the way to an edge of the board.
+    pub fn jump_selected(&mut self, direction: Directions) {
+        let mut cell = self.selected_cell.unwrap_or_default();
+        match direction {
+            Directions::Up => cell[1] = 0,
+            Directions::Down => cell[1] = self.dimensions[1] - 1,
+            Directions::Left => cell[0] = 0,
+            Directions::Right => cell[0] = self.dimensions[0] - 1,
+        }
+
+        self.selected_cell = Some(cell);
+    }
+
+    /// Move the currently selected cell to the next row (up or down) or column (left or right) whose hint numbers
+    /// don't match the goal yet. If every line in that direction is solved, the selected cell doesn't move.
+    ///
+    /// Searching continues from the other side of the board if `wrap_around` is true.
+    pub fn skip_selected(&mut self, direction: Directions, wrap_around: bool) {
+        let mut cell = self.selected_cell.unwrap_or_default();
+        let (axis, forward) = match direction {
+            Directions::Up => (1, false),
+            Directions::Down => (1, true),
+            Directions::Left => (0, false),
+            Directions::Right => (0, true),
+        };
+        let len = self.dimensions[axis];
+        let start = cell[axis];
+
+        for step in 1..len {
+            let index = if forward {
+                if !wrap_around && start + step >= len {
+                    break;
+                }
+                (start + step) % len
+            } else {
+                if !wrap_around && step > start {
+                    break;
+                }
+                (start + len - step) % len
+            };
+            if !self.is_line_solved(axis, index) {
+                cell[axis] = index;
+                break;
+            }
+        }
+
+        self.selected_cell = Some(cell);
+    }
+
     /// Find the current black box groupings in order to find correct values
     /// for numbers nearby columns and rows.
     pub fn get_nums(&self) -> Vec<Vec<Vec<i8>>> {
-        let mut nums = vec![vec![vec![0; self.nums_per[0] as usize]; self.dimensions[0]]];
+        self.get_clues().nums
+    }
 
-        nums.push(vec![vec![0; self.nums_per[1] as usize]; self.dimensions[1]]);
+    /// Find the palette index of every current black box grouping. Laid out just like `get_nums`.
+    pub fn get_colors(&self) -> Vec<Vec<Vec<u8>>> {
+        self.get_clues().colors
+    }
 
-        // Get column nums.
-        for col in 0..self.dimensions[0] {
-            let mut num_hint = 0;
-            let mut filling = false;
-            for row in 0..self.dimensions[1] {
-                if self.data[col][row] == Cell::Filled {
-                    if !filling {
-                        filling = true;
+    /// Find whether every current black box grouping starts or ends with a half filled cell. Laid out just like
+    /// `get_nums`.
+    pub fn get_ends(&self) -> Vec<Vec<Vec<u8>>> {
+        self.get_clues().ends
+    }
+
+    /// Recalculate the current hint numbers, along with their colors and ends.
+    fn update_current_clues(&mut self) {
+        let clues = self.get_clues();
+        self.current_nums = clues.nums;
+        self.current_colors = clues.colors;
+        self.current_ends = clues.ends;
+    }
+
+    /// Hint number values that are all the same, laid out just like `goal_nums`.
+    fn empty_clues<T: Clone + Default>(&self) -> Vec<Vec<Vec<T>>> {
+        (0..2)
+            .map(|axis| {
+                vec![vec![T::default(); self.nums_per[axis] as usize]; self.dimensions[axis]]
+            })
+            .collect()
+    }
+
+    /// Whether or not a column (`axis` 0) or row (`axis` 1) is merged with the next one in a mega puzzle.
+    pub fn is_merged(&self, axis: usize, line: usize) -> bool {
+        self.mega_lines
+            .get(axis)
+            .and_then(|lines| lines.get(line))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// The cell at position `k` of a column (`axis` 0) or row (`axis` 1).
+    fn line_cell(&self, axis: usize, line: usize, k: usize) -> Cell {
+        if axis == 0 {
+            self.data[line][k]
+        } else {
+            self.data[k][line]
+        }
+    }
+
+    /// Find the length, palette index, and flags of every grouping in a merged pair of lines, starting at `line`.
+    ///
+    /// A grouping is every filled cell of the same color connected to each other within the pair. Groupings are
+    /// ordered by their first cell, and the one in the first line goes first when two start at the same spot.
+    fn band_clues(&self, axis: usize, line: usize) -> Vec<(i8, u8, u8)> {
+        let len = self.dimensions[1 - axis];
+        let mut seen = vec![[false; 2]; len];
+        let mut groupings = vec![];
+        for k in 0..len {
+            for side in 0..2 {
+                let color = match self.line_cell(axis, line + side, k).color() {
+                    Some(color) if !seen[k][side] => color,
+                    _ => continue,
+                };
+                let mut size = 0;
+                let mut sides = [false; 2];
+                let mut stack = vec![(k, side)];
+                seen[k][side] = true;
+                while let Some((k, side)) = stack.pop() {
+                    size += 1;
+                    sides[side] = true;
+                    let mut neighbors = vec![(k, 1 - side)];
+                    if k > 0 {
+                        neighbors.push((k - 1, side));
+                    }
+                    if k + 1 < len {
+                        neighbors.push((k + 1, side));
+                    }
+                    for (k, side) in neighbors {
+                        if !seen[k][side]
+                            && self.line_cell(axis, line + side, k).color() == Some(color)
+                        {
+                            seen[k][side] = true;
+                            stack.push((k, side));
+                        }
                     }
-                    nums[0][col][num_hint] += 1;
-                } else if filling {
-                    filling = false;
-                    num_hint += 1;
                 }
+                let flags = match sides {
+                    [true, true] => CLUE_MEGA,
+                    [false, true] => CLUE_SECOND,
+                    _ => 0,
+                };
+                groupings.push((size, color as u8, flags));
             }
         }
+        groupings
+    }
 
-        // Get row nums.
-        for row in 0..self.dimensions[1] {
-            let mut num_hint = 0;
-            let mut filling = false;
-            for col in 0..self.dimensions[0] {
-                if self.data[col][row] == Cell::Filled {
-                    if !filling {
-                        filling = true;
+    /// Find the length, palette index, and ends of every filled cell grouping in every column and row. Single lines
+    /// are read by `line_clues`, and merged pairs of lines in mega puzzles are read by `band_clues`.
+    fn get_clues(&self) -> Clues {
+        let mut clues = Clues {
+            nums: self.empty_clues(),
+            colors: self.empty_clues(),
+            ends: self.empty_clues(),
+        };
+        for axis in 0..2 {
+            // Columns are read from top to bottom, and rows are read from left to right.
+            let (forward, backward) = if axis == 0 {
+                (Directions::Down, Directions::Up)
+            } else {
+                (Directions::Right, Directions::Left)
+            };
+            for line in 0..self.dimensions[axis] {
+                let line_nums = &mut clues.nums[axis][line];
+                let line_colors = &mut clues.colors[axis][line];
+                let line_ends = &mut clues.ends[axis][line];
+                if line > 0 && self.is_merged(axis, line - 1) {
+                    continue;
+                }
+                if self.is_merged(axis, line) {
+                    for (it, (size, color, flags)) in
+                        self.band_clues(axis, line).into_iter().enumerate()
+                    {
+                        line_nums[it] = size;
+                        line_colors[it] = color;
+                        line_ends[it] = flags;
                     }
-                    nums[1][row][num_hint] += 1;
-                } else if filling {
-                    filling = false;
-                    num_hint += 1;
+                    continue;
                 }
+                let cells: Vec<Cell> = (0..self.dimensions[1 - axis])
+                    .map(|k| self.line_cell(axis, line, k))
+                    .collect();
+                for (it, (size, color, flags)) in line_clues(&cells, forward, backward)
+                    .into_iter()
+                    .enumerate()
+                {
+                    line_nums[it] = size;
+                    line_colors[it] = color;
+                    line_ends[it] = flags;
+                }
+            }
+        }
+        clues
+    }
+
+    /// Length, palette index, and ends of every grouping in every column (index 0) and row (index 1) of the cells
+    /// as they are now, read just like the hint numbers. The second line of a merged pair in a mega puzzle is
+    /// `None`, since its groupings are part of the first line's.
+    pub fn picture_clues(&self) -> [PictureClues; 2] {
+        let clues = self.get_clues();
+        let mut lines = [vec![], vec![]];
+        for (axis, lines) in lines.iter_mut().enumerate() {
+            for line in 0..self.dimensions[axis] {
+                if line > 0 && self.is_merged(axis, line - 1) {
+                    lines.push(None);
+                    continue;
+                }
+                let groupings = clues.nums[axis][line]
+                    .iter()
+                    .zip(&clues.colors[axis][line])
+                    .zip(&clues.ends[axis][line])
+                    .take_while(|((&size, _), _)| size != 0)
+                    .map(|((&size, &color), &ends)| (size as usize, color as usize, ends))
+                    .collect();
+                lines.push(Some(groupings));
             }
         }
-        nums
+        lines
+    }
+
+    /// Whether or not a hint number is shown as crossed out, either automatically or by hand.
+    pub fn is_crossed_out(&self, axis: usize, line: usize, index: usize) -> bool {
+        self.goal_nums[axis][line][index] < 0 || self.manual_crossouts[axis][line][index]
+    }
+
+    /// Cross out a hint number by hand, or undo it if it's already crossed out by hand.
+    pub fn toggle_manual_crossout(&mut self, axis: usize, line: usize, index: usize) {
+        let crossout = &mut self.manual_crossouts[axis][line][index];
+        *crossout = !*crossout;
+    }
+
+    /// Clear every hint number crossed out by hand.
+    pub fn reset_manual_crossouts(&mut self) {
+        self.manual_crossouts = self.empty_clues();
+    }
+
+    /// Whether or not the hint numbers crossed out by hand are laid out just like `goal_nums`. Save data from
+    /// before they existed won't have any.
+    fn manual_crossouts_fit(&self) -> bool {
+        self.manual_crossouts.len() == self.goal_nums.len()
+            && self
+                .manual_crossouts
+                .iter()
+                .zip(self.goal_nums.iter())
+                .all(|(a, b)| {
+                    a.len() == b.len() && a.iter().zip(b.iter()).all(|(c, d)| c.len() == d.len())
+                })
     }
 
     /// Hint numbers are stored as signed integers, which means that we utilize the negative values in order to
@@ -342,6 +916,9 @@ impl NonogramBoard {
                     while current_it < self.nums_per[0] as usize {
                         if self.goal_nums[0][k][goal_it].abs()
                             == self.current_nums[0][k][current_it]
+                            && self.goal_colors[0][k][goal_it]
+                                == self.current_colors[0][k][current_it]
+                            && self.goal_ends[0][k][goal_it] == self.current_ends[0][k][current_it]
                             && match_count
                         {
                             if self.goal_nums[0][k][goal_it] > 0 {
@@ -378,6 +955,9 @@ impl NonogramBoard {
                     while current_it < self.nums_per[1] as usize {
                         if self.goal_nums[1][k][goal_it].abs()
                             == self.current_nums[1][k][current_it]
+                            && self.goal_colors[1][k][goal_it]
+                                == self.current_colors[1][k][current_it]
+                            && self.goal_ends[1][k][goal_it] == self.current_ends[1][k][current_it]
                             && match_count
                         {
                             if self.goal_nums[1][k][goal_it] > 0 {
@@ -399,48 +979,174 @@ impl NonogramBoard {
 
     /// Initialize nonogram board.
     pub fn initialize(&mut self) {
-        self.set_goal();
+        self.initialize_from(&mut rand::thread_rng());
+    }
+
+    /// Initialize nonogram board with a goal state picked by `rng`.
+    fn initialize_from<R: Rng>(&mut self, rng: &mut R) {
+        self.set_goal_from(rng);
+        self.goal_data = self.data.clone();
         self.goal_nums = self.get_nums();
+        self.goal_colors = self.get_colors();
+        self.goal_ends = self.get_ends();
+        self.reset_manual_crossouts();
         self.wipe_board();
-        self.game_start = Some(Instant::now());
+        self.update_current_clues();
+        self.game_start = Some(self.now());
         self.reset_board = false;
     }
 }
 
+/// Find the length, palette index, and ends of every filled cell grouping in a single line of cells. `forward` is
+/// the direction the line is read in, and `backward` is the opposite one.
+///
+/// Neighboring filled cells are part of the same grouping if they're the same color and they both cover the edge
+/// they share. Otherwise a grouping ends there, even without a gap, which is how color and triangle puzzles work.
+/// Tentative and marked cells don't count.
+fn line_clues(cells: &[Cell], forward: Directions, backward: Directions) -> Vec<(i8, u8, u8)> {
+    let mut groupings: Vec<(i8, u8, u8)> = vec![];
+    let mut previous = Cell::Empty;
+    for &cell in cells {
+        let continues = cell.color().is_some()
+            && previous.color() == cell.color()
+            && previous.connects(forward)
+            && cell.connects(backward);
+        if let (Cell::Half(..), false) = (previous, continues) {
+            if let Some(grouping) = groupings.last_mut() {
+                grouping.2 |= CLUE_HALF_END;
+            }
+        }
+        if let Some(index) = cell.color() {
+            if continues {
+                if let Some(grouping) = groupings.last_mut() {
+                    grouping.0 += 1;
+                }
+            } else {
+                let flags = match cell {
+                    Cell::Half(..) => CLUE_HALF_START,
+                    _ => 0,
+                };
+                groupings.push((1, index as u8, flags));
+            }
+        }
+        previous = cell;
+    }
+    if let (Cell::Half(..), Some(grouping)) = (previous, groupings.last_mut()) {
+        grouping.2 |= CLUE_HALF_END;
+    }
+    groupings
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_new() {
-        let nonogram = NonogramBoard::new([5, 5], true);
+        let nonogram = NonogramBoard::new([5, 5]);
         assert_eq!(nonogram.dimensions, [5, 5], "Board has correct dimensions.");
         assert_eq!(nonogram.nums_per, [3, 3], "Correct number of hint nums.");
-        assert_eq!(nonogram.check_win(), false, "Goal was generated.");
+        assert!(!nonogram.check_win(), "Goal was generated.");
         assert_eq!(nonogram.count_black, 0, "Correct number for count_black.");
         assert_ne!(nonogram.goal_black, 0, "Goal was generated.");
     }
 
+    #[test]
+    fn test_save_and_load() {
+        use crate::nonogram_storage::MemoryStorage;
+
+        let mut storage = MemoryStorage::new();
+        assert!(
+            NonogramBoard::load(&storage).unwrap().is_none(),
+            "Nothing is loaded before a board is saved."
+        );
+
+        let mut nonogram = NonogramBoard::new([5, 5]);
+        nonogram.set([2, 3], Cell::Filled);
+        nonogram.save(&mut storage).unwrap();
+        let loaded = NonogramBoard::load(&storage).unwrap().unwrap();
+        assert_eq!(
+            loaded.data, nonogram.data,
+            "The board is loaded as it was saved."
+        );
+        assert_eq!(loaded.goal_nums, nonogram.goal_nums);
+
+        storage.write(SAVE_FILE, "{}").unwrap();
+        assert!(
+            NonogramBoard::load(&storage).is_err(),
+            "Incompatible save data is an error."
+        );
+    }
+
+    #[test]
+    fn test_from_saved() {
+        let empty = NonogramBoard::empty([5, 5], 1, Ruleset::Standard);
+        assert!(
+            empty.goal_data.is_empty(),
+            "Empty boards don't generate a goal."
+        );
+        assert!(
+            empty.game_start.is_none(),
+            "Empty boards haven't been started."
+        );
+
+        let mut nonogram = NonogramBoard::with_variant([5, 5], 3, Ruleset::Triangles);
+        nonogram.set([1, 4], Cell::Filled);
+        let restored = NonogramBoard::from_saved(nonogram.saved());
+        assert_eq!(
+            restored.goal_data, nonogram.goal_data,
+            "The goal is restored."
+        );
+        assert_eq!(restored.data, nonogram.data, "The board is restored.");
+        assert_eq!(restored.colors, 3, "The colors are restored.");
+        assert_eq!(
+            restored.ruleset,
+            Ruleset::Triangles,
+            "The ruleset is restored."
+        );
+    }
+
+    #[test]
+    fn test_generate_from() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut nonogram = NonogramBoard::new([10, 10]);
+        nonogram.generate_from(&mut StdRng::seed_from_u64(7));
+        let goal_nums = nonogram.goal_nums.clone();
+        nonogram.generate_from(&mut StdRng::seed_from_u64(7));
+        assert_eq!(
+            nonogram.goal_nums, goal_nums,
+            "The same seed generates the same board."
+        );
+
+        nonogram.init_ratio = 0.0;
+        nonogram.generate_from(&mut StdRng::seed_from_u64(7));
+        assert_eq!(
+            nonogram.goal_black, 0,
+            "Density decides how many cells are filled."
+        );
+    }
+
     #[test]
     fn test_check_win() {
-        let mut nonogram = NonogramBoard::new([5, 5], true);
+        let mut nonogram = NonogramBoard::new([5, 5]);
         nonogram.set_goal();
         nonogram.goal_nums = nonogram.get_nums();
-        assert_eq!(nonogram.check_win(), false, "New goal, empty current.");
+        assert!(!nonogram.check_win(), "New goal, empty current.");
         nonogram.current_nums = nonogram.get_nums();
-        assert_eq!(nonogram.check_win(), true, "Goal and current are the same.");
+        assert!(nonogram.check_win(), "Goal and current are the same.");
         nonogram.wipe_board();
         nonogram.current_nums = nonogram.get_nums();
-        assert_eq!(
-            nonogram.check_win(),
-            false,
+        assert!(
+            !nonogram.check_win(),
             "Wipe current. Goal and current aren't the same."
         );
     }
 
     #[test]
     fn test_set() {
-        let mut nonogram = NonogramBoard::new([5, 5], true);
+        let mut nonogram = NonogramBoard::new([5, 5]);
         assert_eq!(
             nonogram.data[4][4],
             Cell::Empty,
@@ -452,11 +1158,194 @@ mod tests {
             Cell::Filled,
             "Used set() to change value to 1."
         );
+        nonogram.set([4, 4], Cell::Filled);
+        assert_eq!(
+            nonogram.data[4][4],
+            Cell::Filled,
+            "Setting the same value doesn't toggle it."
+        );
+        assert_eq!(nonogram.count_black, 1, "Filled cell is only counted once.");
+        nonogram.set([4, 4], Cell::Marked);
+        assert_eq!(nonogram.data[4][4], Cell::Marked, "Marked a filled cell.");
+        assert_eq!(nonogram.count_black, 0, "Overwritten fill isn't counted.");
+        nonogram.clear([4, 4]);
+        assert_eq!(nonogram.data[4][4], Cell::Empty, "Cleared the cell.");
+    }
+
+    #[test]
+    fn test_maybes() {
+        let mut nonogram = NonogramBoard::new([5, 5]);
+        nonogram.set([0, 0], Cell::Maybe);
+        nonogram.set([1, 0], Cell::Maybe);
+        nonogram.set([2, 0], Cell::Marked);
+        assert_eq!(nonogram.count_black, 0, "Tentative cells aren't counted.");
+        assert_eq!(
+            nonogram.get_nums()[1][0][0],
+            0,
+            "Tentative cells don't count towards hint numbers."
+        );
+        nonogram.discard_maybes();
+        assert_eq!(
+            nonogram.get([0, 0]),
+            Cell::Empty,
+            "Tentative cell discarded."
+        );
+        assert_eq!(nonogram.get([2, 0]), Cell::Marked, "Marked cell untouched.");
+        nonogram.set([0, 0], Cell::Maybe);
+        nonogram.commit_maybes();
+        assert_eq!(
+            nonogram.get([0, 0]),
+            Cell::Filled,
+            "Tentative cell committed."
+        );
+        assert_eq!(nonogram.count_black, 1, "Committed cell is counted.");
+    }
+
+    #[test]
+    fn test_manual_crossouts() {
+        let mut nonogram = NonogramBoard::new([5, 5]);
+        nonogram.goal_nums[1][2][0] = 2;
+        assert!(
+            !nonogram.is_crossed_out(1, 2, 0),
+            "New hints aren't crossed out."
+        );
+        nonogram.toggle_manual_crossout(1, 2, 0);
+        assert!(nonogram.is_crossed_out(1, 2, 0), "Crossed out by hand.");
+        nonogram.set([0, 0], Cell::Filled);
+        assert!(
+            nonogram.is_crossed_out(1, 2, 0),
+            "Automatic crossouts don't undo manual ones."
+        );
+        nonogram.toggle_manual_crossout(1, 2, 0);
+        assert!(!nonogram.is_crossed_out(1, 2, 0), "Manual crossout undone.");
+    }
+
+    #[test]
+    fn test_colors() {
+        let mut nonogram = NonogramBoard::with_variant([5, 5], 3, Ruleset::Standard);
+        assert_eq!(nonogram.colors, 3, "Board uses three colors.");
+        assert_eq!(
+            nonogram.nums_per,
+            [5, 5],
+            "Every cell can be a hint number."
+        );
+        assert!(
+            nonogram
+                .goal_data
+                .iter()
+                .flatten()
+                .all(|cell| match cell.color() {
+                    Some(index) => index < 3,
+                    None => true,
+                }),
+            "Goal only uses the palette colors of the board."
+        );
+
+        nonogram.wipe_board();
+        nonogram.set([0, 0], Cell::Filled);
+        nonogram.set([1, 0], Cell::Filled);
+        nonogram.set([2, 0], Cell::Colored(2));
+        nonogram.set([4, 0], Cell::Colored(2));
+        assert_eq!(
+            nonogram.current_nums[1][0][..3],
+            [2, 1, 1],
+            "Different colors split runs without a gap."
+        );
+        assert_eq!(
+            nonogram.current_colors[1][0][..3],
+            [0, 2, 2],
+            "Runs remember their colors."
+        );
+        assert_eq!(nonogram.count_black, 4, "Every color is counted.");
+    }
+
+    #[test]
+    fn test_triangles() {
+        let mut nonogram = NonogramBoard::with_variant([5, 5], 1, Ruleset::Triangles);
+        assert_eq!(
+            nonogram.ruleset,
+            Ruleset::Triangles,
+            "Board uses triangles."
+        );
+
+        nonogram.wipe_board();
+        nonogram.set([0, 0], Cell::Filled);
+        nonogram.set([1, 0], Cell::Half(0, Corner::TopLeft));
+        nonogram.set([2, 0], Cell::Half(0, Corner::TopRight));
+        nonogram.set([3, 0], Cell::Filled);
+        assert_eq!(
+            nonogram.current_nums[1][0][..3],
+            [2, 2, 0],
+            "Half filled cells end a grouping, even without a gap."
+        );
+        assert_eq!(
+            nonogram.current_ends[1][0][..2],
+            [CLUE_HALF_END, CLUE_HALF_START],
+            "Hint numbers know which ends are half filled."
+        );
+        assert_eq!(
+            nonogram.current_nums[0][1][..2],
+            [1, 0],
+            "A lone half filled cell is a grouping of its own."
+        );
+        assert_eq!(
+            nonogram.current_ends[0][1][0],
+            CLUE_HALF_START | CLUE_HALF_END,
+            "A lone half filled cell starts and ends its grouping."
+        );
+    }
+
+    #[test]
+    fn test_mega() {
+        let mut nonogram = NonogramBoard::with_variant([5, 5], 1, Ruleset::Mega);
+        nonogram.mega_lines = vec![vec![false; 5], vec![true, false, false, false, false]];
+        nonogram.goal_nums = nonogram.empty_clues();
+        nonogram.goal_colors = nonogram.empty_clues();
+        nonogram.goal_ends = nonogram.empty_clues();
+        nonogram.goal_nums[1][0][..3].copy_from_slice(&[3, 1, 1]);
+        nonogram.goal_ends[1][0][..3].copy_from_slice(&[CLUE_MEGA, CLUE_SECOND, 0]);
+        nonogram.goal_nums[0][0][0] = 1;
+        nonogram.goal_nums[0][1][0] = 2;
+        nonogram.goal_nums[0][3][0] = 1;
+        nonogram.goal_nums[0][4][0] = 1;
+
+        nonogram.wipe_board();
+        nonogram.set([0, 0], Cell::Filled);
+        nonogram.set([1, 0], Cell::Filled);
+        nonogram.set([1, 1], Cell::Filled);
+        nonogram.set([3, 1], Cell::Filled);
+        assert_eq!(
+            nonogram.current_nums[1][0][..3],
+            [3, 1, 0],
+            "Cells connected across a merged pair are one grouping."
+        );
+        assert_eq!(
+            nonogram.current_ends[1][0][..2],
+            [CLUE_MEGA, CLUE_SECOND],
+            "Hint numbers know which lines of a merged pair they cover."
+        );
+        assert_eq!(
+            nonogram.current_nums[1][1][0], 0,
+            "The second line of a merged pair has no hint numbers of its own."
+        );
+        assert!(!nonogram.check_win(), "A grouping is missing.");
+        assert_eq!(
+            nonogram.picture_clues()[1][..2],
+            [Some(vec![(3, 0, CLUE_MEGA), (1, 0, CLUE_SECOND)]), None],
+            "Pictures read merged pairs just like the hint numbers."
+        );
+
+        nonogram.set([4, 0], Cell::Filled);
+        assert_eq!(
+            nonogram.current_ends[1][0][2], 0,
+            "A grouping in the first line isn't flagged."
+        );
+        assert!(nonogram.check_win(), "Every merged hint number matches.");
     }
 
     #[test]
     fn test_get() {
-        let mut nonogram = NonogramBoard::new([5, 5], true);
+        let mut nonogram = NonogramBoard::new([5, 5]);
         assert_eq!(
             nonogram.get([4, 4]),
             Cell::Empty,
@@ -465,4 +1354,116 @@ mod tests {
         nonogram.data[4][4] = Cell::Filled;
         assert_eq!(nonogram.get([4, 4]), Cell::Filled, "Changed value to 1.");
     }
+
+    #[test]
+    fn test_change_selected() {
+        let mut nonogram = NonogramBoard::new([5, 5]);
+        nonogram.selected_cell = Some([0, 0]);
+        nonogram.change_selected(Directions::Left, false);
+        assert_eq!(nonogram.selected_cell, Some([0, 0]), "Stopped at the edge.");
+        nonogram.change_selected(Directions::Left, true);
+        assert_eq!(nonogram.selected_cell, Some([4, 0]), "Wrapped around.");
+        nonogram.change_selected(Directions::Right, true);
+        assert_eq!(nonogram.selected_cell, Some([0, 0]), "Wrapped back.");
+        nonogram.jump_selected(Directions::Down);
+        assert_eq!(nonogram.selected_cell, Some([0, 4]), "Jumped to the edge.");
+    }
+
+    #[test]
+    fn test_skip_selected() {
+        let mut nonogram = NonogramBoard::new([5, 5]);
+        nonogram.goal_nums = vec![vec![vec![0; 3]; 5]; 2];
+        nonogram.goal_nums[1][3][0] = 1;
+        nonogram.selected_cell = Some([2, 0]);
+        nonogram.skip_selected(Directions::Down, false);
+        assert_eq!(
+            nonogram.selected_cell,
+            Some([2, 3]),
+            "Skipped to the unsolved row."
+        );
+        nonogram.skip_selected(Directions::Down, false);
+        assert_eq!(
+            nonogram.selected_cell,
+            Some([2, 3]),
+            "No unsolved rows below."
+        );
+        nonogram.selected_cell = Some([2, 4]);
+        nonogram.skip_selected(Directions::Down, true);
+        assert_eq!(
+            nonogram.selected_cell,
+            Some([2, 3]),
+            "Wrapped around to the unsolved row."
+        );
+        nonogram.skip_selected(Directions::Right, true);
+        assert_eq!(
+            nonogram.selected_cell,
+            Some([2, 3]),
+            "Every column is solved."
+        );
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut nonogram = NonogramBoard::new([5, 5]);
+        assert!(!nonogram.is_paused(), "New games aren't paused.");
+        nonogram.pause();
+        assert!(nonogram.is_paused(), "Game is paused.");
+        nonogram.update_duration();
+        let paused_at = nonogram.duration;
+        std::thread::sleep(Duration::from_millis(20));
+        nonogram.update_duration();
+        assert_eq!(
+            nonogram.duration, paused_at,
+            "Timer doesn't run while paused."
+        );
+        nonogram.resume();
+        assert!(!nonogram.is_paused(), "Game is resumed.");
+        assert!(
+            nonogram.paused_duration >= Duration::from_millis(20),
+            "Paused time is excluded."
+        );
+    }
+
+    #[test]
+    fn test_time_attack() {
+        let mut nonogram = NonogramBoard::new([5, 5]);
+        nonogram.mode = GameMode::TimeAttack;
+        nonogram.goal_data = vec![vec![Cell::Empty; 5]; 5];
+        nonogram.goal_data[0][0] = Cell::Filled;
+
+        nonogram.set([1, 1], Cell::Filled);
+        assert_eq!(nonogram.get([1, 1]), Cell::Marked, "Wrong fill is marked.");
+        assert_eq!(
+            nonogram.penalty_duration,
+            Duration::from_secs(TIME_ATTACK_PENALTY_SECONDS),
+            "Wrong fill adds a penalty."
+        );
+        assert_eq!(nonogram.count_black, 0, "Wrong fill isn't counted.");
+
+        nonogram.set([0, 0], Cell::Filled);
+        assert_eq!(nonogram.get([0, 0]), Cell::Filled, "Right fill is kept.");
+        assert_eq!(
+            nonogram.penalty_duration,
+            Duration::from_secs(TIME_ATTACK_PENALTY_SECONDS),
+            "Right fill doesn't add a penalty."
+        );
+        assert_eq!(nonogram.count_black, 1, "Right fill is counted.");
+    }
+
+    #[test]
+    fn test_countdown() {
+        let mut nonogram = NonogramBoard::new([5, 5]);
+        nonogram.mode = GameMode::Countdown;
+        let time_limit = nonogram.time_limit().unwrap();
+        nonogram.update_duration();
+        assert!(!nonogram.time_up, "Countdown just started.");
+        nonogram.game_start = Some(Instant::now() - time_limit - Duration::from_secs(1));
+        nonogram.update_duration();
+        assert!(nonogram.time_up, "Countdown ran out.");
+        assert!(
+            nonogram.end_game_screen,
+            "Running out of time ends the game."
+        );
+        assert_eq!(nonogram.duration, time_limit, "Timer stops at the limit.");
+    }
 }
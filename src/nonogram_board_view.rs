@@ -6,24 +6,38 @@ use graphics::types::Color;
 use graphics::{Context, Graphics};
 use rand::seq::SliceRandom;
 
-use crate::common::{
-    ButtonInteraction, Cell, BOARD_SIZE, DIMENSIONS_CHOICES, IMAGE_NAMES, IMAGE_PRE,
+use std::time::Duration;
+
+use nonogram::common::{
+    column_hint_y, row_hint_x, ButtonInteraction, Cell, GameMode, Ruleset, BOARD_SIZE,
+    CLUE_HALF_END, CLUE_HALF_START, CLUE_MEGA, CLUE_SECOND, CONTROLS_ROWS_SHOWN,
+    CONTROLS_ROW_HEIGHT, CONTROLS_ROW_OFFSET, DIMENSIONS_CHOICES, IMAGE_NAMES, IMAGE_PRE, PALETTE,
+    PICTURE_SCALES, STATS_HISTORY_SHOWN,
 };
-use crate::NonogramController;
+use nonogram::nonogram_clip::NonogramClip;
+use nonogram::nonogram_controller::{button_name, Control};
+use nonogram::{NonogramController, ReplayPlayer, WindowLayout};
+
+/// Format a duration the same way the timer displays it: `HH:MM:SS`.
+fn format_duration(duration: Duration) -> String {
+    let total_seconds = duration.as_secs();
+    let total_mins = total_seconds / 60;
+    let total_hrs = total_mins / 60;
+    let rem_seconds = total_seconds - total_mins * 60;
+    let rem_mins = total_mins - total_hrs * 60;
+    format!("{:02}:{:02}:{:02}", total_hrs, rem_mins, rem_seconds)
+}
 
 #[derive(Default)]
 /// Stores nonogram view settings.
 pub struct NonogramViewSettings {
-    /// X and Y coordinates of nonogram board relative to top left corner of the window.
-    pub position: [f64; 2],
+    /// Where the board and every button are, which the controller needs to handle events.
+    pub layout: WindowLayout,
 
     /// Overall size value of nonogram board. This ends up being used in an equation which determines
     /// the width and height of the board based on how many rows and columns it has.
     pub size: f64,
 
-    /// [width, height] of nonogram board.
-    pub board_dimensions: [f64; 2],
-
     /// [columns, rows] in nonogram board.
     pub cell_dimensions: [usize; 2],
 
@@ -72,13 +86,25 @@ pub struct NonogramViewSettings {
     /// Background color of marked cell.
     pub marked_cell_background_color: Color,
 
+    /// Background color of tentative cell. Also used for the tool button's text and the selected cell's edge
+    /// while in pencil mode.
+    pub maybe_cell_background_color: Color,
+
+    /// Color of the cells covered by the line or rectangle tool while dragging.
+    pub tool_preview_color: Color,
+
+    /// Color covering the nonogram board while the game is paused.
+    pub paused_board_color: Color,
+
     /// General color of basically all text in the game.
     pub text_color: Color,
 
-    /// Location and size of unselected dimensions dropdown menu box.
-    ///
-    /// Format: [x, y, width, height]
-    pub dimensions_dropdown_menu_box: [f64; 4],
+    /// Color of the "new personal best" notice on the win screen. Also used for headings on the stats and
+    /// controls screens.
+    pub personal_best_color: Color,
+
+    /// Color of actions on the controls screen that share a keybinding with another action.
+    pub conflict_color: Color,
 
     /// Location and size of selected dimensions dropdown menu box.
     ///
@@ -90,15 +116,10 @@ pub struct NonogramViewSettings {
     /// Format: [x, y, width, height]
     pub win_box_rect: [f64; 4],
 
-    /// Location and size of restart button in main game screen.
-    ///
-    /// Format: [x, y, width, height]
-    pub restart_box: [f64; 4],
-
-    /// Location and size of new game button on win screen.
+    /// Position and size of the box showing how far along a replay is, while watching one.
     ///
-    /// Format: [x, y, width, height]
-    pub new_game_box: [f64; 4],
+    /// [x, y, w, h]
+    pub replay_box: [f64; 4],
 
     /// Randomly generated win critique of user's winning game board.
     pub win_critique: String,
@@ -109,9 +130,23 @@ impl NonogramViewSettings {
     /// Creates new nonogram view settings.
     pub fn new(new_cell_dimensions: [usize; 2]) -> NonogramViewSettings {
         let mut view_settings = NonogramViewSettings {
-            position: [300.0, 240.0],
+            layout: WindowLayout {
+                position: [300.0, 240.0],
+                board_dimensions: [0.0; 2],
+                dimensions_dropdown_menu_box: [300.0, 10.0, 100.0, 30.0],
+                restart_box: [450.0, 10.0, 100.0, 30.0],
+                new_game_box: [450.0, 10.0, 100.0, 30.0],
+                save_picture_box: [450.0, 10.0, 100.0, 30.0],
+                stats_box: [560.0, 10.0, 100.0, 30.0],
+                mode_box: [670.0, 10.0, 170.0, 30.0],
+                tool_box: [1010.0, 10.0, 170.0, 30.0],
+                palette_box: [20.0, 12.0, 250.0, 25.0],
+                clip_map_box: [20.0, 75.0, 250.0, 150.0],
+                controls_box: [850.0, 10.0, 150.0, 30.0],
+                menu_box_rect: [600.0, 450.0, 700.0, 800.0],
+                menu_back_box: [0.0, 0.0, 0.0, 30.0],
+            },
             size: BOARD_SIZE,
-            board_dimensions: [0.0; 2],
             cell_dimensions: [new_cell_dimensions[0], new_cell_dimensions[1]],
             cell_size: 0.0,
             win_board_dimensions: [0.0, 240.0],
@@ -128,12 +163,15 @@ impl NonogramViewSettings {
             selected_cell_border_radius: 2.0,
             filled_cell_background_color: hex("353235"),
             marked_cell_background_color: hex("f77b00"),
+            maybe_cell_background_color: hex("9a969a"),
+            tool_preview_color: hex("5adbfd55"),
+            paused_board_color: hex("333333"),
             text_color: hex("ffffff"),
-            dimensions_dropdown_menu_box: [300.0, 10.0, 100.0, 30.0],
+            personal_best_color: hex("f7c400"),
+            conflict_color: hex("e0533d"),
             dimensions_dropdown_menu_select_background: [0.0; 4],
-            win_box_rect: [600.0, 500.0, 250.0, 200.0],
-            restart_box: [450.0, 10.0, 100.0, 30.0],
-            new_game_box: [450.0, 10.0, 100.0, 30.0],
+            win_box_rect: [600.0, 500.0, 250.0, 290.0],
+            replay_box: [20.0, 790.0, 250.0, 65.0],
             win_critique: "".to_string(),
         };
         view_settings.init_new();
@@ -147,9 +185,9 @@ impl NonogramViewSettings {
         // and the size of the board which is set by the BOARD_SIZE const in common.rs.
         let cols = self.cell_dimensions[0] as f64;
         let rows = self.cell_dimensions[1] as f64;
-        self.board_dimensions[0] = (cols / (cols + rows)) * self.size;
-        self.board_dimensions[1] = (rows / (cols + rows)) * self.size;
-        self.cell_size = self.board_dimensions[0] / cols;
+        self.layout.board_dimensions[0] = (cols / (cols + rows)) * self.size;
+        self.layout.board_dimensions[1] = (rows / (cols + rows)) * self.size;
+        self.cell_size = self.layout.board_dimensions[0] / cols;
 
         // A random string that's displayed near an image of the final board upon winning.
         // Ends up saying something like, "That looks just like Abraham Lincoln!".
@@ -173,17 +211,43 @@ impl NonogramViewSettings {
         self.win_box_rect[1] -= self.win_box_rect[3] / 2.0;
 
         // New game box / button at the bottom of the win box is center aligned and located at the very bottom of the win box.
-        self.new_game_box[2] = self.win_box_rect[2];
-        self.new_game_box[0] =
-            self.win_box_rect[0] + (self.win_box_rect[2] / 2.0) - (self.new_game_box[2] / 2.0);
-        self.new_game_box[1] = self.win_box_rect[1] + self.win_box_rect[3] - self.new_game_box[3];
+        self.layout.new_game_box[2] = self.win_box_rect[2];
+        self.layout.new_game_box[0] = self.win_box_rect[0] + (self.win_box_rect[2] / 2.0)
+            - (self.layout.new_game_box[2] / 2.0);
+        self.layout.new_game_box[1] =
+            self.win_box_rect[1] + self.win_box_rect[3] - self.layout.new_game_box[3];
+
+        // Save picture button sits right below the win box, lined up with the new game button.
+        self.layout.save_picture_box[0] = self.layout.new_game_box[0];
+        self.layout.save_picture_box[2] = self.layout.new_game_box[2];
+        self.layout.save_picture_box[1] = self.win_box_rect[1] + self.win_box_rect[3] + 10.0;
+
+        // Stats and controls box is center-aligned, and its back button sits at the very bottom of it just like the new game button.
+        self.layout.menu_box_rect[0] -= self.layout.menu_box_rect[2] / 2.0;
+        self.layout.menu_box_rect[1] -= self.layout.menu_box_rect[3] / 2.0;
+        self.layout.menu_back_box[2] = self.layout.menu_box_rect[2];
+        self.layout.menu_back_box[0] = self.layout.menu_box_rect[0];
+        self.layout.menu_back_box[1] = self.layout.menu_box_rect[1] + self.layout.menu_box_rect[3]
+            - self.layout.menu_back_box[3];
 
         // Setup dimensions dropdown menu stuff.
-        self.dimensions_dropdown_menu_select_background = self.dimensions_dropdown_menu_box;
+        self.dimensions_dropdown_menu_select_background = self.layout.dimensions_dropdown_menu_box;
         self.dimensions_dropdown_menu_select_background[3] *= (DIMENSIONS_CHOICES.len() + 3) as f64;
     }
 }
 
+/// How far a hint number of a merged pair in a mega puzzle is moved from the first line of the pair. Mega hint
+/// numbers sit between both lines, and hint numbers of the second line sit on the second line.
+fn mega_shift(ends: u8, cell_size: f64) -> f64 {
+    if ends & CLUE_MEGA != 0 {
+        cell_size / 2.0
+    } else if ends & CLUE_SECOND != 0 {
+        cell_size
+    } else {
+        0.0
+    }
+}
+
 /// Stores visual information about a nonogram.
 pub struct NonogramView {
     /// Stores nonogram view settings.
@@ -218,18 +282,18 @@ impl NonogramView {
 
         let settings = &self.settings;
 
-        let total_seconds = controller.nonogram.duration.as_secs();
-        let total_mins = total_seconds / 60;
-        let total_hrs = total_mins / 60;
-        let rem_seconds = total_seconds - total_mins * 60;
-        let rem_mins = total_mins - total_hrs * 60;
-
         // Draw a large transparent rectangle over window.
         // Last two digits in hex refer to transparency: https://css-tricks.com/8-digit-hex-codes/
         // Rectangle::new(hex("000000E6")).draw(window_rect, &c.draw_state, c.transform, g);
 
+        // Draw controls screen.
+        if controller.controls_screen {
+            self.draw_controls(controller, glyphs, c, g);
+        // Draw stats screen.
+        } else if controller.stats_screen {
+            self.draw_stats(controller, glyphs, c, g);
         // Draw win screen.
-        if controller.nonogram.end_game_screen {
+        } else if controller.nonogram.end_game_screen {
             //if true {
             Rectangle::new_round(hex("333333"), 10.0).draw(
                 settings.win_box_rect,
@@ -237,11 +301,14 @@ impl NonogramView {
                 c.transform,
                 g,
             );
-            // Randomly generated artist critique of player's winning image.
+            // Randomly generated artist critique of player's winning image, unless the player ran out of time.
+            let critique_str = if controller.nonogram.time_up {
+                "Time's up! Better luck next time.".to_string()
+            } else {
+                settings.win_critique.clone()
+            };
             let critique_size = 25;
-            let critique_width = glyphs
-                .width(critique_size, &settings.win_critique)
-                .unwrap_or(0.0);
+            let critique_width = glyphs.width(critique_size, &critique_str).unwrap_or(0.0);
             // Cargo fmt makes this this way, and Cargo clippy yells at us about it if we try and do the math
             // directly in the critique_loc array. This isn't my fault.
             let critique_x = settings.win_box_rect[0] + (settings.win_box_rect[2] / 2.0)
@@ -249,7 +316,7 @@ impl NonogramView {
             let critique_loc = [critique_x, settings.win_box_rect[1] - 30.0];
             Text::new_color(settings.text_color, critique_size)
                 .draw(
-                    &settings.win_critique,
+                    &critique_str,
                     glyphs,
                     &c.draw_state,
                     c.transform.trans(critique_loc[0], critique_loc[1]),
@@ -267,7 +334,7 @@ impl NonogramView {
             // Left-aligned timer title.
             Text::new_color(settings.text_color, 25)
                 .draw(
-                    &"TIME",
+                    "TIME",
                     glyphs,
                     &c.draw_state,
                     c.transform.trans(stat_row_x[0], stat_row_y),
@@ -276,7 +343,7 @@ impl NonogramView {
                 .unwrap_or_else(|_| panic!("text draw failed"));
 
             // Right-aligned stat indicating what the timer ended on when previous puzzle was solved.
-            let timer_str = format!("{:02}:{:02}:{:02}", total_hrs, rem_mins, rem_seconds);
+            let timer_str = format_duration(controller.duration());
             let timer_size = 25;
             let timer_width = glyphs.width(timer_size, &timer_str).unwrap_or(0.0);
             Text::new_color(settings.text_color, timer_size)
@@ -293,7 +360,7 @@ impl NonogramView {
             stat_row_y += stat_row_margins[1];
             Text::new_color(settings.text_color, 25)
                 .draw(
-                    &"BLACK",
+                    "BLACK",
                     glyphs,
                     &c.draw_state,
                     c.transform.trans(stat_row_x[0], stat_row_y),
@@ -302,7 +369,7 @@ impl NonogramView {
                 .unwrap_or_else(|_| panic!("text draw failed"));
 
             // Right-aligned count of black/filled cells.
-            let black_count_str = format!("{:>8}", controller.nonogram.goal_black);
+            let black_count_str = format!("{:>8}", controller.goal_black());
             let black_count_size = 25;
             let black_count_width = glyphs
                 .width(black_count_size, &black_count_str)
@@ -324,7 +391,7 @@ impl NonogramView {
             // Left-aligned total cell count title.
             Text::new_color(settings.text_color, 25)
                 .draw(
-                    &"TOTAL",
+                    "TOTAL",
                     glyphs,
                     &c.draw_state,
                     c.transform.trans(stat_row_x[0], stat_row_y),
@@ -333,7 +400,7 @@ impl NonogramView {
                 .unwrap_or_else(|_| panic!("text draw failed"));
 
             // Right-aligned total count of cells.
-            let total_count = controller.nonogram.dimensions[0] * controller.nonogram.dimensions[1];
+            let total_count = controller.dimensions()[0] * controller.dimensions()[1];
             let total_count_str = format!("{}", total_count);
             let total_count_size = 25;
             let total_count_width = glyphs
@@ -356,7 +423,7 @@ impl NonogramView {
             // Left-aligned black_square/total_square ratio title.
             Text::new_color(settings.text_color, 25)
                 .draw(
-                    &"RATIO",
+                    "RATIO",
                     glyphs,
                     &c.draw_state,
                     c.transform.trans(stat_row_x[0], stat_row_y),
@@ -365,7 +432,7 @@ impl NonogramView {
                 .unwrap_or_else(|_| panic!("text draw failed"));
 
             // Right-aligned black_square/total_square ratio title.
-            let black_total_ratio = controller.nonogram.goal_black as f64 / total_count as f64;
+            let black_total_ratio = controller.goal_black() as f64 / total_count as f64;
             let black_total_ratio_str = format!("{:.2}", black_total_ratio);
             let black_total_ratio_size = 25;
             let black_total_ratio_width = glyphs
@@ -388,7 +455,7 @@ impl NonogramView {
             // Left-aligned dimensions title.
             Text::new_color(settings.text_color, 25)
                 .draw(
-                    &"DIMENSIONS",
+                    "DIMENSIONS",
                     glyphs,
                     &c.draw_state,
                     c.transform.trans(stat_row_x[0], stat_row_y),
@@ -399,7 +466,8 @@ impl NonogramView {
             // Right-aligned dimensions.
             let dimensions_str = format!(
                 "{}x{}",
-                controller.nonogram.dimensions[0], controller.nonogram.dimensions[1]
+                controller.dimensions()[0],
+                controller.dimensions()[1]
             );
             let dimensions_size = 25;
             let dimensions_width = glyphs
@@ -416,11 +484,100 @@ impl NonogramView {
                 )
                 .unwrap_or_else(|_| panic!("text draw failed"));
 
+            // New stat row.
+            stat_row_y += stat_row_margins[1];
+
+            // Left-aligned game mode title.
+            Text::new_color(settings.text_color, 25)
+                .draw(
+                    "MODE",
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(stat_row_x[0], stat_row_y),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+
+            // Right-aligned game mode.
+            let mode_str = controller.nonogram.mode.name();
+            let mode_size = 25;
+            let mode_width = glyphs.width(mode_size, mode_str).unwrap_or(0.0);
+            Text::new_color(settings.text_color, mode_size)
+                .draw(
+                    mode_str,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(stat_row_x[1] - mode_width, stat_row_y),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+
+            // New stat row.
+            stat_row_y += stat_row_margins[1];
+
+            // Left-aligned personal best title.
+            Text::new_color(settings.text_color, 25)
+                .draw(
+                    "BEST",
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(stat_row_x[0], stat_row_y),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+
+            // Right-aligned personal best time for these dimensions.
+            let best_str = match controller
+                .stats
+                .get(
+                    controller.dimensions(),
+                    controller.nonogram.mode,
+                    controller.nonogram.colors,
+                    controller.nonogram.ruleset,
+                )
+                .and_then(|size_stats| size_stats.best_time)
+            {
+                Some(best_time) => format_duration(best_time),
+                None => "--:--:--".to_string(),
+            };
+            let best_size = 25;
+            let best_width = glyphs.width(best_size, &best_str).unwrap_or(0.0);
+            Text::new_color(settings.text_color, best_size)
+                .draw(
+                    &best_str,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(stat_row_x[1] - best_width, stat_row_y),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+
+            // Center-aligned personal best notice.
+            if controller.new_personal_best {
+                stat_row_y += stat_row_margins[1];
+                let personal_best_str = "NEW PERSONAL BEST!".to_string();
+                let personal_best_size = 20;
+                let personal_best_width = glyphs
+                    .width(personal_best_size, &personal_best_str)
+                    .unwrap_or(0.0);
+                let personal_best_x = settings.win_box_rect[0] + (settings.win_box_rect[2] / 2.0)
+                    - (personal_best_width / 2.0);
+                Text::new_color(settings.personal_best_color, personal_best_size)
+                    .draw(
+                        &personal_best_str,
+                        glyphs,
+                        &c.draw_state,
+                        c.transform.trans(personal_best_x, stat_row_y),
+                        g,
+                    )
+                    .unwrap_or_else(|_| panic!("text draw failed"));
+            }
+
             // New game button.
             match controller.new_game_button {
                 ButtonInteraction::None => {
                     Rectangle::new_round(hex("9e4c41"), 5.0).draw(
-                        settings.new_game_box,
+                        settings.layout.new_game_box,
                         &c.draw_state,
                         c.transform,
                         g,
@@ -428,7 +585,7 @@ impl NonogramView {
                 }
                 ButtonInteraction::Hover => {
                     Rectangle::new_round(hex("773931"), 5.0).draw(
-                        settings.new_game_box,
+                        settings.layout.new_game_box,
                         &c.draw_state,
                         c.transform,
                         g,
@@ -436,7 +593,7 @@ impl NonogramView {
                 }
                 ButtonInteraction::Select => {
                     Rectangle::new_round(hex("633029"), 5.0).draw(
-                        settings.new_game_box,
+                        settings.layout.new_game_box,
                         &c.draw_state,
                         c.transform,
                         g,
@@ -452,10 +609,11 @@ impl NonogramView {
                 .unwrap_or(0.0);
             // Cargo fmt made these this way, and Cargo clippy yells at us if we try to do this when creating the
             // new_game_button_loc array. This isn't my fault.
-            let new_game_button_x = settings.new_game_box[0] + (settings.new_game_box[2] / 2.0)
+            let new_game_button_x = settings.layout.new_game_box[0]
+                + (settings.layout.new_game_box[2] / 2.0)
                 - (new_game_button_width / 2.0);
-            let new_game_button_y = settings.new_game_box[1]
-                + (settings.new_game_box[3] / 2.0)
+            let new_game_button_y = settings.layout.new_game_box[1]
+                + (settings.layout.new_game_box[3] / 2.0)
                 + ((new_game_button_size as f64 * 0.75) / 2.0);
             let new_game_button_loc = [new_game_button_x, new_game_button_y];
             Text::new_color(settings.text_color, new_game_button_size)
@@ -469,11 +627,100 @@ impl NonogramView {
                 )
                 .unwrap_or_else(|_| panic!("text draw failed"));
 
-            // Draw board background.
+            // Save picture button, along with how the picture will be saved. Pictures can't be saved when time ran
+            // out, since they aren't solved.
+            if !controller.nonogram.time_up {
+                let save_picture_color = match controller.save_picture_button {
+                    ButtonInteraction::None => hex("3f6f9e"),
+                    ButtonInteraction::Hover => hex("325a82"),
+                    ButtonInteraction::Select => hex("294a6b"),
+                };
+                Rectangle::new_round(save_picture_color, 5.0).draw(
+                    settings.layout.save_picture_box,
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+
+                let save_picture_str = "SAVE PICTURE".to_string();
+                let save_picture_size = 25;
+                let save_picture_width = glyphs
+                    .width(save_picture_size, &save_picture_str)
+                    .unwrap_or(0.0);
+                let save_picture_x = settings.layout.save_picture_box[0]
+                    + (settings.layout.save_picture_box[2] / 2.0)
+                    - (save_picture_width / 2.0);
+                let save_picture_y = settings.layout.save_picture_box[1]
+                    + (settings.layout.save_picture_box[3] / 2.0)
+                    + ((save_picture_size as f64 * 0.75) / 2.0);
+                Text::new_color(settings.text_color, save_picture_size)
+                    .draw(
+                        &save_picture_str,
+                        glyphs,
+                        &c.draw_state,
+                        c.transform.trans(save_picture_x, save_picture_y),
+                        g,
+                    )
+                    .unwrap_or_else(|_| panic!("text draw failed"));
+
+                // Center-aligned picture options, followed by where the last picture was saved.
+                let mut picture_row_y = settings.layout.save_picture_box[1]
+                    + settings.layout.save_picture_box[3]
+                    + 25.0;
+                let bound = |control: Control| {
+                    controller
+                        .controls
+                        .get(control)
+                        .first()
+                        .map_or("-".to_string(), |&button| {
+                            button_name(button).to_uppercase()
+                        })
+                };
+                let mut picture_rows = vec![format!(
+                    "{} PX CELLS ({}/{}), {} ({})",
+                    PICTURE_SCALES[controller.picture_scale],
+                    bound(Control::SmallerPicture),
+                    bound(Control::BiggerPicture),
+                    if controller.picture_clues {
+                        "WITH HINTS"
+                    } else {
+                        "NO HINTS"
+                    },
+                    bound(Control::TogglePictureClues)
+                )];
+                if let Some(message) = &controller.picture_message {
+                    picture_rows.push(message.clone());
+                }
+                for picture_row_str in picture_rows {
+                    let picture_row_size = 15;
+                    let picture_row_width = glyphs
+                        .width(picture_row_size, &picture_row_str)
+                        .unwrap_or(0.0);
+                    let picture_row_x = settings.layout.save_picture_box[0]
+                        + (settings.layout.save_picture_box[2] / 2.0)
+                        - (picture_row_width / 2.0);
+                    Text::new_color(settings.text_color, picture_row_size)
+                        .draw(
+                            &picture_row_str,
+                            glyphs,
+                            &c.draw_state,
+                            c.transform.trans(picture_row_x, picture_row_y),
+                            g,
+                        )
+                        .unwrap_or_else(|_| panic!("text draw failed"));
+                    picture_row_y += 20.0;
+                }
+            }
+
+            // Draw board background. A clip shows its whole picture, which is scaled down to the same height as a
+            // single board.
+            let picture = controller.picture();
+            let picture_dimensions = controller.dimensions();
+            let win_cell_size = settings.win_board_dimensions[1] / picture_dimensions[1] as f64;
             let mut board_rect = [
                 settings.win_box_rect[0],
                 settings.win_box_rect[1] - 300.0,
-                settings.win_board_dimensions[0],
+                win_cell_size * picture_dimensions[0] as f64,
                 settings.win_board_dimensions[1],
             ];
 
@@ -487,300 +734,459 @@ impl NonogramView {
             );
 
             // Draw the game winning image.
-            for col in 0..settings.cell_dimensions[0] {
-                for row in 0..settings.cell_dimensions[1] {
-                    let value = controller.nonogram.get([col, row]);
-                    let pos = [
-                        col as f64 * settings.win_cell_size,
-                        row as f64 * settings.win_cell_size,
-                    ];
-                    if value == Cell::Filled {
+            for (col, cells) in picture.iter().enumerate() {
+                for (row, &value) in cells.iter().enumerate() {
+                    let pos = [col as f64 * win_cell_size, row as f64 * win_cell_size];
+                    if let Some(color) = value.color() {
                         let cell_rect = [
                             board_rect[0] + pos[0],
                             board_rect[1] + pos[1],
-                            settings.win_cell_size,
-                            settings.win_cell_size,
+                            win_cell_size,
+                            win_cell_size,
                         ];
-                        Rectangle::new(settings.filled_cell_background_color).draw(
-                            cell_rect,
-                            &c.draw_state,
-                            c.transform,
-                            g,
-                        );
+                        self.draw_filled_cell(value, color, cell_rect, c, g);
                     }
                 }
             }
         } else {
             let board_rect = [
-                settings.position[0],
-                settings.position[1],
-                settings.board_dimensions[0],
-                settings.board_dimensions[1],
+                settings.layout.position[0],
+                settings.layout.position[1],
+                settings.layout.board_dimensions[0],
+                settings.layout.board_dimensions[1],
             ];
 
-            // Draw board background.
-            Rectangle::new(settings.background_color).draw(
-                board_rect,
-                &c.draw_state,
-                c.transform,
-                g,
-            );
-
-            // Draw filled cell background.
-            // We calculate the height of text by multiplying font size by 0.75 in order to convert between pixels and points.
-            let mark_size = (settings.cell_size / 1.5) as u32;
-            let mark_width = mark_glyphs.width(mark_size, &"x").unwrap_or(0.0);
-            let mark_loc = [
-                (settings.cell_size / 2.0) - (mark_width as f64 / 2.0),
-                (settings.cell_size / 2.0) + ((mark_size as f64 * 0.75) / 2.0),
-            ];
-            let mark_text = Text::new_color(settings.marked_cell_background_color, mark_size);
-
-            for col in 0..settings.cell_dimensions[0] {
-                for row in 0..settings.cell_dimensions[1] {
-                    let value = controller.nonogram.get([col, row]);
-                    let pos = [
-                        col as f64 * settings.cell_size,
-                        row as f64 * settings.cell_size,
-                    ];
-                    if value == Cell::Filled {
-                        let cell_rect = [
-                            settings.position[0] + pos[0],
-                            settings.position[1] + pos[1],
-                            settings.cell_size,
-                            settings.cell_size,
+            if controller.nonogram.is_paused() {
+                // Hide the board while paused so it can't be studied without the timer running.
+                Rectangle::new(settings.paused_board_color).draw(
+                    board_rect,
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+
+                let paused_str = "PAUSED".to_string();
+                let paused_size = 50;
+                let paused_width = glyphs.width(paused_size, &paused_str).unwrap_or(0.0);
+                let paused_x = board_rect[0] + (board_rect[2] / 2.0) - (paused_width / 2.0);
+                let paused_y = board_rect[1] + (board_rect[3] / 2.0);
+                Text::new_color(settings.text_color, paused_size)
+                    .draw(
+                        &paused_str,
+                        glyphs,
+                        &c.draw_state,
+                        c.transform.trans(paused_x, paused_y),
+                        g,
+                    )
+                    .unwrap_or_else(|_| panic!("text draw failed"));
+
+                let resume_str = "Press P or click the board to resume".to_string();
+                let resume_size = 20;
+                let resume_width = glyphs.width(resume_size, &resume_str).unwrap_or(0.0);
+                let resume_x = board_rect[0] + (board_rect[2] / 2.0) - (resume_width / 2.0);
+                Text::new_color(settings.text_color, resume_size)
+                    .draw(
+                        &resume_str,
+                        glyphs,
+                        &c.draw_state,
+                        c.transform.trans(resume_x, paused_y + 40.0),
+                        g,
+                    )
+                    .unwrap_or_else(|_| panic!("text draw failed"));
+            } else {
+                // Draw board background.
+                Rectangle::new(settings.background_color).draw(
+                    board_rect,
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+
+                // Draw filled cell background.
+                // We calculate the height of text by multiplying font size by 0.75 in order to convert between pixels and points.
+                let mark_size = (settings.cell_size / 1.5) as u32;
+                let mark_width = mark_glyphs.width(mark_size, "x").unwrap_or(0.0);
+                let mark_loc = [
+                    (settings.cell_size / 2.0) - (mark_width / 2.0),
+                    (settings.cell_size / 2.0) + ((mark_size as f64 * 0.75) / 2.0),
+                ];
+                let mark_text = Text::new_color(settings.marked_cell_background_color, mark_size);
+
+                for col in 0..settings.cell_dimensions[0] {
+                    for row in 0..settings.cell_dimensions[1] {
+                        let value = controller.nonogram.get([col, row]);
+                        let pos = [
+                            col as f64 * settings.cell_size,
+                            row as f64 * settings.cell_size,
                         ];
-                        Rectangle::new(settings.filled_cell_background_color).draw(
-                            cell_rect,
-                            &c.draw_state,
-                            c.transform,
-                            g,
-                        );
-                    } else if value == Cell::Marked {
-                        mark_text
-                            .draw(
-                                "x",
-                                mark_glyphs,
+                        if let Some(color) = value.color() {
+                            let cell_rect = [
+                                settings.layout.position[0] + pos[0],
+                                settings.layout.position[1] + pos[1],
+                                settings.cell_size,
+                                settings.cell_size,
+                            ];
+                            self.draw_filled_cell(value, color, cell_rect, c, g);
+                        } else if value == Cell::Maybe {
+                            // Tentative fills are drawn smaller than real ones so they stand out.
+                            let inset = settings.cell_size * 0.2;
+                            let cell_rect = [
+                                settings.layout.position[0] + pos[0] + inset,
+                                settings.layout.position[1] + pos[1] + inset,
+                                settings.cell_size - inset * 2.0,
+                                settings.cell_size - inset * 2.0,
+                            ];
+                            Rectangle::new(settings.maybe_cell_background_color).draw(
+                                cell_rect,
                                 &c.draw_state,
-                                c.transform.trans(
-                                    settings.position[0] + pos[0] + mark_loc[0],
-                                    settings.position[1] + pos[1] + mark_loc[1],
-                                ),
+                                c.transform,
                                 g,
-                            )
-                            .unwrap_or_else(|_| panic!("text draw failed"));
+                            );
+                        } else if value == Cell::Marked {
+                            mark_text
+                                .draw(
+                                    "x",
+                                    mark_glyphs,
+                                    &c.draw_state,
+                                    c.transform.trans(
+                                        settings.layout.position[0] + pos[0] + mark_loc[0],
+                                        settings.layout.position[1] + pos[1] + mark_loc[1],
+                                    ),
+                                    g,
+                                )
+                                .unwrap_or_else(|_| panic!("text draw failed"));
+                        }
                     }
                 }
-            }
 
-            // Draw column and row hint numbers.
-            // We calculate the height of text by multiplying font size by 0.75 in order to convert between pixels and points.
-            let hint_num_size = 15;
-            let hint_reg = Text::new_color(settings.text_color, hint_num_size);
-            let hint_cross = Text::new_color(hex("666666"), hint_num_size);
-
-            // Draw column hint numbers.
-            // Currently this logic goes through the effort of finding the width of each individual number
-            // in order to try and center all the numbers in a column. This might not be worth the effort,
-            // as it's only really noticeable when the numbers start hitting the double digits.
-            for k in 0..settings.cell_dimensions[0] as usize {
-                let mut num_pos = 0;
-                if controller.nonogram.goal_nums[0][k][0] == 0 {
-                    let ch = "0".to_string();
-                    let hint_num_width = glyphs.width(hint_num_size, &ch).unwrap_or(0.0);
-                    let col_num_loc = (settings.cell_size / 2.0) - (hint_num_width as f64 / 2.0);
-                    let col_hint_x =
-                        settings.position[0] + (k as f64 * settings.cell_size) + col_num_loc;
-                    let col_hint_y = settings.position[0] - num_pos as f64 * 20.0 - 80.0;
-                    if controller.nonogram.current_nums[0][k][0] == 0 {
-                        hint_cross
-                            .draw(
-                                &ch,
-                                glyphs,
-                                &c.draw_state,
-                                c.transform.trans(col_hint_x, col_hint_y),
-                                g,
-                            )
-                            .unwrap_or_else(|_| panic!("text draw failed"));
-                    } else {
-                        hint_reg
-                            .draw(
-                                &ch,
-                                glyphs,
-                                &c.draw_state,
-                                c.transform.trans(col_hint_x, col_hint_y),
-                                g,
-                            )
-                            .unwrap_or_else(|_| panic!("text draw failed"));
+                // Highlight the cells that the line or rectangle tool will change once the mouse button is released.
+                for ind in controller.tool_cells() {
+                    let cell_rect = [
+                        settings.layout.position[0] + ind[0] as f64 * settings.cell_size,
+                        settings.layout.position[1] + ind[1] as f64 * settings.cell_size,
+                        settings.cell_size,
+                        settings.cell_size,
+                    ];
+                    Rectangle::new(settings.tool_preview_color).draw(
+                        cell_rect,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+
+                // Draw column and row hint numbers.
+                // We calculate the height of text by multiplying font size by 0.75 in order to convert between pixels and points.
+                let hint_num_size = 15;
+                let hint_reg = Text::new_color(settings.text_color, hint_num_size);
+                let hint_cross = Text::new_color(hex("666666"), hint_num_size);
+
+                // Draw column hint numbers.
+                // Currently this logic goes through the effort of finding the width of each individual number
+                // in order to try and center all the numbers in a column. This might not be worth the effort,
+                // as it's only really noticeable when the numbers start hitting the double digits.
+                for k in 0..settings.cell_dimensions[0] {
+                    // The hint numbers of a merged pair are all drawn by the first column.
+                    if k > 0 && controller.nonogram.is_merged(0, k - 1) {
+                        continue;
                     }
-                } else {
-                    for i in (0..controller.nonogram.nums_per[0] as usize).rev() {
-                        let hint_val = controller.nonogram.goal_nums[0][k][i];
-                        // Only draw column numbers that aren't 0.
-                        if hint_val != 0 {
-                            let ch = hint_val.abs().to_string();
-                            let hint_num_width = glyphs.width(hint_num_size, &ch).unwrap_or(0.0);
-                            let col_num_loc =
-                                (settings.cell_size / 2.0) - (hint_num_width as f64 / 2.0);
-                            let col_hint_x = settings.position[0]
-                                + (k as f64 * settings.cell_size)
-                                + col_num_loc;
-                            let col_hint_y = settings.position[0] - num_pos as f64 * 20.0 - 80.0;
-
-                            // Either draw a normal number, or draw a crossout number.
-                            if hint_val > 0 {
-                                hint_reg
-                                    .draw(
-                                        &ch,
-                                        glyphs,
-                                        &c.draw_state,
-                                        c.transform.trans(col_hint_x, col_hint_y),
+                    let mut num_pos = 0;
+                    if controller.nonogram.goal_nums[0][k][0] == 0 {
+                        let ch = "0".to_string();
+                        let hint_num_width = glyphs.width(hint_num_size, &ch).unwrap_or(0.0);
+                        let col_num_loc = (settings.cell_size / 2.0) - (hint_num_width / 2.0);
+                        let col_hint_x = settings.layout.position[0]
+                            + (k as f64 * settings.cell_size)
+                            + col_num_loc;
+                        let col_hint_y = column_hint_y(settings.layout.position, num_pos);
+                        if controller.nonogram.current_nums[0][k][0] == 0 {
+                            hint_cross
+                                .draw(
+                                    &ch,
+                                    glyphs,
+                                    &c.draw_state,
+                                    c.transform.trans(col_hint_x, col_hint_y),
+                                    g,
+                                )
+                                .unwrap_or_else(|_| panic!("text draw failed"));
+                        } else {
+                            hint_reg
+                                .draw(
+                                    &ch,
+                                    glyphs,
+                                    &c.draw_state,
+                                    c.transform.trans(col_hint_x, col_hint_y),
+                                    g,
+                                )
+                                .unwrap_or_else(|_| panic!("text draw failed"));
+                        }
+                    } else {
+                        for i in (0..controller.nonogram.nums_per[0] as usize).rev() {
+                            let hint_val = controller.nonogram.goal_nums[0][k][i];
+                            // Only draw column numbers that aren't 0.
+                            if hint_val != 0 {
+                                let ch = hint_val.abs().to_string();
+                                let hint_num_width =
+                                    glyphs.width(hint_num_size, &ch).unwrap_or(0.0);
+                                let col_num_loc =
+                                    (settings.cell_size / 2.0) - (hint_num_width / 2.0);
+                                let ends = controller.nonogram.goal_ends[0][k][i];
+                                let col_hint_x = settings.layout.position[0]
+                                    + (k as f64 * settings.cell_size)
+                                    + col_num_loc
+                                    + mega_shift(ends, settings.cell_size);
+                                let col_hint_y = column_hint_y(settings.layout.position, num_pos);
+
+                                // Either draw a normal number, or draw a crossout number.
+                                if !controller.nonogram.is_crossed_out(0, k, i) {
+                                    let hint_color = self.hint_color(
+                                        controller.nonogram.goal_colors[0][k][i] as usize,
+                                    );
+                                    Text::new_color(hint_color, hint_num_size)
+                                        .draw(
+                                            &ch,
+                                            glyphs,
+                                            &c.draw_state,
+                                            c.transform.trans(col_hint_x, col_hint_y),
+                                            g,
+                                        )
+                                        .unwrap_or_else(|_| panic!("text draw failed"));
+                                    self.draw_hint_ends(
+                                        controller.nonogram.goal_ends[0][k][i],
+                                        [col_hint_x, col_hint_y],
+                                        hint_num_width,
+                                        hint_color,
+                                        c,
                                         g,
-                                    )
-                                    .unwrap_or_else(|_| panic!("text draw failed"));
-                            } else {
-                                hint_cross
-                                    .draw(
-                                        &ch,
-                                        glyphs,
-                                        &c.draw_state,
-                                        c.transform.trans(col_hint_x, col_hint_y),
+                                    );
+                                } else {
+                                    hint_cross
+                                        .draw(
+                                            &ch,
+                                            glyphs,
+                                            &c.draw_state,
+                                            c.transform.trans(col_hint_x, col_hint_y),
+                                            g,
+                                        )
+                                        .unwrap_or_else(|_| panic!("text draw failed"));
+                                    self.draw_hint_ends(
+                                        controller.nonogram.goal_ends[0][k][i],
+                                        [col_hint_x, col_hint_y],
+                                        hint_num_width,
+                                        hex("666666"),
+                                        c,
                                         g,
-                                    )
-                                    .unwrap_or_else(|_| panic!("text draw failed"));
+                                    );
+                                }
+                                if ends & CLUE_MEGA != 0 {
+                                    let outline_color =
+                                        if controller.nonogram.is_crossed_out(0, k, i) {
+                                            hex("666666")
+                                        } else {
+                                            self.hint_color(
+                                                controller.nonogram.goal_colors[0][k][i] as usize,
+                                            )
+                                        };
+                                    self.draw_mega_outline(
+                                        [
+                                            settings.layout.position[0]
+                                                + (k as f64 * settings.cell_size)
+                                                + 3.0,
+                                            col_hint_y - 15.0,
+                                            2.0 * settings.cell_size - 6.0,
+                                            19.0,
+                                        ],
+                                        outline_color,
+                                        c,
+                                        g,
+                                    );
+                                }
+                                num_pos += 1;
                             }
-                            num_pos += 1;
                         }
                     }
                 }
-            }
 
-            // Draw row hint numbers.
-            let row_num_loc = (settings.cell_size / 2.0) + ((hint_num_size as f64 * 0.75) / 2.0);
-            let mut row_hint_y = settings.position[1] + row_num_loc;
-            for k in 0..settings.cell_dimensions[1] as usize {
-                let mut num_pos = 0;
-                if controller.nonogram.goal_nums[1][k][0] == 0 {
-                    let row_hint_x = settings.position[0] - num_pos as f64 * 20.0 - 25.0;
-                    if controller.nonogram.current_nums[1][k][0] == 0 {
-                        hint_cross
-                            .draw(
-                                &"0",
-                                glyphs,
-                                &c.draw_state,
-                                c.transform.trans(row_hint_x, row_hint_y),
-                                g,
-                            )
-                            .unwrap_or_else(|_| panic!("text draw failed"));
-                    } else {
-                        hint_reg
-                            .draw(
-                                &"0",
-                                glyphs,
-                                &c.draw_state,
-                                c.transform.trans(row_hint_x, row_hint_y),
-                                g,
-                            )
-                            .unwrap_or_else(|_| panic!("text draw failed"));
+                // Draw row hint numbers.
+                let row_num_loc =
+                    (settings.cell_size / 2.0) + ((hint_num_size as f64 * 0.75) / 2.0);
+                let mut row_hint_y = settings.layout.position[1] + row_num_loc;
+                for k in 0..settings.cell_dimensions[1] {
+                    // The hint numbers of a merged pair are all drawn by the first row.
+                    if k > 0 && controller.nonogram.is_merged(1, k - 1) {
+                        row_hint_y += settings.cell_size;
+                        continue;
                     }
-                } else {
-                    for i in (0..controller.nonogram.nums_per[1] as usize).rev() {
-                        let hint_val = controller.nonogram.goal_nums[1][k][i];
-
-                        // Only draw row numbers that aren't 0.
-                        if hint_val != 0 {
-                            let ch = hint_val.abs().to_string();
-                            let row_hint_x = settings.position[0] - num_pos as f64 * 20.0 - 25.0;
-
-                            // Either draw a normal number, or draw a crossout number.
-                            if hint_val > 0 {
-                                hint_reg
-                                    .draw(
-                                        &ch,
-                                        glyphs,
-                                        &c.draw_state,
-                                        c.transform.trans(row_hint_x, row_hint_y),
+                    let mut num_pos = 0;
+                    if controller.nonogram.goal_nums[1][k][0] == 0 {
+                        let row_hint_x = row_hint_x(settings.layout.position, num_pos);
+                        if controller.nonogram.current_nums[1][k][0] == 0 {
+                            hint_cross
+                                .draw(
+                                    "0",
+                                    glyphs,
+                                    &c.draw_state,
+                                    c.transform.trans(row_hint_x, row_hint_y),
+                                    g,
+                                )
+                                .unwrap_or_else(|_| panic!("text draw failed"));
+                        } else {
+                            hint_reg
+                                .draw(
+                                    "0",
+                                    glyphs,
+                                    &c.draw_state,
+                                    c.transform.trans(row_hint_x, row_hint_y),
+                                    g,
+                                )
+                                .unwrap_or_else(|_| panic!("text draw failed"));
+                        }
+                    } else {
+                        for i in (0..controller.nonogram.nums_per[1] as usize).rev() {
+                            let hint_val = controller.nonogram.goal_nums[1][k][i];
+
+                            // Only draw row numbers that aren't 0.
+                            if hint_val != 0 {
+                                let ch = hint_val.abs().to_string();
+                                let hint_num_width =
+                                    glyphs.width(hint_num_size, &ch).unwrap_or(0.0);
+                                let row_hint_x = row_hint_x(settings.layout.position, num_pos);
+                                let ends = controller.nonogram.goal_ends[1][k][i];
+                                let row_hint_y = row_hint_y + mega_shift(ends, settings.cell_size);
+
+                                // Either draw a normal number, or draw a crossout number.
+                                if !controller.nonogram.is_crossed_out(1, k, i) {
+                                    let hint_color = self.hint_color(
+                                        controller.nonogram.goal_colors[1][k][i] as usize,
+                                    );
+                                    Text::new_color(hint_color, hint_num_size)
+                                        .draw(
+                                            &ch,
+                                            glyphs,
+                                            &c.draw_state,
+                                            c.transform.trans(row_hint_x, row_hint_y),
+                                            g,
+                                        )
+                                        .unwrap_or_else(|_| panic!("text draw failed"));
+                                    self.draw_hint_ends(
+                                        controller.nonogram.goal_ends[1][k][i],
+                                        [row_hint_x, row_hint_y],
+                                        hint_num_width,
+                                        hint_color,
+                                        c,
                                         g,
-                                    )
-                                    .unwrap_or_else(|_| panic!("text draw failed"));
-                            } else {
-                                hint_cross
-                                    .draw(
-                                        &ch,
-                                        glyphs,
-                                        &c.draw_state,
-                                        c.transform.trans(row_hint_x, row_hint_y),
+                                    );
+                                } else {
+                                    hint_cross
+                                        .draw(
+                                            &ch,
+                                            glyphs,
+                                            &c.draw_state,
+                                            c.transform.trans(row_hint_x, row_hint_y),
+                                            g,
+                                        )
+                                        .unwrap_or_else(|_| panic!("text draw failed"));
+                                    self.draw_hint_ends(
+                                        controller.nonogram.goal_ends[1][k][i],
+                                        [row_hint_x, row_hint_y],
+                                        hint_num_width,
+                                        hex("666666"),
+                                        c,
+                                        g,
+                                    );
+                                }
+                                if ends & CLUE_MEGA != 0 {
+                                    let outline_color =
+                                        if controller.nonogram.is_crossed_out(1, k, i) {
+                                            hex("666666")
+                                        } else {
+                                            self.hint_color(
+                                                controller.nonogram.goal_colors[1][k][i] as usize,
+                                            )
+                                        };
+                                    self.draw_mega_outline(
+                                        [
+                                            row_hint_x - 4.0,
+                                            settings.layout.position[1]
+                                                + (k as f64 * settings.cell_size)
+                                                + 3.0,
+                                            hint_num_width + 8.0,
+                                            2.0 * settings.cell_size - 6.0,
+                                        ],
+                                        outline_color,
+                                        c,
                                         g,
-                                    )
-                                    .unwrap_or_else(|_| panic!("text draw failed"));
+                                    );
+                                }
+                                num_pos += 1;
                             }
-                            num_pos += 1;
                         }
                     }
+                    row_hint_y += settings.cell_size;
                 }
-                row_hint_y += settings.cell_size;
-            }
 
-            // Draw cell borders.
-            let cell_edge = Line::new(settings.cell_edge_color, settings.cell_edge_radius);
-            for i in 0..controller.nonogram.dimensions[0] {
-                // Skip lines that are covered by sections.
-                if (i % 5) == 0 {
-                    continue;
-                }
+                // Draw cell borders.
+                let cell_edge = Line::new(settings.cell_edge_color, settings.cell_edge_radius);
+                for i in 0..controller.nonogram.dimensions[0] {
+                    // Skip lines that are covered by sections.
+                    if (i % 5) == 0 {
+                        continue;
+                    }
 
-                let x = settings.position[0]
-                    + i as f64 / controller.nonogram.dimensions[0] as f64
-                        * settings.cell_size
-                        * controller.nonogram.dimensions[0] as f64;
-                let y2 = settings.position[1]
-                    + settings.cell_size * controller.nonogram.dimensions[1] as f64;
+                    let x = settings.layout.position[0]
+                        + i as f64 / controller.nonogram.dimensions[0] as f64
+                            * settings.cell_size
+                            * controller.nonogram.dimensions[0] as f64;
+                    let y2 = settings.layout.position[1]
+                        + settings.cell_size * controller.nonogram.dimensions[1] as f64;
 
-                let vline = [x, settings.position[1], x, y2];
-                cell_edge.draw(vline, &c.draw_state, c.transform, g);
-            }
-            for i in 0..controller.nonogram.dimensions[1] {
-                // Skip lines that are covered by sections.
-                if (i % 5) == 0 {
-                    continue;
+                    let vline = [x, settings.layout.position[1], x, y2];
+                    cell_edge.draw(vline, &c.draw_state, c.transform, g);
                 }
+                for i in 0..controller.nonogram.dimensions[1] {
+                    // Skip lines that are covered by sections.
+                    if (i % 5) == 0 {
+                        continue;
+                    }
 
-                let y = settings.position[1]
-                    + i as f64 / controller.nonogram.dimensions[1] as f64
-                        * settings.cell_size
-                        * controller.nonogram.dimensions[1] as f64;
-                let x2 = settings.position[0]
-                    + settings.cell_size * controller.nonogram.dimensions[0] as f64;
+                    let y = settings.layout.position[1]
+                        + i as f64 / controller.nonogram.dimensions[1] as f64
+                            * settings.cell_size
+                            * controller.nonogram.dimensions[1] as f64;
+                    let x2 = settings.layout.position[0]
+                        + settings.cell_size * controller.nonogram.dimensions[0] as f64;
 
-                let hline = [settings.position[0], y, x2, y];
-                cell_edge.draw(hline, &c.draw_state, c.transform, g);
-            }
+                    let hline = [settings.layout.position[0], y, x2, y];
+                    cell_edge.draw(hline, &c.draw_state, c.transform, g);
+                }
 
-            // Draw section borders.
-            let section_edge = Line::new(settings.section_edge_color, settings.section_edge_radius);
-            for i in 1..(controller.nonogram.dimensions[0] / 5) {
-                // Set up coordinates.
-                let x = settings.position[0]
-                    + i as f64 / (controller.nonogram.dimensions[0] / 5) as f64
-                        * settings.cell_size
-                        * controller.nonogram.dimensions[0] as f64;
-                let y2 = settings.position[1]
-                    + settings.cell_size * controller.nonogram.dimensions[1] as f64;
-
-                let vline = [x, settings.position[1], x, y2];
-                section_edge.draw(vline, &c.draw_state, c.transform, g);
-            }
-            for i in 1..(controller.nonogram.dimensions[1] / 5) {
-                // Set up coordinates.
-                let y = settings.position[1]
-                    + i as f64 / (controller.nonogram.dimensions[1] / 5) as f64
-                        * settings.cell_size
-                        * controller.nonogram.dimensions[1] as f64;
-                let x2 = settings.position[0]
-                    + settings.cell_size * controller.nonogram.dimensions[0] as f64;
-
-                let hline = [settings.position[0], y, x2, y];
-                section_edge.draw(hline, &c.draw_state, c.transform, g);
+                // Draw section borders.
+                let section_edge =
+                    Line::new(settings.section_edge_color, settings.section_edge_radius);
+                for i in 1..(controller.nonogram.dimensions[0] / 5) {
+                    // Set up coordinates.
+                    let x = settings.layout.position[0]
+                        + i as f64 / (controller.nonogram.dimensions[0] / 5) as f64
+                            * settings.cell_size
+                            * controller.nonogram.dimensions[0] as f64;
+                    let y2 = settings.layout.position[1]
+                        + settings.cell_size * controller.nonogram.dimensions[1] as f64;
+
+                    let vline = [x, settings.layout.position[1], x, y2];
+                    section_edge.draw(vline, &c.draw_state, c.transform, g);
+                }
+                for i in 1..(controller.nonogram.dimensions[1] / 5) {
+                    // Set up coordinates.
+                    let y = settings.layout.position[1]
+                        + i as f64 / (controller.nonogram.dimensions[1] / 5) as f64
+                            * settings.cell_size
+                            * controller.nonogram.dimensions[1] as f64;
+                    let x2 = settings.layout.position[0]
+                        + settings.cell_size * controller.nonogram.dimensions[0] as f64;
+
+                    let hline = [settings.layout.position[0], y, x2, y];
+                    section_edge.draw(hline, &c.draw_state, c.transform, g);
+                }
             }
 
             // Draw board edge.
@@ -792,7 +1198,7 @@ impl NonogramView {
             );
 
             // Draw info box.
-            let info_box_rect = [20.0, 70.0, 250.0, 150.0];
+            let info_box_rect = [20.0, 70.0, 250.0, 200.0];
             Rectangle::new_round(hex("333333"), 10.0).draw(
                 info_box_rect,
                 &c.draw_state,
@@ -866,8 +1272,16 @@ impl NonogramView {
                 )
                 .unwrap_or_else(|_| panic!("text draw failed"));
 
-            // Draw timer title.
-            let timer_title_str = "TIMER".to_string();
+            // Draw timer title. Countdown mode shows the time that's left, and time attack mode shows how much
+            // of the timer comes from penalties.
+            let timer_title_str = match controller.nonogram.mode {
+                GameMode::Classic => "TIMER".to_string(),
+                GameMode::Countdown => "TIME LEFT".to_string(),
+                GameMode::TimeAttack => format!(
+                    "TIMER (+{} PENALTY)",
+                    format_duration(controller.nonogram.penalty_duration)
+                ),
+            };
             let timer_title_size = 12;
             let timer_title_width = glyphs
                 .width(timer_title_size, &timer_title_str)
@@ -887,13 +1301,18 @@ impl NonogramView {
                 .unwrap_or_else(|_| panic!("text draw failed"));
 
             // Draw timer.
-            let timer_str = format!("{:02}:{:02}:{:02}", total_hrs, rem_mins, rem_seconds);
+            let timer_str = match controller.nonogram.time_limit() {
+                Some(time_limit) => {
+                    format_duration(time_limit.saturating_sub(controller.nonogram.duration))
+                }
+                None => format_duration(controller.nonogram.duration),
+            };
             let timer_size = 50;
 
             // Unlike with the other drawn text, we don't use the actual string here,
             // because we don't want it to keep changing its location subtly for every
             // second that passes.
-            let timer_width = glyphs.width(timer_size, &"00:00:00").unwrap_or(0.0);
+            let timer_width = glyphs.width(timer_size, "00:00:00").unwrap_or(0.0);
             let timer_loc = [
                 info_box_rect[0] + (info_box_rect[2] / 2.0) - (timer_width / 2.0),
                 200.0,
@@ -908,20 +1327,66 @@ impl NonogramView {
                 )
                 .unwrap_or_else(|_| panic!("text draw failed"));
 
+            // Draw game mode title.
+            let mode_title_str = "MODE".to_string();
+            let mode_title_size = 12;
+            let mode_title_width = glyphs
+                .width(mode_title_size, &mode_title_str)
+                .unwrap_or(0.0);
+            let mode_title_loc = [
+                info_box_rect[0] + (info_box_rect[2] / 2.0) - (mode_title_width / 2.0),
+                220.0,
+            ];
+            Text::new_color(settings.text_color, mode_title_size)
+                .draw(
+                    &mode_title_str,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(mode_title_loc[0], mode_title_loc[1]),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+
+            // Draw game mode.
+            let mode_str = controller.nonogram.mode.name();
+            let mode_size = 25;
+            let mode_width = glyphs.width(mode_size, mode_str).unwrap_or(0.0);
+            let mode_loc = [
+                info_box_rect[0] + (info_box_rect[2] / 2.0) - (mode_width / 2.0),
+                250.0,
+            ];
+            Text::new_color(settings.text_color, mode_size)
+                .draw(
+                    mode_str,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(mode_loc[0], mode_loc[1]),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+
             // Draw selected cell border.
-            if let Some(ind) = controller.nonogram.selected_cell {
+            if let (Some(ind), false) = (
+                controller.nonogram.selected_cell,
+                controller.nonogram.is_paused(),
+            ) {
                 let pos = [
                     ind[0] as f64 * settings.cell_size,
                     ind[1] as f64 * settings.cell_size,
                 ];
                 let cell_rect = [
-                    settings.position[0] + pos[0],
-                    settings.position[1] + pos[1],
+                    settings.layout.position[0] + pos[0],
+                    settings.layout.position[1] + pos[1],
                     settings.cell_size,
                     settings.cell_size,
                 ];
+                let border_color = if controller.pencil {
+                    settings.maybe_cell_background_color
+                } else {
+                    settings.selected_cell_border_color
+                };
                 Rectangle::new_round_border(
-                    settings.selected_cell_border_color,
+                    border_color,
                     settings.selected_cell_border_round_radius,
                     settings.selected_cell_border_radius,
                 )
@@ -931,22 +1396,22 @@ impl NonogramView {
             // Dropdown size selection menu.
             let dimensions_size = 25;
             let dimensions_pos = [
-                settings.dimensions_dropdown_menu_box[0] + 5.0,
-                settings.dimensions_dropdown_menu_box[1]
-                    + (settings.dimensions_dropdown_menu_box[3] / 2.0)
+                settings.layout.dimensions_dropdown_menu_box[0] + 5.0,
+                settings.layout.dimensions_dropdown_menu_box[1]
+                    + (settings.layout.dimensions_dropdown_menu_box[3] / 2.0)
                     + ((dimensions_size as f64 * 0.75) / 2.0),
             ];
 
             match controller.dimensions_dropdown_menu {
                 ButtonInteraction::None => {
                     Rectangle::new_round(hex("333333"), 5.0).draw(
-                        settings.dimensions_dropdown_menu_box,
+                        settings.layout.dimensions_dropdown_menu_box,
                         &c.draw_state,
                         c.transform,
                         g,
                     );
                     Rectangle::new_round_border(hex("333333"), 5.0, 2.0).draw(
-                        settings.dimensions_dropdown_menu_box,
+                        settings.layout.dimensions_dropdown_menu_box,
                         &c.draw_state,
                         c.transform,
                         g,
@@ -954,13 +1419,13 @@ impl NonogramView {
                 }
                 ButtonInteraction::Hover => {
                     Rectangle::new_round(hex("2D2D2D"), 5.0).draw(
-                        settings.dimensions_dropdown_menu_box,
+                        settings.layout.dimensions_dropdown_menu_box,
                         &c.draw_state,
                         c.transform,
                         g,
                     );
                     Rectangle::new_round_border(hex("2D2D2D"), 5.0, 2.0).draw(
-                        settings.dimensions_dropdown_menu_box,
+                        settings.layout.dimensions_dropdown_menu_box,
                         &c.draw_state,
                         c.transform,
                         g,
@@ -980,7 +1445,7 @@ impl NonogramView {
                         g,
                     );
                     Rectangle::new_round(hex("2D2D2D"), 5.0).draw(
-                        settings.dimensions_dropdown_menu_box,
+                        settings.layout.dimensions_dropdown_menu_box,
                         &c.draw_state,
                         c.transform,
                         g,
@@ -992,7 +1457,7 @@ impl NonogramView {
                                 ButtonInteraction::None => (),
                                 ButtonInteraction::Hover => {
                                     Rectangle::new(hex("222222")).draw(
-                                        settings.dimensions_dropdown_menu_box,
+                                        settings.layout.dimensions_dropdown_menu_box,
                                         &c.draw_state,
                                         c.transform.trans(0.0, dimensions_pos[1] * (it + 1) as f64),
                                         g,
@@ -1000,7 +1465,7 @@ impl NonogramView {
                                 }
                                 ButtonInteraction::Select => {
                                     Rectangle::new(hex("333333")).draw(
-                                        settings.dimensions_dropdown_menu_box,
+                                        settings.layout.dimensions_dropdown_menu_box,
                                         &c.draw_state,
                                         c.transform.trans(0.0, dimensions_pos[1] * (it + 1) as f64),
                                         g,
@@ -1055,11 +1520,11 @@ impl NonogramView {
                     material_icons_glyphs,
                     &c.draw_state,
                     c.transform.trans(
-                        settings.dimensions_dropdown_menu_box[0]
-                            + settings.dimensions_dropdown_menu_box[2]
+                        settings.layout.dimensions_dropdown_menu_box[0]
+                            + settings.layout.dimensions_dropdown_menu_box[2]
                             - dimensions_dropdown_arrow_width,
-                        settings.dimensions_dropdown_menu_box[1]
-                            + (settings.dimensions_dropdown_menu_box[3] / 2.0)
+                        settings.layout.dimensions_dropdown_menu_box[1]
+                            + (settings.layout.dimensions_dropdown_menu_box[3] / 2.0)
                             + (dimensions_dropdown_arrow_size as f64 * 0.75),
                     ),
                     g,
@@ -1070,7 +1535,7 @@ impl NonogramView {
             match controller.restart_button {
                 ButtonInteraction::None => {
                     Rectangle::new_round(hex("9e4c41"), 5.0).draw(
-                        settings.restart_box,
+                        settings.layout.restart_box,
                         &c.draw_state,
                         c.transform,
                         g,
@@ -1078,7 +1543,7 @@ impl NonogramView {
                 }
                 ButtonInteraction::Hover => {
                     Rectangle::new_round(hex("773931"), 5.0).draw(
-                        settings.restart_box,
+                        settings.layout.restart_box,
                         &c.draw_state,
                         c.transform,
                         g,
@@ -1086,7 +1551,7 @@ impl NonogramView {
                 }
                 ButtonInteraction::Select => {
                     Rectangle::new_round(hex("633029"), 5.0).draw(
-                        settings.restart_box,
+                        settings.layout.restart_box,
                         &c.draw_state,
                         c.transform,
                         g,
@@ -1102,14 +1567,942 @@ impl NonogramView {
                     glyphs,
                     &c.draw_state,
                     c.transform.trans(
-                        settings.restart_box[0] + 5.0,
-                        settings.restart_box[1]
-                            + (settings.restart_box[3] / 2.0)
+                        settings.layout.restart_box[0] + 5.0,
+                        settings.layout.restart_box[1]
+                            + (settings.layout.restart_box[3] / 2.0)
                             + ((restart_size as f64 * 0.75) / 2.0),
                     ),
                     g,
                 )
                 .unwrap_or_else(|_| panic!("text draw failed"));
-        }
+
+            // Stats button.
+            match controller.stats_button {
+                ButtonInteraction::None => {
+                    Rectangle::new_round(hex("41749e"), 5.0).draw(
+                        settings.layout.stats_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+                ButtonInteraction::Hover => {
+                    Rectangle::new_round(hex("315877"), 5.0).draw(
+                        settings.layout.stats_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+                ButtonInteraction::Select => {
+                    Rectangle::new_round(hex("294a63"), 5.0).draw(
+                        settings.layout.stats_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+            }
+
+            let stats_str = "STATS".to_string();
+            let stats_size = 25;
+            let stats_width = glyphs.width(stats_size, &stats_str).unwrap_or(0.0);
+            Text::new_color(settings.text_color, stats_size)
+                .draw(
+                    &stats_str,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(
+                        settings.layout.stats_box[0] + (settings.layout.stats_box[2] / 2.0)
+                            - (stats_width / 2.0),
+                        settings.layout.stats_box[1]
+                            + (settings.layout.stats_box[3] / 2.0)
+                            + ((stats_size as f64 * 0.75) / 2.0),
+                    ),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+
+            // Game mode button. Shows the game mode of the next board, just like the dimensions dropdown menu.
+            match controller.mode_button {
+                ButtonInteraction::None => {
+                    Rectangle::new_round(hex("333333"), 5.0).draw(
+                        settings.layout.mode_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+                ButtonInteraction::Hover => {
+                    Rectangle::new_round(hex("2D2D2D"), 5.0).draw(
+                        settings.layout.mode_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+                ButtonInteraction::Select => {
+                    Rectangle::new_round(hex("222222"), 5.0).draw(
+                        settings.layout.mode_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+            }
+
+            let next_mode_str = controller.nonogram.next_mode.name();
+            let next_mode_size = 25;
+            let next_mode_width = glyphs.width(next_mode_size, next_mode_str).unwrap_or(0.0);
+            Text::new_color(settings.text_color, next_mode_size)
+                .draw(
+                    next_mode_str,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(
+                        settings.layout.mode_box[0] + (settings.layout.mode_box[2] / 2.0)
+                            - (next_mode_width / 2.0),
+                        settings.layout.mode_box[1]
+                            + (settings.layout.mode_box[3] / 2.0)
+                            + ((next_mode_size as f64 * 0.75) / 2.0),
+                    ),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+
+            // Palette selector.
+            self.draw_palette(controller, glyphs, c, g);
+
+            // Overview map of the panels of a clip.
+            if let Some(clip) = &controller.clip {
+                self.draw_clip_map(controller, clip, glyphs, c, g);
+            }
+
+            // Tool button. Shows the tool used for dragging the mouse across the board, grayed out in pencil mode.
+            match controller.tool_button {
+                ButtonInteraction::None => {
+                    Rectangle::new_round(hex("333333"), 5.0).draw(
+                        settings.layout.tool_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+                ButtonInteraction::Hover => {
+                    Rectangle::new_round(hex("2D2D2D"), 5.0).draw(
+                        settings.layout.tool_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+                ButtonInteraction::Select => {
+                    Rectangle::new_round(hex("222222"), 5.0).draw(
+                        settings.layout.tool_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+            }
+
+            let tool_str = controller.tool.name();
+            let tool_size = 25;
+            let tool_width = glyphs.width(tool_size, tool_str).unwrap_or(0.0);
+            let tool_color = if controller.pencil {
+                settings.maybe_cell_background_color
+            } else {
+                settings.text_color
+            };
+            Text::new_color(tool_color, tool_size)
+                .draw(
+                    tool_str,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(
+                        settings.layout.tool_box[0] + (settings.layout.tool_box[2] / 2.0)
+                            - (tool_width / 2.0),
+                        settings.layout.tool_box[1]
+                            + (settings.layout.tool_box[3] / 2.0)
+                            + ((tool_size as f64 * 0.75) / 2.0),
+                    ),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+
+            // Controls button.
+            match controller.controls_button {
+                ButtonInteraction::None => {
+                    Rectangle::new_round(hex("41749e"), 5.0).draw(
+                        settings.layout.controls_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+                ButtonInteraction::Hover => {
+                    Rectangle::new_round(hex("315877"), 5.0).draw(
+                        settings.layout.controls_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+                ButtonInteraction::Select => {
+                    Rectangle::new_round(hex("294a63"), 5.0).draw(
+                        settings.layout.controls_box,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+            }
+
+            let controls_str = "CONTROLS".to_string();
+            let controls_size = 25;
+            let controls_width = glyphs.width(controls_size, &controls_str).unwrap_or(0.0);
+            Text::new_color(settings.text_color, controls_size)
+                .draw(
+                    &controls_str,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(
+                        settings.layout.controls_box[0] + (settings.layout.controls_box[2] / 2.0)
+                            - (controls_width / 2.0),
+                        settings.layout.controls_box[1]
+                            + (settings.layout.controls_box[3] / 2.0)
+                            + ((controls_size as f64 * 0.75) / 2.0),
+                    ),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+        }
+    }
+
+    /// Fill color of a palette index. The first color is the usual filled cell color.
+    fn palette_color(&self, index: usize) -> Color {
+        if index == 0 {
+            self.settings.filled_cell_background_color
+        } else {
+            hex(PALETTE[index])
+        }
+    }
+
+    /// Draw a filled cell in its palette color. Half filled cells only fill the triangle covering their corner.
+    fn draw_filled_cell<G: Graphics>(
+        &self,
+        cell: Cell,
+        color: usize,
+        cell_rect: [f64; 4],
+        c: &Context,
+        g: &mut G,
+    ) {
+        use graphics::{Polygon, Rectangle};
+
+        match cell {
+            Cell::Half(_, corner) => {
+                Polygon::new(self.palette_color(color)).draw(
+                    &corner.triangle(cell_rect),
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+            }
+            _ => {
+                Rectangle::new(self.palette_color(color)).draw(
+                    cell_rect,
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+            }
+        }
+    }
+
+    /// Draw the outline around a mega hint number, covering both lines of its merged pair.
+    fn draw_mega_outline<G: Graphics>(&self, rect: [f64; 4], color: Color, c: &Context, g: &mut G) {
+        use graphics::Rectangle;
+
+        Rectangle::new_border(color, 1.0).draw(rect, &c.draw_state, c.transform, g);
+    }
+
+    /// Draw a small wedge before a hint number if its grouping starts with a half filled cell, and after it if
+    /// its grouping ends with one. `pos` is where the hint number's text was drawn.
+    fn draw_hint_ends<G: Graphics>(
+        &self,
+        ends: u8,
+        pos: [f64; 2],
+        width: f64,
+        color: Color,
+        c: &Context,
+        g: &mut G,
+    ) {
+        use graphics::Polygon;
+
+        if ends & CLUE_HALF_START != 0 {
+            Polygon::new(color).draw(
+                &[
+                    [pos[0] - 7.0, pos[1]],
+                    [pos[0] - 2.0, pos[1]],
+                    [pos[0] - 2.0, pos[1] - 6.0],
+                ],
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        }
+        if ends & CLUE_HALF_END != 0 {
+            let x = pos[0] + width;
+            Polygon::new(color).draw(
+                &[
+                    [x + 2.0, pos[1]],
+                    [x + 7.0, pos[1]],
+                    [x + 2.0, pos[1] - 6.0],
+                ],
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        }
+    }
+
+    /// Text color of a hint number with a palette index. The first color is too dark to read on the background,
+    /// so it uses the usual text color.
+    fn hint_color(&self, index: usize) -> Color {
+        if index == 0 {
+            self.settings.text_color
+        } else {
+            hex(PALETTE[index])
+        }
+    }
+
+    /// Draw the overview map of a clip. Every panel is drawn where it sits in the whole picture, with its cells
+    /// shown once it's solved or while it's the panel being solved. The current panel is outlined.
+    fn draw_clip_map<G: Graphics, C>(
+        &self,
+        controller: &NonogramController,
+        clip: &NonogramClip,
+        glyphs: &mut C,
+        c: &Context,
+        g: &mut G,
+    ) where
+        C: CharacterCache<Texture = G::Texture>,
+    {
+        use graphics::text::Text;
+        use graphics::{Rectangle, Transformed};
+
+        let settings = &self.settings;
+        let live = &controller.nonogram;
+
+        let map_title_str = format!(
+            "PANEL {} OF {} - {} SOLVED",
+            clip.current + 1,
+            clip.panel_count(),
+            clip.solved_count(live)
+        );
+        Text::new_color(settings.text_color, 15)
+            .draw(
+                &map_title_str,
+                glyphs,
+                &c.draw_state,
+                c.transform.trans(
+                    settings.layout.clip_map_box[0],
+                    settings.layout.clip_map_box[1] - 8.0,
+                ),
+                g,
+            )
+            .unwrap_or_else(|_| panic!("text draw failed"));
+
+        for (index, rect) in clip
+            .panel_rects(settings.layout.clip_map_box)
+            .iter()
+            .enumerate()
+        {
+            let shown = index == clip.current || clip.is_solved(index, live);
+            let background = if shown {
+                settings.background_color
+            } else {
+                hex("444444")
+            };
+            Rectangle::new(background).draw(*rect, &c.draw_state, c.transform, g);
+
+            if shown {
+                let panel = clip.panel(index, live);
+                let cell_size = rect[2] / panel.dimensions[0] as f64;
+                for (col, cells) in panel.data.iter().enumerate() {
+                    for (row, &value) in cells.iter().enumerate() {
+                        if let Some(color) = value.color() {
+                            let cell_rect = [
+                                rect[0] + col as f64 * cell_size,
+                                rect[1] + row as f64 * cell_size,
+                                cell_size,
+                                cell_size,
+                            ];
+                            self.draw_filled_cell(value, color, cell_rect, c, g);
+                        }
+                    }
+                }
+            }
+
+            Rectangle::new_border(hex("222222"), 0.5).draw(*rect, &c.draw_state, c.transform, g);
+        }
+
+        if let Some(rect) = clip
+            .panel_rects(settings.layout.clip_map_box)
+            .get(clip.current)
+        {
+            Rectangle::new_border(settings.selected_cell_border_color, 1.5).draw(
+                *rect,
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        }
+    }
+
+    /// Draw the palette selector. Shows every color of the current board, with the color that cells are filled
+    /// with outlined, followed by the number of colors and the ruleset the next board will have. In triangle
+    /// puzzles, the colors are drawn as the half filled cell that will be filled in.
+    fn draw_palette<G: Graphics, C>(
+        &self,
+        controller: &NonogramController,
+        glyphs: &mut C,
+        c: &Context,
+        g: &mut G,
+    ) where
+        C: CharacterCache<Texture = G::Texture>,
+    {
+        use graphics::text::Text;
+        use graphics::{Rectangle, Transformed};
+
+        let settings = &self.settings;
+        let swatch_size = settings.layout.palette_box[3];
+
+        for index in 0..controller.nonogram.colors {
+            let swatch_rect = [
+                settings.layout.palette_box[0] + index as f64 * (swatch_size + 5.0),
+                settings.layout.palette_box[1],
+                swatch_size,
+                swatch_size,
+            ];
+            match (controller.nonogram.ruleset, controller.half) {
+                (Ruleset::Triangles, Some(corner)) => {
+                    self.draw_filled_cell(
+                        Cell::Half(index as u8, corner),
+                        index,
+                        swatch_rect,
+                        c,
+                        g,
+                    );
+                }
+                _ => {
+                    Rectangle::new_round(self.palette_color(index), 5.0).draw(
+                        swatch_rect,
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
+                }
+            }
+            if index == controller.color && !controller.pencil {
+                Rectangle::new_round_border(
+                    settings.selected_cell_border_color,
+                    settings.selected_cell_border_round_radius,
+                    settings.selected_cell_border_radius,
+                )
+                .draw(swatch_rect, &c.draw_state, c.transform, g);
+            }
+        }
+
+        // The number of colors and the ruleset of the next board are listed on top of each other.
+        let next_size = 12;
+        let next_x = settings.layout.palette_box[0] + PALETTE.len() as f64 * (swatch_size + 5.0);
+        let next_lines = [
+            format!("NEXT: {} COLORS", controller.nonogram.next_colors),
+            controller.nonogram.next_ruleset.name().to_string(),
+        ];
+        for (it, line) in next_lines.iter().enumerate() {
+            Text::new_color(settings.text_color, next_size)
+                .draw(
+                    line,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(
+                        next_x,
+                        settings.layout.palette_box[1] + (it + 1) as f64 * (swatch_size / 2.0)
+                            - 2.0,
+                    ),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+        }
+    }
+
+    /// Draw the controls screen.
+    ///
+    /// Lists every action along with the buttons bound to it. Clicking an action waits for the next button
+    /// press and adds it to that action.
+    fn draw_controls<G: Graphics, C>(
+        &self,
+        controller: &NonogramController,
+        glyphs: &mut C,
+        c: &Context,
+        g: &mut G,
+    ) where
+        C: CharacterCache<Texture = G::Texture>,
+    {
+        use graphics::text::Text;
+        use graphics::{Rectangle, Transformed};
+
+        let settings = &self.settings;
+
+        Rectangle::new_round(hex("333333"), 10.0).draw(
+            settings.layout.menu_box_rect,
+            &c.draw_state,
+            c.transform,
+            g,
+        );
+
+        // Center-aligned controls title above the controls box.
+        let controls_title_str = "CONTROLS".to_string();
+        let controls_title_size = 25;
+        let controls_title_width = glyphs
+            .width(controls_title_size, &controls_title_str)
+            .unwrap_or(0.0);
+        let controls_title_x = settings.layout.menu_box_rect[0]
+            + (settings.layout.menu_box_rect[2] / 2.0)
+            - (controls_title_width / 2.0);
+        Text::new_color(settings.text_color, controls_title_size)
+            .draw(
+                &controls_title_str,
+                glyphs,
+                &c.draw_state,
+                c.transform
+                    .trans(controls_title_x, settings.layout.menu_box_rect[1] - 10.0),
+                g,
+            )
+            .unwrap_or_else(|_| panic!("text draw failed"));
+
+        let row_size = 20;
+        let column_x = [
+            settings.layout.menu_box_rect[0] + 20.0,
+            settings.layout.menu_box_rect[0] + 260.0,
+        ];
+
+        // Column headings.
+        let heading_y = settings.layout.menu_box_rect[1] + 35.0;
+        for (x, heading) in column_x.iter().zip(["ACTION", "KEYS"].iter()) {
+            Text::new_color(settings.personal_best_color, row_size)
+                .draw(
+                    heading,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(*x, heading_y),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+        }
+
+        // Only the rows that aren't scrolled out of view are drawn.
+        let shown = controller.controls_scroll
+            ..(controller.controls_scroll + CONTROLS_ROWS_SHOWN).min(controller.controls_rows());
+        for it in shown.clone() {
+            // Every row lists the keybindings of an action, except for the last one, which lists the edit policy.
+            let control = Control::ALL.get(it).copied();
+            let shown_row = it - controller.controls_scroll;
+            let row_rect = [
+                settings.layout.menu_box_rect[0],
+                settings.layout.menu_box_rect[1]
+                    + CONTROLS_ROW_OFFSET
+                    + shown_row as f64 * CONTROLS_ROW_HEIGHT,
+                settings.layout.menu_box_rect[2],
+                CONTROLS_ROW_HEIGHT,
+            ];
+            if control.is_some() && controller.rebinding == control {
+                Rectangle::new(hex("222222")).draw(row_rect, &c.draw_state, c.transform, g);
+            } else if controller.controls_hover == Some(it) {
+                Rectangle::new(hex("2D2D2D")).draw(row_rect, &c.draw_state, c.transform, g);
+            }
+
+            let (name_str, name_color, value_str) = match control {
+                Some(control) => {
+                    let name_color = if controller.controls.has_conflict(control) {
+                        settings.conflict_color
+                    } else {
+                        settings.text_color
+                    };
+                    let bindings_str = if controller.rebinding == Some(control) {
+                        "Press a key or button...".to_string()
+                    } else if controller.controls.get(control).is_empty() {
+                        "-".to_string()
+                    } else {
+                        controller
+                            .controls
+                            .get(control)
+                            .iter()
+                            .map(|&button| button_name(button))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    };
+                    (control.name(), name_color, bindings_str)
+                }
+                None => (
+                    "EDIT POLICY",
+                    settings.personal_best_color,
+                    controller.controls.edit_policy.name().to_string(),
+                ),
+            };
+
+            let row_y = row_rect[1] + (row_rect[3] / 2.0) + ((row_size as f64 * 0.75) / 2.0);
+            Text::new_color(name_color, row_size)
+                .draw(
+                    name_str,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(column_x[0], row_y),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+            Text::new_color(settings.text_color, row_size)
+                .draw(
+                    &value_str,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(column_x[1], row_y),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+        }
+
+        // Instructions below the list of actions.
+        let help_size = 15;
+        let help_y = settings.layout.menu_box_rect[1]
+            + CONTROLS_ROW_OFFSET
+            + CONTROLS_ROWS_SHOWN as f64 * CONTROLS_ROW_HEIGHT
+            + 30.0;
+        let help_lines = [
+            format!(
+                "Showing rows {}-{} of {}. Scroll, or press Up, Down, Page Up or Page Down, to see the rest.",
+                shown.start + 1,
+                shown.end,
+                controller.controls_rows()
+            ),
+            "Click an action, then press a key or button to add it. Backspace clears it. Escape cancels.".to_string(),
+            "Press Delete to restore the default controls. Actions shown in red share a key.".to_string(),
+            "Click EDIT POLICY to choose whether filling or marking a cell that isn't empty clears it or replaces it."
+                .to_string(),
+        ];
+        for (it, line) in help_lines.iter().enumerate() {
+            Text::new_color(settings.text_color, help_size)
+                .draw(
+                    line,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform.trans(column_x[0], help_y + it as f64 * 20.0),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+        }
+
+        self.draw_menu_back_button(controller, glyphs, c, g);
+    }
+
+    /// Draw how far along a replay is, along with how fast it's being played back.
+    pub fn draw_replay<G: Graphics, C>(
+        &self,
+        player: &ReplayPlayer,
+        glyphs: &mut C,
+        c: &Context,
+        g: &mut G,
+    ) where
+        C: CharacterCache<Texture = G::Texture>,
+    {
+        use graphics::text::Text;
+        use graphics::{Rectangle, Transformed};
+
+        let settings = &self.settings;
+        let replay_box = settings.replay_box;
+        Rectangle::new_round(hex("333333"), 10.0).draw(replay_box, &c.draw_state, c.transform, g);
+
+        // Left-aligned replay title, with the speed or whether it's paused.
+        let replay_title_str = if player.paused {
+            "REPLAY - PAUSED".to_string()
+        } else {
+            format!("REPLAY - {}x", player.speed())
+        };
+        Text::new_color(settings.text_color, 15)
+            .draw(
+                &replay_title_str,
+                glyphs,
+                &c.draw_state,
+                c.transform
+                    .trans(replay_box[0] + 10.0, replay_box[1] + 22.0),
+                g,
+            )
+            .unwrap_or_else(|_| panic!("text draw failed"));
+
+        // Right-aligned time played back out of the length of the replay.
+        let replay_time_str = format!(
+            "{} / {}",
+            format_duration(Duration::from_secs_f64(player.time)),
+            format_duration(Duration::from_secs_f64(player.length()))
+        );
+        let replay_time_size = 12;
+        let replay_time_width = glyphs
+            .width(replay_time_size, &replay_time_str)
+            .unwrap_or(0.0);
+        Text::new_color(settings.text_color, replay_time_size)
+            .draw(
+                &replay_time_str,
+                glyphs,
+                &c.draw_state,
+                c.transform.trans(
+                    replay_box[0] + replay_box[2] - 10.0 - replay_time_width,
+                    replay_box[1] + 22.0,
+                ),
+                g,
+            )
+            .unwrap_or_else(|_| panic!("text draw failed"));
+
+        // Progress bar along the bottom of the box.
+        let bar_rect = [
+            replay_box[0] + 10.0,
+            replay_box[1] + replay_box[3] - 25.0,
+            replay_box[2] - 20.0,
+            10.0,
+        ];
+        let progress = if player.length() > 0.0 {
+            (player.time / player.length()).min(1.0)
+        } else {
+            1.0
+        };
+        Rectangle::new_round(hex("555555"), 5.0).draw(bar_rect, &c.draw_state, c.transform, g);
+        Rectangle::new_round(settings.selected_cell_border_color, 5.0).draw(
+            [
+                bar_rect[0],
+                bar_rect[1],
+                bar_rect[2] * progress,
+                bar_rect[3],
+            ],
+            &c.draw_state,
+            c.transform,
+            g,
+        );
+    }
+
+    /// Draw the stats screen.
+    ///
+    /// Shows games played, games won, best time, and average time for every board size in
+    /// `DIMENSIONS_CHOICES` in the game mode, number of colors and ruleset of the next board, followed by a list of
+    /// the most recent games.
+    fn draw_stats<G: Graphics, C>(
+        &self,
+        controller: &NonogramController,
+        glyphs: &mut C,
+        c: &Context,
+        g: &mut G,
+    ) where
+        C: CharacterCache<Texture = G::Texture>,
+    {
+        use graphics::text::Text;
+        use graphics::{Rectangle, Transformed};
+
+        let settings = &self.settings;
+        let stats = &controller.stats;
+
+        Rectangle::new_round(hex("333333"), 10.0).draw(
+            settings.layout.menu_box_rect,
+            &c.draw_state,
+            c.transform,
+            g,
+        );
+
+        // Center-aligned stats title above the stats box.
+        let stats_title_str = format!(
+            "STATS - {} - {} COLORS - {}",
+            controller.nonogram.next_mode.name(),
+            controller.nonogram.next_colors,
+            controller.nonogram.next_ruleset.name()
+        );
+        let stats_title_size = 25;
+        let stats_title_width = glyphs
+            .width(stats_title_size, &stats_title_str)
+            .unwrap_or(0.0);
+        let stats_title_x = settings.layout.menu_box_rect[0]
+            + (settings.layout.menu_box_rect[2] / 2.0)
+            - (stats_title_width / 2.0);
+        Text::new_color(settings.text_color, stats_title_size)
+            .draw(
+                &stats_title_str,
+                glyphs,
+                &c.draw_state,
+                c.transform
+                    .trans(stats_title_x, settings.layout.menu_box_rect[1] - 10.0),
+                g,
+            )
+            .unwrap_or_else(|_| panic!("text draw failed"));
+
+        // Every column of the table is left-aligned at these offsets from the left edge of the stats box.
+        let column_x = [20.0, 150.0, 270.0, 380.0, 540.0];
+        let row_size = 20;
+        let row_margin = 25.0;
+        let mut row_y = settings.layout.menu_box_rect[1] + 35.0;
+
+        let header = ["SIZE", "PLAYED", "WON", "BEST", "AVERAGE"];
+        for (x, title) in column_x.iter().zip(header.iter()) {
+            Text::new_color(settings.personal_best_color, row_size)
+                .draw(
+                    title,
+                    glyphs,
+                    &c.draw_state,
+                    c.transform
+                        .trans(settings.layout.menu_box_rect[0] + x, row_y),
+                    g,
+                )
+                .unwrap_or_else(|_| panic!("text draw failed"));
+        }
+
+        for dimensions in DIMENSIONS_CHOICES.iter() {
+            row_y += row_margin;
+            let row = match stats.get(
+                *dimensions,
+                controller.nonogram.next_mode,
+                controller.nonogram.next_colors,
+                controller.nonogram.next_ruleset,
+            ) {
+                Some(size_stats) => [
+                    format!("{}x{}", dimensions[0], dimensions[1]),
+                    size_stats.games_played.to_string(),
+                    size_stats.games_won.to_string(),
+                    size_stats
+                        .best_time
+                        .map_or_else(|| "--:--:--".to_string(), format_duration),
+                    size_stats
+                        .average_time()
+                        .map_or_else(|| "--:--:--".to_string(), format_duration),
+                ],
+                None => [
+                    format!("{}x{}", dimensions[0], dimensions[1]),
+                    "0".to_string(),
+                    "0".to_string(),
+                    "--:--:--".to_string(),
+                    "--:--:--".to_string(),
+                ],
+            };
+            for (x, cell) in column_x.iter().zip(row.iter()) {
+                Text::new_color(settings.text_color, row_size)
+                    .draw(
+                        cell,
+                        glyphs,
+                        &c.draw_state,
+                        c.transform
+                            .trans(settings.layout.menu_box_rect[0] + x, row_y),
+                        g,
+                    )
+                    .unwrap_or_else(|_| panic!("text draw failed"));
+            }
+        }
+
+        // Most recent games, newest first.
+        row_y += row_margin * 2.0;
+        Text::new_color(settings.personal_best_color, row_size)
+            .draw(
+                "RECENT GAMES",
+                glyphs,
+                &c.draw_state,
+                c.transform
+                    .trans(settings.layout.menu_box_rect[0] + column_x[0], row_y),
+                g,
+            )
+            .unwrap_or_else(|_| panic!("text draw failed"));
+
+        for record in stats.history.iter().rev().take(STATS_HISTORY_SHOWN) {
+            row_y += row_margin;
+            let row = [
+                format!("{}x{}", record.dimensions[0], record.dimensions[1]),
+                if record.won { "WON" } else { "LOST" }.to_string(),
+                format_duration(record.duration),
+                record.mode.name().to_string(),
+            ];
+            for (x, cell) in [column_x[0], column_x[1], column_x[3], column_x[4]]
+                .iter()
+                .zip(row.iter())
+            {
+                Text::new_color(settings.text_color, row_size)
+                    .draw(
+                        cell,
+                        glyphs,
+                        &c.draw_state,
+                        c.transform
+                            .trans(settings.layout.menu_box_rect[0] + x, row_y),
+                        g,
+                    )
+                    .unwrap_or_else(|_| panic!("text draw failed"));
+            }
+        }
+
+        self.draw_menu_back_button(controller, glyphs, c, g);
+    }
+
+    /// Draw the back button at the bottom of the stats and controls screens.
+    fn draw_menu_back_button<G: Graphics, C>(
+        &self,
+        controller: &NonogramController,
+        glyphs: &mut C,
+        c: &Context,
+        g: &mut G,
+    ) where
+        C: CharacterCache<Texture = G::Texture>,
+    {
+        use graphics::text::Text;
+        use graphics::{Rectangle, Transformed};
+
+        let settings = &self.settings;
+
+        match controller.menu_back_button {
+            ButtonInteraction::None => {
+                Rectangle::new_round(hex("9e4c41"), 5.0).draw(
+                    settings.layout.menu_back_box,
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+            }
+            ButtonInteraction::Hover => {
+                Rectangle::new_round(hex("773931"), 5.0).draw(
+                    settings.layout.menu_back_box,
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+            }
+            ButtonInteraction::Select => {
+                Rectangle::new_round(hex("633029"), 5.0).draw(
+                    settings.layout.menu_back_box,
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+            }
+        }
+
+        let back_str = "BACK".to_string();
+        let back_size = 25;
+        let back_width = glyphs.width(back_size, &back_str).unwrap_or(0.0);
+        Text::new_color(settings.text_color, back_size)
+            .draw(
+                &back_str,
+                glyphs,
+                &c.draw_state,
+                c.transform.trans(
+                    settings.layout.menu_back_box[0] + (settings.layout.menu_back_box[2] / 2.0)
+                        - (back_width / 2.0),
+                    settings.layout.menu_back_box[1]
+                        + (settings.layout.menu_back_box[3] / 2.0)
+                        + ((back_size as f64 * 0.75) / 2.0),
+                ),
+                g,
+            )
+            .unwrap_or_else(|_| panic!("text draw failed"));
     }
 }
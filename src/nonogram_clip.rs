@@ -0,0 +1,367 @@
+//! Responsible for clips, which are pictures too big for a single board. A clip is split into panels, and every
+//! panel is solved as its own board.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::common::{Cell, GameMode, Ruleset, CLIP_FILE, CLIP_PANEL_SIZE, MAX_BOARD_DIMENSION};
+use crate::nonogram_board::{NonogramBoard, SavedBoard};
+use crate::nonogram_picture::PictureClues;
+use crate::nonogram_storage::Storage;
+
+/// Contains the information about a clip we're going to save in between each session.
+#[derive(Serialize, Deserialize)]
+struct SavedClip {
+    dimensions: [usize; 2],
+    current: usize,
+    panels: Vec<Option<SavedBoard>>,
+}
+
+/// A picture split into panels.
+///
+/// The panel being solved is owned by the controller as its board, so it can be played just like any other
+/// board. Every other panel waits here with its timer paused.
+pub struct NonogramClip {
+    /// Columns and rows of the whole picture.
+    pub dimensions: [usize; 2],
+
+    /// How many panels the picture is split into across and down.
+    pub grid: [usize; 2],
+
+    /// Every panel, going across each row of panels from the top left. The slot of the current panel is empty.
+    panels: Vec<Option<NonogramBoard>>,
+
+    /// Index of the panel being solved.
+    pub current: usize,
+}
+
+impl NonogramClip {
+    /// Whether or not a board with these dimensions has to be split into panels.
+    pub fn is_needed(dimensions: [usize; 2]) -> bool {
+        dimensions[0] > MAX_BOARD_DIMENSION || dimensions[1] > MAX_BOARD_DIMENSION
+    }
+
+    /// Create a clip with a randomly generated goal state for every panel. Returns the clip along with its first
+    /// panel, which is the one to solve first.
+    pub fn new(
+        dimensions: [usize; 2],
+        mode: GameMode,
+        colors: usize,
+        ruleset: Ruleset,
+    ) -> (NonogramClip, NonogramBoard) {
+        let grid = [
+            dimensions[0].div_ceil(CLIP_PANEL_SIZE),
+            dimensions[1].div_ceil(CLIP_PANEL_SIZE),
+        ];
+        let mut clip = NonogramClip {
+            dimensions,
+            grid,
+            panels: vec![],
+            current: 0,
+        };
+        for index in 0..grid[0] * grid[1] {
+            let mut panel =
+                NonogramBoard::with_variant(clip.panel_dimensions(index), colors, ruleset);
+            panel.next_dimensions = dimensions;
+            panel.mode = mode;
+            panel.next_mode = mode;
+            panel.pause();
+            clip.panels.push(Some(panel));
+        }
+
+        let mut first = clip.panels[0].take().expect("a clip always has a panel");
+        first.resume();
+        (clip, first)
+    }
+
+    /// Load the clip saved by `save`. `live` is the board loaded from savedata.json, which has to be the current
+    /// panel of the clip. Returns `None` if there's no clip to load, or if it doesn't belong to `live`.
+    pub fn load<S: Storage + ?Sized>(storage: &S, live: &NonogramBoard) -> Option<NonogramClip> {
+        let saved: SavedClip = storage
+            .read(CLIP_FILE)
+            .and_then(|clip_data| serde_json::from_str(&clip_data).ok())?;
+        let mut clip = NonogramClip {
+            dimensions: saved.dimensions,
+            grid: [
+                saved.dimensions[0].div_ceil(CLIP_PANEL_SIZE),
+                saved.dimensions[1].div_ceil(CLIP_PANEL_SIZE),
+            ],
+            panels: vec![],
+            current: saved.current,
+        };
+        if saved.panels.len() != clip.panel_count()
+            || clip.current >= clip.panel_count()
+            || clip.panel_dimensions(clip.current) != live.dimensions
+        {
+            return None;
+        }
+
+        for (index, panel) in saved.panels.into_iter().enumerate() {
+            if index == clip.current {
+                clip.panels.push(None);
+                continue;
+            }
+            let mut panel = NonogramBoard::from_saved(panel?);
+            panel.pause();
+            clip.panels.push(Some(panel));
+        }
+        Some(clip)
+    }
+
+    /// Save every panel except the current one, which is saved along with the board.
+    pub fn save<S: Storage + ?Sized>(&self, storage: &mut S) -> Result<(), String> {
+        let saved = SavedClip {
+            dimensions: self.dimensions,
+            current: self.current,
+            panels: self
+                .panels
+                .iter()
+                .map(|panel| panel.as_ref().map(NonogramBoard::saved))
+                .collect(),
+        };
+        let clip_data = serde_json::to_string_pretty(&saved)
+            .map_err(|why| format!("Couldn't write {}: {}", CLIP_FILE, why))?;
+        storage.write(CLIP_FILE, &clip_data)
+    }
+
+    /// Number of panels in the clip.
+    pub fn panel_count(&self) -> usize {
+        self.grid[0] * self.grid[1]
+    }
+
+    /// Column and row of the picture that the top left cell of a panel is at.
+    pub fn panel_origin(&self, index: usize) -> [usize; 2] {
+        [
+            (index % self.grid[0]) * CLIP_PANEL_SIZE,
+            (index / self.grid[0]) * CLIP_PANEL_SIZE,
+        ]
+    }
+
+    /// Columns and rows of a panel.
+    pub fn panel_dimensions(&self, index: usize) -> [usize; 2] {
+        let origin = self.panel_origin(index);
+        [
+            CLIP_PANEL_SIZE.min(self.dimensions[0] - origin[0]),
+            CLIP_PANEL_SIZE.min(self.dimensions[1] - origin[1]),
+        ]
+    }
+
+    /// Get a panel. `live` is the board of the current panel.
+    pub fn panel<'a>(&'a self, index: usize, live: &'a NonogramBoard) -> &'a NonogramBoard {
+        self.panels[index].as_ref().unwrap_or(live)
+    }
+
+    /// Whether or not a panel has been solved.
+    pub fn is_solved(&self, index: usize, live: &NonogramBoard) -> bool {
+        let panel = self.panel(index, live);
+        panel.end_game_screen && !panel.time_up
+    }
+
+    /// Number of panels that have been solved.
+    pub fn solved_count(&self, live: &NonogramBoard) -> usize {
+        (0..self.panel_count())
+            .filter(|&index| self.is_solved(index, live))
+            .count()
+    }
+
+    /// The next panel after the current one that hasn't been solved yet, going back to the first panel after the
+    /// last one. `None` if every other panel has been solved.
+    pub fn next_unsolved(&self, live: &NonogramBoard) -> Option<usize> {
+        (1..self.panel_count())
+            .map(|step| (self.current + step) % self.panel_count())
+            .find(|&index| !self.is_solved(index, live))
+    }
+
+    /// Put the current panel back into the clip and take out another one. The timer of the panel put back is
+    /// paused, and the timer of the panel taken out is resumed.
+    pub fn switch(&mut self, live: &mut NonogramBoard, index: usize) {
+        if index == self.current || index >= self.panel_count() {
+            return;
+        }
+
+        let mut next = self.panels[index]
+            .take()
+            .expect("only the current panel is missing");
+        next.resume();
+        live.pause();
+        live.update_duration();
+        self.panels[self.current] = Some(std::mem::replace(live, next));
+        self.current = index;
+    }
+
+    /// Every panel's cells put together into the whole picture, laid out just like `NonogramBoard::data`.
+    pub fn picture(&self, live: &NonogramBoard) -> Vec<Vec<Cell>> {
+        let mut picture = vec![vec![Cell::Empty; self.dimensions[1]]; self.dimensions[0]];
+        for index in 0..self.panel_count() {
+            let origin = self.panel_origin(index);
+            for (col, cells) in self.panel(index, live).data.iter().enumerate() {
+                for (row, &cell) in cells.iter().enumerate() {
+                    picture[origin[0] + col][origin[1] + row] = cell;
+                }
+            }
+        }
+        picture
+    }
+
+    /// Every panel's groupings put together into the whole picture's columns and rows, see
+    /// `NonogramBoard::picture_clues`. A line of the picture lists the groupings of every panel it crosses, in order.
+    pub fn picture_clues(&self, live: &NonogramBoard) -> [PictureClues; 2] {
+        let mut lines = [
+            vec![None; self.dimensions[0]],
+            vec![None; self.dimensions[1]],
+        ];
+        for index in 0..self.panel_count() {
+            let origin = self.panel_origin(index);
+            let panel_lines = self.panel(index, live).picture_clues();
+            for axis in 0..2 {
+                for (line, groupings) in panel_lines[axis].iter().enumerate() {
+                    if let Some(groupings) = groupings {
+                        lines[axis][origin[axis] + line]
+                            .get_or_insert_with(Vec::new)
+                            .extend(groupings);
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    /// Time spent on every panel put together.
+    pub fn duration(&self, live: &NonogramBoard) -> Duration {
+        (0..self.panel_count())
+            .map(|index| self.panel(index, live).duration)
+            .sum()
+    }
+
+    /// Number of filled cells in the goal state of every panel put together.
+    pub fn goal_black(&self, live: &NonogramBoard) -> u64 {
+        (0..self.panel_count())
+            .map(|index| self.panel(index, live).goal_black)
+            .sum()
+    }
+
+    /// Where every panel is drawn on the overview map, going across each row of panels from the top left. The
+    /// whole picture is scaled to fit inside `map_box`.
+    pub fn panel_rects(&self, map_box: [f64; 4]) -> Vec<[f64; 4]> {
+        let cell_size =
+            (map_box[2] / self.dimensions[0] as f64).min(map_box[3] / self.dimensions[1] as f64);
+        (0..self.panel_count())
+            .map(|index| {
+                let origin = self.panel_origin(index);
+                let panel_dimensions = self.panel_dimensions(index);
+                [
+                    map_box[0] + origin[0] as f64 * cell_size,
+                    map_box[1] + origin[1] as f64 * cell_size,
+                    panel_dimensions[0] as f64 * cell_size,
+                    panel_dimensions[1] as f64 * cell_size,
+                ]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panels() {
+        assert!(!NonogramClip::is_needed([30, 30]), "30x30 fits on a board.");
+        assert!(NonogramClip::is_needed([60, 40]), "60x40 doesn't.");
+
+        let (mut clip, mut live) =
+            NonogramClip::new([60, 40], GameMode::Classic, 1, Ruleset::Standard);
+        assert_eq!(clip.grid, [4, 3], "60x40 is split into 4 by 3 panels.");
+        assert_eq!(live.dimensions, [15, 15], "The first panel is full size.");
+        assert_eq!(
+            clip.panel_dimensions(11),
+            [15, 10],
+            "Panels on the bottom edge are cut short."
+        );
+        assert_eq!(clip.panel_origin(5), [15, 15], "Panels go across first.");
+        assert_eq!(
+            live.next_dimensions,
+            [60, 40],
+            "Restarting makes another clip of the same size."
+        );
+        assert!(
+            clip.panel(1, &live).is_paused(),
+            "Panels waiting to be solved don't run their timers."
+        );
+
+        let picture = clip.picture(&live);
+        assert_eq!(
+            [picture.len(), picture[0].len()],
+            [60, 40],
+            "The picture covers every panel."
+        );
+        assert_eq!(
+            clip.panel_rects([0.0, 0.0, 120.0, 120.0])[5],
+            [30.0, 30.0, 30.0, 30.0],
+            "The map is scaled to fit its box."
+        );
+
+        live.data[0][0] = Cell::Filled;
+        clip.panels[4].as_mut().unwrap().data[0][0] = Cell::Filled;
+        let clues = clip.picture_clues(&live);
+        assert_eq!(
+            clues[0][0],
+            Some(vec![(1, 0, 0), (1, 0, 0)]),
+            "A column of the picture lists the groupings of every panel it crosses."
+        );
+        assert_eq!(clues[1][15], Some(vec![(1, 0, 0)]));
+        assert_eq!(clues[1][16], Some(vec![]));
+    }
+
+    #[test]
+    fn test_switch() {
+        let (mut clip, mut live) =
+            NonogramClip::new([45, 30], GameMode::Classic, 1, Ruleset::Standard);
+        live.set([0, 0], Cell::Filled);
+        assert_eq!(clip.next_unsolved(&live), Some(1), "Panels go in order.");
+
+        clip.switch(&mut live, 4);
+        assert_eq!(clip.current, 4, "Any panel can be switched to.");
+        assert!(!live.is_paused(), "The timer of the new panel runs.");
+        assert!(
+            clip.panel(0, &live).is_paused(),
+            "The timer of the old panel stops."
+        );
+        assert_eq!(
+            clip.picture(&live)[0][0],
+            Cell::Filled,
+            "The old panel keeps its cells."
+        );
+
+        clip.panels[5].as_mut().unwrap().end_game_screen = true;
+        assert_eq!(
+            clip.next_unsolved(&live),
+            Some(0),
+            "Solved panels are skipped."
+        );
+        assert_eq!(clip.solved_count(&live), 1, "One panel is solved.");
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        use crate::nonogram_storage::MemoryStorage;
+
+        let mut storage = MemoryStorage::new();
+        let (mut clip, mut live) =
+            NonogramClip::new([45, 30], GameMode::Classic, 1, Ruleset::Standard);
+        clip.switch(&mut live, 2);
+        clip.save(&mut storage).unwrap();
+
+        let loaded = NonogramClip::load(&storage, &live).expect("clip was saved");
+        assert_eq!(loaded.current, 2, "The current panel is kept.");
+        assert_eq!(
+            loaded.picture(&live),
+            clip.picture(&live),
+            "Every panel is kept."
+        );
+        assert!(
+            NonogramClip::load(&storage, &NonogramBoard::new([10, 10])).is_none(),
+            "A clip isn't loaded for a board that isn't one of its panels."
+        );
+    }
+}
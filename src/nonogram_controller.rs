@@ -1,48 +1,334 @@
 //! Responsible for dealing with all input.
 
-use piston::input::{Button, GenericEvent, Key, MouseButton};
-use serde_json::json;
-use std::error::Error;
-use std::fs::File;
-use std::path::Path;
-
-use crate::common::{ButtonInteraction, Cell, Directions, DIMENSIONS_CHOICES};
+use piston::input::{
+    Button, ControllerButton, ControllerHat, GenericEvent, HatState, Key, MouseButton,
+};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::common::{
+    column_hint_at, row_hint_at, ButtonInteraction, Cell, Corner, Directions, EditPolicy, Ruleset,
+    Tool, CLIP_FILE, CONTROLS_FILE, CONTROLS_ROWS_SHOWN, CONTROLS_ROW_HEIGHT, CONTROLS_ROW_OFFSET,
+    DIMENSIONS_CHOICES, PAD_A, PAD_B, PAD_BACK, PAD_DPAD_DOWN, PAD_DPAD_LEFT, PAD_DPAD_RIGHT,
+    PAD_DPAD_UP, PAD_START, PAD_Y, PALETTE, PICTURE_SCALES, SAVE_FILE, STICK_DEADZONE,
+    STICK_X_AXIS, STICK_Y_AXIS,
+};
+use crate::nonogram_action::Action;
 use crate::nonogram_board::NonogramBoard;
+use crate::nonogram_clip::NonogramClip;
+use crate::nonogram_picture::Picture;
+use crate::nonogram_replay::Replay;
+use crate::nonogram_stats::NonogramStats;
+use crate::nonogram_storage::Storage;
+
+/// Declares every action that can be bound to a key, along with the `NonogramControls` field holding its keybindings
+/// and its name on the controls screen. Builds `Control`, `Control::ALL`, `Control::name`, `NonogramControls::get` and
+/// `NonogramControls::get_mut` from that one list, so a new action only needs a line here and a field with defaults.
+macro_rules! controls {
+    ($($(#[$doc:meta])* $control:ident => $field:ident, $name:expr;)*) => {
+        /// Every action that can be bound to a key. Used to look up keybindings by action when rebinding them.
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub enum Control {
+            $($(#[$doc])* $control,)*
+        }
+
+        impl Control {
+            /// Every action, in the order they're listed on the controls screen.
+            pub const ALL: &'static [Control] = &[$(Control::$control),*];
+
+            /// Name of the action as shown to the user.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Control::$control => $name,)*
+                }
+            }
+        }
+
+        impl NonogramControls {
+            /// Get the buttons bound to an action.
+            pub fn get(&self, control: Control) -> &Vec<Button> {
+                match control {
+                    $(Control::$control => &self.$field,)*
+                }
+            }
+
+            /// Get the buttons bound to an action so they can be changed.
+            pub fn get_mut(&mut self, control: Control) -> &mut Vec<Button> {
+                match control {
+                    $(Control::$control => &mut self.$field,)*
+                }
+            }
+        }
+    };
+}
+
+controls! {
+    /// Filling the cell that the mouse cursor is hovering over.
+    MouseFill => mouse_fill, "FILL (MOUSE)";
+
+    /// Marking the cell that the mouse cursor is hovering over.
+    MouseMark => mouse_mark, "MARK (MOUSE)";
+
+    /// Moving the selected cell focus up.
+    MoveUp => move_up, "MOVE UP";
+
+    /// Moving the selected cell focus down.
+    MoveDown => move_down, "MOVE DOWN";
+
+    /// Moving the selected cell focus left.
+    MoveLeft => move_left, "MOVE LEFT";
+
+    /// Moving the selected cell focus right.
+    MoveRight => move_right, "MOVE RIGHT";
+
+    /// Filling the currently selected cell.
+    KeyFill => key_fill, "FILL (SELECTED)";
+
+    /// Marking the currently selected cell.
+    KeyMark => key_mark, "MARK (SELECTED)";
+
+    /// Held down while moving to jump all the way to an edge of the board.
+    Jump => jump, "JUMP TO EDGE";
+
+    /// Held down while moving to skip to the next row or column that isn't solved yet.
+    Skip => skip, "NEXT UNSOLVED LINE";
+
+    /// Generating a new board and starting from scratch.
+    Restart => restart, "RESTART";
+
+    /// Pausing and resuming the game.
+    Pause => pause, "PAUSE";
+
+    /// Increasing the dimensions of the next board to be generated.
+    DimUp => dim_up, "BIGGER BOARD";
+
+    /// Decreasing the dimensions of the next board to be generated.
+    DimDown => dim_down, "SMALLER BOARD";
+
+    /// Cycling through the game modes of the next board to be generated.
+    NextMode => next_mode, "NEXT MODE";
+
+    /// Cycling through the tools used for dragging the mouse across the board.
+    NextTool => next_tool, "NEXT TOOL";
+
+    /// Turning pencil mode on and off. Filling a cell in pencil mode makes a tentative fill.
+    Pencil => pencil, "PENCIL MODE";
+
+    /// Turning every tentative fill into a real fill.
+    CommitPencil => commit_pencil, "COMMIT PENCIL";
+
+    /// Clearing every tentative fill.
+    DiscardPencil => discard_pencil, "DISCARD PENCIL";
+
+    /// Cycling through the palette colors of a color puzzle.
+    NextColor => next_color, "NEXT COLOR";
+
+    /// Cycling through the number of palette colors of the next board to be generated.
+    NextPalette => next_palette, "NEXT PALETTE SIZE";
+
+    /// Cycling through the shapes that cells are filled with in a triangle puzzle.
+    NextShape => next_shape, "NEXT SHAPE";
+
+    /// Cycling through the rulesets of the next board to be generated.
+    NextRuleset => next_ruleset, "NEXT RULESET";
+
+    /// Moving on to the next unsolved panel of a clip.
+    NextPanel => next_panel, "NEXT PANEL";
+
+    /// Making the cells of the picture saved from the win screen bigger.
+    BiggerPicture => bigger_picture, "BIGGER PICTURE";
+
+    /// Making the cells of the picture saved from the win screen smaller.
+    SmallerPicture => smaller_picture, "SMALLER PICTURE";
+
+    /// Switching whether or not the picture saved from the win screen has hint numbers.
+    TogglePictureClues => toggle_picture_clues, "PICTURE HINT NUMBERS";
+}
+
+/// Name of a button as shown to the user.
+pub fn button_name(button: Button) -> String {
+    match button {
+        Button::Keyboard(key) => format!("{:?}", key),
+        Button::Mouse(mouse_button) => format!("Mouse {:?}", mouse_button),
+        Button::Controller(controller_button) => match controller_button.button {
+            PAD_A => "Pad A".to_string(),
+            PAD_B => "Pad B".to_string(),
+            PAD_Y => "Pad Y".to_string(),
+            PAD_BACK => "Pad Back".to_string(),
+            PAD_START => "Pad Start".to_string(),
+            PAD_DPAD_UP => "Pad Up".to_string(),
+            PAD_DPAD_DOWN => "Pad Down".to_string(),
+            PAD_DPAD_LEFT => "Pad Left".to_string(),
+            PAD_DPAD_RIGHT => "Pad Right".to_string(),
+            other => format!("Pad {}", other),
+        },
+        Button::Hat(hat) => format!("Hat {:?}", hat.state),
+    }
+}
+
+/// Keybindings aren't tied to a specific controller. Every controller button is treated as if it came from the
+/// first controller, so any connected controller can be used to play.
+fn any_controller(button: Button) -> Button {
+    match button {
+        Button::Controller(controller_button) => {
+            Button::Controller(ControllerButton::new(0, controller_button.button))
+        }
+        Button::Hat(hat) => Button::Hat(ControllerHat::new(0, hat.which, hat.state)),
+        _ => button,
+    }
+}
+
+/// Whether or not one of the buttons bound to an action has been pressed.
+fn pressed<E: GenericEvent>(bindings: &[Button], e: &E) -> bool {
+    e.press_args()
+        .is_some_and(|button| bindings.contains(&any_controller(button)))
+}
+
+/// Whether or not one of the buttons bound to an action has been released.
+fn released<E: GenericEvent>(bindings: &[Button], e: &E) -> bool {
+    e.release_args()
+        .is_some_and(|button| bindings.contains(&any_controller(button)))
+}
+
+/// Which way a mouse drag is locked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DragLock {
+    /// The drag only moves left and right along the row it started on.
+    Row,
+
+    /// The drag only moves up and down along the column it started on.
+    Column,
+}
+
+/// Every cell on a straight line between two cells, including both ends. Uses Bresenham's line algorithm.
+pub(crate) fn line_cells(from: [usize; 2], to: [usize; 2]) -> Vec<[usize; 2]> {
+    let (mut x, mut y) = (from[0] as isize, from[1] as isize);
+    let (x1, y1) = (to[0] as isize, to[1] as isize);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut cells = vec![];
+    loop {
+        cells.push([x as usize, y as usize]);
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+    cells
+}
 
 /// Handles nonogram keybindings.
+///
+/// Every action can be bound to any number of keyboard keys, mouse buttons, or controller buttons. The keybindings
+/// are loaded from and saved to `CONTROLS_FILE`, so they can also be changed by editing that file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NonogramControls {
     /// Keybinding for filling the cell that the mouse cursor is hovering over.
-    pub mouse_fill: Button,
+    pub mouse_fill: Vec<Button>,
 
     /// Keybinding for marking the cell that the mouse cursor is hovering over.
-    pub mouse_mark: Button,
+    pub mouse_mark: Vec<Button>,
 
     /// Keybinding for moving the selected cell focus up.
-    pub move_up: Button,
+    pub move_up: Vec<Button>,
 
     /// Keybinding for moving the selected cell focus down.
-    pub move_down: Button,
+    pub move_down: Vec<Button>,
 
     /// Keybinding for moving the selected cell focus left.
-    pub move_left: Button,
+    pub move_left: Vec<Button>,
 
     /// Keybinding for moving the selected cell focus right.
-    pub move_right: Button,
+    pub move_right: Vec<Button>,
 
     /// Keybinding for filling the currently selected cell.
-    pub key_fill: Button,
+    pub key_fill: Vec<Button>,
 
     /// Keybinding for marking the currently selected cell.
-    pub key_mark: Button,
+    pub key_mark: Vec<Button>,
+
+    /// Keybinding that makes the movement keybindings jump to an edge of the board while held down.
+    pub jump: Vec<Button>,
+
+    /// Keybinding that makes the movement keybindings skip to the next unsolved row or column while held down.
+    pub skip: Vec<Button>,
 
     /// Keybinding for generating a new board and starting from scratch.
-    pub restart: Button,
+    pub restart: Vec<Button>,
+
+    /// Keybinding for pausing and resuming the game.
+    pub pause: Vec<Button>,
 
     /// Keybinding for increasing the dimensions of the next board to be generated.
-    pub dim_up: Button,
+    pub dim_up: Vec<Button>,
 
     /// Keybinding for decreasing the dimensions of the next board to be generated.
-    pub dim_down: Button,
+    pub dim_down: Vec<Button>,
+
+    /// Keybinding for cycling through the game modes of the next board to be generated.
+    pub next_mode: Vec<Button>,
+
+    /// Keybinding for cycling through the tools used for dragging the mouse across the board.
+    pub next_tool: Vec<Button>,
+
+    /// Keybinding for turning pencil mode on and off.
+    pub pencil: Vec<Button>,
+
+    /// Keybinding for turning every tentative fill into a real fill.
+    pub commit_pencil: Vec<Button>,
+
+    /// Keybinding for clearing every tentative fill.
+    pub discard_pencil: Vec<Button>,
+
+    /// Keybinding for cycling through the palette colors of a color puzzle.
+    pub next_color: Vec<Button>,
+
+    /// Keybinding for cycling through the number of palette colors of the next board to be generated.
+    pub next_palette: Vec<Button>,
+
+    /// Keybinding for cycling through the shapes that cells are filled with in a triangle puzzle.
+    pub next_shape: Vec<Button>,
+
+    /// Keybinding for cycling through the rulesets of the next board to be generated.
+    pub next_ruleset: Vec<Button>,
+
+    /// Keybinding for moving on to the next unsolved panel of a clip.
+    pub next_panel: Vec<Button>,
+
+    /// Keybinding for making the cells of the picture saved from the win screen bigger.
+    pub bigger_picture: Vec<Button>,
+
+    /// Keybinding for making the cells of the picture saved from the win screen smaller.
+    pub smaller_picture: Vec<Button>,
+
+    /// Keybinding for switching whether or not the picture saved from the win screen has hint numbers.
+    pub toggle_picture_clues: Vec<Button>,
+
+    /// How long (in milliseconds) a movement keybinding has to be held down before the selected cell starts
+    /// moving on its own.
+    pub repeat_delay: u64,
+
+    /// How long (in milliseconds) the selected cell waits between moves while a movement keybinding is held down.
+    /// 0 turns auto-repeat off.
+    pub repeat_interval: u64,
+
+    /// Whether or not moving off an edge of the board travels to the other side.
+    pub wrap_around: bool,
+
+    /// Whether filling or marking a cell that isn't empty clears it or overwrites it.
+    pub edit_policy: EditPolicy,
 }
 
 /// Default implementation for NonogramControls.
@@ -50,17 +336,243 @@ impl Default for NonogramControls {
     /// Creates new control handler.
     fn default() -> Self {
         NonogramControls {
-            mouse_fill: Button::Mouse(MouseButton::Left),
-            mouse_mark: Button::Mouse(MouseButton::Right),
-            move_up: Button::Keyboard(Key::W),
-            move_down: Button::Keyboard(Key::S),
-            move_left: Button::Keyboard(Key::A),
-            move_right: Button::Keyboard(Key::D),
-            key_fill: Button::Keyboard(Key::J),
-            key_mark: Button::Keyboard(Key::K),
-            restart: Button::Keyboard(Key::R),
-            dim_up: Button::Keyboard(Key::Up),
-            dim_down: Button::Keyboard(Key::Down),
+            mouse_fill: vec![Button::Mouse(MouseButton::Left)],
+            mouse_mark: vec![Button::Mouse(MouseButton::Right)],
+            move_up: vec![
+                Button::Keyboard(Key::W),
+                Button::Controller(ControllerButton::new(0, PAD_DPAD_UP)),
+                Button::Hat(ControllerHat::new(0, 0, HatState::Up)),
+            ],
+            move_down: vec![
+                Button::Keyboard(Key::S),
+                Button::Controller(ControllerButton::new(0, PAD_DPAD_DOWN)),
+                Button::Hat(ControllerHat::new(0, 0, HatState::Down)),
+            ],
+            move_left: vec![
+                Button::Keyboard(Key::A),
+                Button::Controller(ControllerButton::new(0, PAD_DPAD_LEFT)),
+                Button::Hat(ControllerHat::new(0, 0, HatState::Left)),
+            ],
+            move_right: vec![
+                Button::Keyboard(Key::D),
+                Button::Controller(ControllerButton::new(0, PAD_DPAD_RIGHT)),
+                Button::Hat(ControllerHat::new(0, 0, HatState::Right)),
+            ],
+            key_fill: vec![
+                Button::Keyboard(Key::J),
+                Button::Controller(ControllerButton::new(0, PAD_A)),
+            ],
+            key_mark: vec![
+                Button::Keyboard(Key::K),
+                Button::Controller(ControllerButton::new(0, PAD_B)),
+            ],
+            jump: vec![Button::Keyboard(Key::LShift)],
+            skip: vec![Button::Keyboard(Key::LCtrl)],
+            restart: vec![
+                Button::Keyboard(Key::R),
+                Button::Controller(ControllerButton::new(0, PAD_BACK)),
+            ],
+            pause: vec![
+                Button::Keyboard(Key::P),
+                Button::Controller(ControllerButton::new(0, PAD_START)),
+            ],
+            dim_up: vec![Button::Keyboard(Key::Up)],
+            dim_down: vec![Button::Keyboard(Key::Down)],
+            next_mode: vec![
+                Button::Keyboard(Key::M),
+                Button::Controller(ControllerButton::new(0, PAD_Y)),
+            ],
+            next_tool: vec![Button::Keyboard(Key::T)],
+            pencil: vec![Button::Keyboard(Key::N)],
+            commit_pencil: vec![Button::Keyboard(Key::C)],
+            discard_pencil: vec![Button::Keyboard(Key::X)],
+            next_color: vec![Button::Keyboard(Key::Q)],
+            next_palette: vec![Button::Keyboard(Key::O)],
+            next_shape: vec![Button::Keyboard(Key::H)],
+            next_ruleset: vec![Button::Keyboard(Key::V)],
+            next_panel: vec![Button::Keyboard(Key::B)],
+            bigger_picture: vec![Button::Keyboard(Key::Right)],
+            smaller_picture: vec![Button::Keyboard(Key::Left)],
+            toggle_picture_clues: vec![Button::Keyboard(Key::Tab)],
+            repeat_delay: 300,
+            repeat_interval: 60,
+            wrap_around: false,
+            edit_policy: EditPolicy::Toggle,
+        }
+    }
+}
+
+/// NonogramControls functionality.
+impl NonogramControls {
+    /// Load keybindings from controls.json. If it doesn't exist, the default keybindings are written to it so
+    /// they can be edited by hand. Actions missing from the file keep their default keybindings.
+    pub fn load<S: Storage + ?Sized>(storage: &mut S) -> NonogramControls {
+        let controls = match storage.read(CONTROLS_FILE) {
+            Some(controls_data) => match serde_json::from_str(&controls_data) {
+                Ok(controls) => controls,
+                Err(why) => {
+                    println!(
+                        "Couldn't read {}: {}. Using default controls.",
+                        CONTROLS_FILE, why
+                    );
+                    NonogramControls::default()
+                }
+            },
+            None => {
+                let controls = NonogramControls::default();
+                controls.save(storage);
+                controls
+            }
+        };
+
+        for (button, first, second) in controls.conflicts() {
+            println!(
+                "Warning: {} is bound to both {} and {}.",
+                button_name(button),
+                first.name(),
+                second.name()
+            );
+        }
+
+        controls
+    }
+
+    /// Write keybindings to controls.json.
+    pub fn save<S: Storage + ?Sized>(&self, storage: &mut S) {
+        let result = serde_json::to_string_pretty(self)
+            .map_err(|why| format!("Couldn't write to {}: {}", CONTROLS_FILE, why))
+            .and_then(|contents| storage.write(CONTROLS_FILE, &contents));
+        if let Err(why) = result {
+            println!("{}", why);
+        }
+    }
+
+    /// Add a button to an action. Does nothing if the button is already bound to that action.
+    pub fn bind(&mut self, control: Control, button: Button) {
+        let button = any_controller(button);
+        let bindings = self.get_mut(control);
+        if !bindings.contains(&button) {
+            bindings.push(button);
+        }
+    }
+
+    /// Find every button that's bound to more than one action.
+    pub fn conflicts(&self) -> Vec<(Button, Control, Control)> {
+        let mut conflicts = vec![];
+        for (i, &first) in Control::ALL.iter().enumerate() {
+            for &second in Control::ALL.iter().skip(i + 1) {
+                for &button in self.get(first) {
+                    if self.get(second).contains(&button) {
+                        conflicts.push((button, first, second));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Whether or not any of the buttons bound to an action are also bound to another action.
+    pub fn has_conflict(&self, control: Control) -> bool {
+        self.conflicts()
+            .iter()
+            .any(|&(_, first, second)| first == control || second == control)
+    }
+}
+
+/// Where the board and every button of the window are, so events can tell what's being hovered over or clicked.
+/// Laid out by the view, which owns it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WindowLayout {
+    /// X and Y coordinates of nonogram board relative to top left corner of the window.
+    pub position: [f64; 2],
+
+    /// [width, height] of nonogram board.
+    pub board_dimensions: [f64; 2],
+
+    /// Location and size of unselected dimensions dropdown menu box.
+    ///
+    /// Format: [x, y, width, height]
+    pub dimensions_dropdown_menu_box: [f64; 4],
+
+    /// Location and size of restart button in main game screen.
+    ///
+    /// Format: [x, y, width, height]
+    pub restart_box: [f64; 4],
+
+    /// Location and size of new game button on win screen.
+    ///
+    /// Format: [x, y, width, height]
+    pub new_game_box: [f64; 4],
+
+    /// Location and size of save picture button on win screen.
+    ///
+    /// Format: [x, y, width, height]
+    pub save_picture_box: [f64; 4],
+
+    /// Location and size of stats button in main game screen.
+    ///
+    /// Format: [x, y, width, height]
+    pub stats_box: [f64; 4],
+
+    /// Location and size of game mode button in main game screen.
+    ///
+    /// Format: [x, y, width, height]
+    pub mode_box: [f64; 4],
+
+    /// Location and size of tool button in main game screen.
+    ///
+    /// Format: [x, y, width, height]
+    pub tool_box: [f64; 4],
+
+    /// Location and size of the palette selector in main game screen. Each palette color is a square as tall as
+    /// this box.
+    ///
+    /// Format: [x, y, width, height]
+    pub palette_box: [f64; 4],
+
+    /// Location and size of the overview map of a clip's panels in main game screen. The whole picture is scaled
+    /// to fit inside this box.
+    ///
+    /// Format: [x, y, width, height]
+    pub clip_map_box: [f64; 4],
+
+    /// Location and size of controls button in main game screen.
+    ///
+    /// Format: [x, y, width, height]
+    pub controls_box: [f64; 4],
+
+    /// Location and size of the box that the stats and controls screens are drawn in.
+    ///
+    /// Format: [x, y, width, height]
+    pub menu_box_rect: [f64; 4],
+
+    /// Location and size of back button on stats and controls screens.
+    ///
+    /// Format: [x, y, width, height]
+    pub menu_back_box: [f64; 4],
+}
+
+/// `WindowLayout` functionality.
+impl WindowLayout {
+    /// Lay out just the board, with every button and menu far outside of the window where nothing can reach them.
+    /// Used by everything that sends events without drawing the window.
+    pub fn board_only(position: [f64; 2], board_dimensions: [f64; 2]) -> WindowLayout {
+        let nowhere = [-1.0e7, -1.0e7, 0.0, 0.0];
+        WindowLayout {
+            position,
+            board_dimensions,
+            dimensions_dropdown_menu_box: nowhere,
+            restart_box: nowhere,
+            new_game_box: nowhere,
+            save_picture_box: nowhere,
+            stats_box: nowhere,
+            mode_box: nowhere,
+            tool_box: nowhere,
+            palette_box: nowhere,
+            clip_map_box: nowhere,
+            controls_box: nowhere,
+            menu_box_rect: nowhere,
+            menu_back_box: nowhere,
         }
     }
 }
@@ -82,69 +594,998 @@ pub struct NonogramController {
     /// Stores whether a fill keybinding or mark keybinding are being held down.
     key_d: [bool; 2],
 
-    /// Stores whether or not then next keyboard move will travel to the other side of the board.
-    loop_back: bool,
+    /// Stores which way the controller stick is tilted along the [x, y] axes. -1 is left or up, 1 is right or
+    /// down, and 0 is centered.
+    stick_d: [i8; 2],
+
+    /// Stores whether a jump keybinding or skip keybinding are being held down.
+    modifier_d: [bool; 2],
+
+    /// Direction of the movement keybinding or controller stick tilt that's being held down.
+    held_move: Option<Directions>,
+
+    /// Time that the held down movement will move the selected cell again.
+    next_repeat: Option<Instant>,
 
     /// Whether or not mouse was original clicked on board.
     board_d: bool,
 
+    /// Cell that the mouse cursor is hovering over. `None` if the mouse cursor isn't on the board.
+    hover_cell: Option<[usize; 2]>,
+
+    /// Cell that the current mouse drag started on.
+    drag_start: Option<[usize; 2]>,
+
+    /// Most recent cell painted by the current mouse drag.
+    drag_last: Option<[usize; 2]>,
+
+    /// Row or column the current mouse drag is locked to. `None` until the drag leaves its first cell.
+    drag_lock: Option<DragLock>,
+
     /// Stores current cell type being manipulated (empty, filled, marked).
     current_action: Cell,
 
+    /// Stores the cell type that cells being manipulated are turned into.
+    target_action: Cell,
+
+    /// Tool used for dragging the mouse across the board.
+    pub tool: Tool,
+
+    /// True if filling a cell makes a tentative fill instead of a real one.
+    pub pencil: bool,
+
+    /// Palette index that cells are filled with.
+    pub color: usize,
+
+    /// Corner that cells are half filled towards in a triangle puzzle. None fills the whole cell.
+    pub half: Option<Corner>,
+
     /// Current status of dimensions dropdown menu.
     pub dimensions_dropdown_menu: ButtonInteraction,
 
     /// Index of dropdown menu selected, and interaction type.
     pub dimensions_dropdown_options: (usize, ButtonInteraction),
 
-    /// Current status of restart button.
-    pub restart_button: ButtonInteraction,
+    /// Current status of restart button.
+    pub restart_button: ButtonInteraction,
+
+    /// Current status of new game button.
+    pub new_game_button: ButtonInteraction,
+
+    /// Current status of save picture button on the win screen.
+    pub save_picture_button: ButtonInteraction,
+
+    /// Current status of stats button.
+    pub stats_button: ButtonInteraction,
+
+    /// Current status of game mode button.
+    pub mode_button: ButtonInteraction,
+
+    /// Current status of tool button.
+    pub tool_button: ButtonInteraction,
+
+    /// Current status of back button on stats and controls screens.
+    pub menu_back_button: ButtonInteraction,
+
+    /// Current status of controls button.
+    pub controls_button: ButtonInteraction,
+
+    /// True if we're at the controls screen.
+    pub controls_screen: bool,
+
+    /// Index of the row on the controls screen that the mouse cursor is hovering over. One past the last action is
+    /// the edit policy row.
+    pub controls_hover: Option<usize>,
+
+    /// Index of the first row shown on the controls screen.
+    pub controls_scroll: usize,
+
+    /// The action waiting for a new keybinding on the controls screen.
+    pub rebinding: Option<Control>,
+
+    /// True if we're at the stats screen.
+    pub stats_screen: bool,
+
+    /// Stores statistics and personal bests of every game played.
+    pub stats: NonogramStats,
+
+    /// False if the stats file couldn't be read, in which case it's left alone instead of being overwritten.
+    stats_loaded: bool,
+
+    /// The clip that the current board is a panel of. `None` if the current board is a whole picture.
+    pub clip: Option<NonogramClip>,
+
+    /// Where the board and clip are saved when the game is closed.
+    pub storage: Box<dyn Storage>,
+
+    /// Whether or not the current game has already been recorded in the stats.
+    game_recorded: bool,
+
+    /// True if the most recently won game was a new personal best for its board size.
+    pub new_personal_best: bool,
+
+    /// Index of the cell size in `PICTURE_SCALES` that pictures are saved at.
+    pub picture_scale: usize,
+
+    /// Whether or not pictures are saved with their hint numbers.
+    pub picture_clues: bool,
+
+    /// True once saving the picture has been asked for on the win screen. Whoever draws the game saves it and sets
+    /// this back to false, since pictures are drawn the same way as the window.
+    pub save_picture: bool,
+
+    /// Where the picture was saved, or why it couldn't be. Shown on the win screen.
+    pub picture_message: Option<String>,
+
+    /// Every action taken since recording started, if the session is being recorded.
+    replay: Option<Replay>,
+
+    /// Name the replay is saved under along with the board.
+    replay_name: String,
+
+    /// When recording started. Every recorded action is timed from here.
+    replay_start: Instant,
+}
+
+/// Implementation for NonogramController.
+impl NonogramController {
+    /// Creates a new nonogram controller. `nonogram` is usually the board loaded from `storage`, and any clip it's
+    /// a panel of is loaded from there too.
+    pub fn new(nonogram: NonogramBoard, mut storage: Box<dyn Storage>) -> NonogramController {
+        let game_recorded = nonogram.end_game_screen;
+        let controls = NonogramControls::load(&mut *storage);
+        let clip = NonogramClip::load(&*storage, &nonogram);
+        let (stats, stats_loaded) = match NonogramStats::load(&*storage) {
+            Ok(stats) => (stats, true),
+            Err(why) => {
+                println!("{}. Stats won't be saved until it's fixed or deleted.", why);
+                (NonogramStats::default(), false)
+            }
+        };
+        NonogramController {
+            controls,
+            nonogram,
+            cursor_pos: [0.0; 2],
+            mouse_d: [false; 2],
+            key_d: [false; 2],
+            stick_d: [0; 2],
+            modifier_d: [false; 2],
+            held_move: None,
+            next_repeat: None,
+            board_d: false,
+            hover_cell: None,
+            drag_start: None,
+            drag_last: None,
+            drag_lock: None,
+            current_action: Cell::Empty,
+            target_action: Cell::Empty,
+            tool: Tool::Pen,
+            pencil: false,
+            color: 0,
+            half: None,
+            dimensions_dropdown_menu: ButtonInteraction::None,
+            dimensions_dropdown_options: (0, ButtonInteraction::None),
+            restart_button: ButtonInteraction::None,
+            new_game_button: ButtonInteraction::None,
+            save_picture_button: ButtonInteraction::None,
+            stats_button: ButtonInteraction::None,
+            mode_button: ButtonInteraction::None,
+            tool_button: ButtonInteraction::None,
+            menu_back_button: ButtonInteraction::None,
+            controls_button: ButtonInteraction::None,
+            controls_screen: false,
+            controls_hover: None,
+            controls_scroll: 0,
+            rebinding: None,
+            stats_screen: false,
+            stats,
+            stats_loaded,
+            clip,
+            storage,
+            game_recorded,
+            new_personal_best: false,
+            picture_scale: PICTURE_SCALES
+                .iter()
+                .position(|&scale| scale == 20)
+                .unwrap_or(0),
+            picture_clues: false,
+            save_picture: false,
+            picture_message: None,
+            replay: None,
+            replay_name: String::new(),
+            replay_start: Instant::now(),
+        }
+    }
+
+    /// Start recording every action taken, starting from the current board. The replay is saved under `name`
+    /// whenever the board is saved.
+    pub fn start_recording(&mut self, name: &str) {
+        let mut replay = Replay::new();
+        replay.edit_policy = self.controls.edit_policy;
+        replay.wrap_around = self.controls.wrap_around;
+        replay.start_game(0.0, &self.nonogram);
+        self.replay = Some(replay);
+        self.replay_name = name.to_string();
+        self.replay_start = Instant::now();
+    }
+
+    /// Everything recorded since `start_recording`, if the session is being recorded.
+    pub fn replay(&self) -> Option<&Replay> {
+        self.replay.as_ref()
+    }
+
+    /// Start a new game in the replay once the board has been swapped for a different one.
+    fn record_board(&mut self) {
+        let time = self.replay_start.elapsed().as_secs_f64();
+        if let Some(replay) = &mut self.replay {
+            replay.start_game(time, &self.nonogram);
+        }
+    }
+
+    /// Replace the board, dropping whatever the mouse was doing on the old one. Used to play back replays and
+    /// scripts.
+    pub fn load_board(&mut self, board: NonogramBoard) {
+        self.nonogram = board;
+        self.hover_cell = None;
+        self.board_d = false;
+        self.drag_start = None;
+        self.drag_last = None;
+        self.drag_lock = None;
+    }
+
+    /// Record the current game in the stats and save them.
+    ///
+    /// Won games are recorded as soon as the win screen comes up. Games that are restarted before
+    /// they're won are only recorded if the player has touched the board.
+    fn record_game(&mut self) {
+        if self.game_recorded {
+            return;
+        }
+
+        let won = self.nonogram.end_game_screen && !self.nonogram.time_up;
+        if !self.nonogram.end_game_screen
+            && self
+                .picture()
+                .iter()
+                .flatten()
+                .all(|&cell| cell == Cell::Empty)
+        {
+            return;
+        }
+
+        self.nonogram.update_duration();
+        self.new_personal_best = self.stats.record_game(
+            self.dimensions(),
+            self.nonogram.mode,
+            self.nonogram.colors,
+            self.nonogram.ruleset,
+            self.duration(),
+            won,
+        );
+        self.game_recorded = true;
+        if self.stats_loaded {
+            self.stats.save(&mut *self.storage);
+        }
+    }
+
+    /// Columns and rows of the whole picture being solved, which is bigger than the current board in a clip.
+    pub fn dimensions(&self) -> [usize; 2] {
+        match &self.clip {
+            Some(clip) => clip.dimensions,
+            None => self.nonogram.dimensions,
+        }
+    }
+
+    /// Cells of the whole picture being solved, laid out just like `NonogramBoard::data`.
+    pub fn picture(&self) -> Vec<Vec<Cell>> {
+        match &self.clip {
+            Some(clip) => clip.picture(&self.nonogram),
+            None => self.nonogram.data.clone(),
+        }
+    }
+
+    /// The whole picture being solved, laid out to be saved at the chosen cell size, with or without its hint
+    /// numbers. The hint numbers are read by the board, so they're shown just like in the game.
+    pub fn picture_to_save(&self) -> Picture {
+        let clues = if !self.picture_clues {
+            None
+        } else if let Some(clip) = &self.clip {
+            Some(clip.picture_clues(&self.nonogram))
+        } else {
+            Some(self.nonogram.picture_clues())
+        };
+        Picture::new(self.picture(), PICTURE_SCALES[self.picture_scale], clues)
+    }
+
+    /// Time spent on the whole picture being solved.
+    pub fn duration(&self) -> Duration {
+        match &self.clip {
+            Some(clip) => clip.duration(&self.nonogram),
+            None => self.nonogram.duration,
+        }
+    }
+
+    /// Number of filled cells in the goal state of the whole picture being solved.
+    pub fn goal_black(&self) -> u64 {
+        match &self.clip {
+            Some(clip) => clip.goal_black(&self.nonogram),
+            None => self.nonogram.goal_black,
+        }
+    }
+
+    /// Switch to another panel of the current clip. Any drag in progress is dropped, because its cells belong to
+    /// the old panel.
+    fn switch_panel(&mut self, index: usize) {
+        if let Some(clip) = &mut self.clip {
+            clip.switch(&mut self.nonogram, index);
+            self.hover_cell = None;
+            self.drag_start = None;
+            self.drag_last = None;
+            self.drag_lock = None;
+            self.record_board();
+        }
+    }
+
+    /// Record the current game if necessary and request a new board from the main loop.
+    fn restart(&mut self) {
+        self.record_game();
+        self.nonogram.reset_board = true;
+        self.color = 0;
+        self.half = None;
+        self.game_recorded = false;
+        self.new_personal_best = false;
+    }
+
+    /// Number of rows on the controls screen, including the ones scrolled out of view. The row after every action is
+    /// the edit policy.
+    pub fn controls_rows(&self) -> usize {
+        Control::ALL.len() + 1
+    }
+
+    /// Scroll the controls screen by some number of rows, negative being up. The row under the mouse cursor is
+    /// worked out again, since a different row is there now.
+    fn scroll_controls(&mut self, rows: isize) {
+        let most = self.controls_rows().saturating_sub(CONTROLS_ROWS_SHOWN) as isize;
+        let scroll = (self.controls_scroll as isize + rows).clamp(0, most) as usize;
+        self.controls_hover = self
+            .controls_hover
+            .map(|row| row - self.controls_scroll + scroll);
+        self.controls_scroll = scroll;
+    }
+
+    /// Replace the current board with a brand-new one once a restart has been requested.
+    ///
+    /// Resetting the board causes it to wipe the current state, potentially create a new board with different
+    /// dimensions, a different game mode, or a different number of colors or ruleset than the current board
+    /// depending on the user's choice, and generate a new goal state.
+    ///
+    /// Dimensions too big for a single board create a clip instead, and the current board becomes its first panel.
+    pub fn start_next_board(&mut self) {
+        let next_dimensions = self.nonogram.next_dimensions;
+        let next_mode = self.nonogram.next_mode;
+        let next_colors = self.nonogram.next_colors;
+        let next_ruleset = self.nonogram.next_ruleset;
+        if NonogramClip::is_needed(next_dimensions) {
+            let (clip, first_panel) =
+                NonogramClip::new(next_dimensions, next_mode, next_colors, next_ruleset);
+            self.nonogram = first_panel;
+            self.clip = Some(clip);
+        } else {
+            self.nonogram = NonogramBoard::with_variant(next_dimensions, next_colors, next_ruleset);
+            self.nonogram.mode = next_mode;
+            self.nonogram.next_mode = next_mode;
+            self.clip = None;
+        }
+        self.save_picture = false;
+        self.picture_message = None;
+        self.record_board();
+    }
+
+    /// Move the selected cell, filling or marking the cell we land on if a fill or mark keybinding is being
+    /// held down.
+    ///
+    /// Used by both the movement keybindings and the controller sticks.
+    fn move_selected(&mut self, direction: Directions) {
+        if self.modifier_d[0] {
+            self.nonogram.jump_selected(direction);
+        } else if self.modifier_d[1] {
+            self.nonogram
+                .skip_selected(direction, self.controls.wrap_around);
+        } else {
+            self.nonogram
+                .change_selected(direction, self.controls.wrap_around);
+        }
+        if let Some(ind) = self.nonogram.selected_cell {
+            if (self.key_d[0] || self.key_d[1]) && self.nonogram.get(ind) == self.current_action {
+                self.nonogram.set(ind, self.target_action);
+            }
+        }
+    }
+
+    /// Move the selected cell once, then keep moving it in the same direction after `repeat_delay` until the
+    /// movement is let go.
+    fn start_move(&mut self, direction: Directions) {
+        self.move_selected(direction);
+        self.held_move = Some(direction);
+        self.next_repeat = Some(Instant::now() + Duration::from_millis(self.controls.repeat_delay));
+    }
+
+    /// Stop repeating a movement once it's let go. Letting go of a different direction than the one that's
+    /// repeating doesn't do anything.
+    fn stop_move(&mut self, direction: Directions) {
+        if self.held_move == Some(direction) {
+            self.held_move = None;
+            self.next_repeat = None;
+        }
+    }
+
+    /// The direction to move the selected cell in again if a movement has been held down long enough.
+    fn due_repeat(&mut self) -> Option<Directions> {
+        if self.controls.repeat_interval == 0 {
+            return None;
+        }
+        if let (Some(direction), Some(next_repeat)) = (self.held_move, self.next_repeat) {
+            let now = Instant::now();
+            if now >= next_repeat {
+                self.next_repeat = Some(now + Duration::from_millis(self.controls.repeat_interval));
+                return Some(direction);
+            }
+        }
+        None
+    }
+
+    /// Start dragging the mouse across the board from the cell that was clicked.
+    ///
+    /// Whatever is done to the first cell is done to every other cell that's dragged across, as long as they
+    /// started out the same as the first cell. The line and rectangle tools wait until the drag is finished.
+    fn start_drag(&mut self, ind: [usize; 2], val: Cell) {
+        self.board_d = true;
+        self.start_action(ind, val);
+        self.drag_start = Some(ind);
+        self.drag_last = None;
+        self.drag_lock = None;
+        if self.tool == Tool::Pen {
+            self.drag_paint(ind);
+        } else {
+            self.drag_last = Some(ind);
+        }
+    }
+
+    /// What filling a cell turns it into. Tentative fills are used while in pencil mode, and triangle puzzles
+    /// can fill half of a cell.
+    fn fill_value(&self) -> Cell {
+        let color = self.color.min(self.nonogram.colors - 1);
+        match (self.pencil, self.nonogram.ruleset, self.half) {
+            (true, _, _) => Cell::Maybe,
+            (false, Ruleset::Triangles, Some(corner)) => Cell::Half(color as u8, corner),
+            _ => Cell::from_color(color),
+        }
+    }
+
+    /// Remember what the first cell of a drag or held down keybinding started out as, and what it's going to be
+    /// turned into. The edit policy decides whether filling or marking a cell that isn't empty clears it or
+    /// overwrites it.
+    fn start_action(&mut self, ind: [usize; 2], val: Cell) {
+        self.current_action = self.nonogram.get(ind);
+        self.target_action = self.controls.edit_policy.apply(self.current_action, val);
+    }
+
+    /// Fill or mark every cell covered by the line or rectangle tool once the mouse button is released.
+    fn finish_drag(&mut self) {
+        for cell in self.tool_cells() {
+            if self.nonogram.get(cell) == self.current_action {
+                self.nonogram.set(cell, self.target_action);
+            }
+        }
+        self.board_d = false;
+    }
+
+    /// Every cell that the line or rectangle tool will fill or mark once the mouse button is released. Empty if
+    /// the pen tool is being used or nothing is being dragged.
+    pub fn tool_cells(&self) -> Vec<[usize; 2]> {
+        match (self.board_d, self.drag_start, self.drag_last) {
+            (true, Some(start), Some(end)) => match self.tool {
+                Tool::Pen => vec![],
+                Tool::Line => line_cells(start, end),
+                Tool::Rectangle => {
+                    let mut cells = vec![];
+                    for col in start[0].min(end[0])..=start[0].max(end[0]) {
+                        for row in start[1].min(end[1])..=start[1].max(end[1]) {
+                            cells.push([col, row]);
+                        }
+                    }
+                    cells
+                }
+            },
+            _ => vec![],
+        }
+    }
+
+    /// Keep a mouse drag on the row or column it started moving along, just like Picross does. Otherwise a slight
+    /// wobble of the mouse would spill over into the neighboring rows or columns.
+    fn lock_drag(&mut self, mut ind: [usize; 2]) -> [usize; 2] {
+        if !self.board_d || self.tool == Tool::Rectangle {
+            return ind;
+        }
+        if let Some(start) = self.drag_start {
+            if self.drag_lock.is_none() && ind != start {
+                let distance_x = (ind[0] as isize - start[0] as isize).abs();
+                let distance_y = (ind[1] as isize - start[1] as isize).abs();
+                self.drag_lock = if distance_x >= distance_y {
+                    Some(DragLock::Row)
+                } else {
+                    Some(DragLock::Column)
+                };
+            }
+            match self.drag_lock {
+                Some(DragLock::Row) => ind[1] = start[1],
+                Some(DragLock::Column) => ind[0] = start[0],
+                None => (),
+            }
+        }
+        ind
+    }
+
+    /// Fill or mark every cell between the last cell painted by the mouse and this one. The mouse cursor can skip
+    /// over cells when it's moving fast, so we can't rely on every cell it passes over producing an event.
+    fn drag_paint(&mut self, ind: [usize; 2]) {
+        let from = self.drag_last.unwrap_or(ind);
+        for cell in line_cells(from, ind) {
+            if Some(cell) == self.drag_last {
+                continue;
+            }
+            if self.nonogram.get(cell) == self.current_action {
+                self.nonogram.set(cell, self.target_action);
+            }
+        }
+        self.drag_last = Some(ind);
+    }
+
+    /// Find the hint number under the mouse cursor. Returns `[column_or_row, which_column_or_row, which_number]`,
+    /// just like the indices of `goal_nums`.
+    ///
+    /// This has to line up with where `NonogramView::draw` puts the hint numbers. Column hint numbers are stacked
+    /// upwards from just above the board, and row hint numbers are lined up leftwards from just left of the board,
+    /// each 20 pixels apart.
+    fn hint_at(&self, board_pos: [f64; 2], size: [f64; 2]) -> Option<[usize; 3]> {
+        let x = self.cursor_pos[0] - board_pos[0];
+        let y = self.cursor_pos[1] - board_pos[1];
+        let cell_size = size[0] / self.nonogram.dimensions[0] as f64;
+
+        let (axis, line, num_pos) = if x >= 0.0 && x < size[0] && y < 0.0 {
+            (
+                0,
+                (x / cell_size) as usize,
+                column_hint_at(board_pos, self.cursor_pos[1])?,
+            )
+        } else if y >= 0.0 && y < size[1] && x < 0.0 {
+            (
+                1,
+                (y / cell_size) as usize,
+                row_hint_at(board_pos, self.cursor_pos[0])?,
+            )
+        } else {
+            return None;
+        };
+
+        // The hint numbers of a merged pair of lines all belong to the first line.
+        let line = if line > 0 && self.nonogram.is_merged(axis, line - 1) {
+            line - 1
+        } else {
+            line
+        };
+
+        // Hint numbers are drawn starting from the last one, skipping zeros.
+        let nums = &self.nonogram.goal_nums[axis][line];
+        nums.iter()
+            .enumerate()
+            .rev()
+            .filter(|&(_, &num)| num != 0)
+            .nth(num_pos)
+            .map(|(index, _)| [axis, line, index])
+    }
+
+    /// Whether or not the board can be played right now. Nothing on the board can be changed while the game is
+    /// paused, after it's over, or while the stats or controls screen is up.
+    fn is_playing(&self) -> bool {
+        !self.stats_screen
+            && !self.controls_screen
+            && self.rebinding.is_none()
+            && !self.nonogram.end_game_screen
+            && !self.nonogram.is_paused()
+    }
+
+    /// Whether or not a cell is on the current board.
+    fn is_on_board(&self, ind: [usize; 2]) -> bool {
+        ind[0] < self.nonogram.dimensions[0] && ind[1] < self.nonogram.dimensions[1]
+    }
+
+    /// Apply an action to the game. Actions that don't make sense at the moment, like filling a cell while the game
+    /// is paused, are ignored.
+    pub fn apply(&mut self, action: Action) {
+        if action != Action::Tick {
+            let time = self.replay_start.elapsed().as_secs_f64();
+            if let Some(replay) = &mut self.replay {
+                replay.record(time, action);
+            }
+        }
+
+        let playing = self.is_playing();
+        let won = self.nonogram.end_game_screen
+            && !self.nonogram.time_up
+            && !self.controls_screen
+            && !self.stats_screen;
+        match action {
+            Action::StartFill(ind) if playing && self.is_on_board(ind) => {
+                self.start_drag(ind, self.fill_value());
+            }
+            Action::StartMark(ind) if playing && self.is_on_board(ind) => {
+                self.start_drag(ind, Cell::Marked);
+            }
+            Action::Hover(Some(ind)) if playing && self.is_on_board(ind) => {
+                let cell = self.lock_drag(ind);
+                self.hover_cell = Some(cell);
+                self.nonogram.selected_cell = Some(cell);
+                if self.board_d {
+                    if self.tool == Tool::Pen {
+                        self.drag_paint(cell);
+                    } else {
+                        self.drag_last = Some(cell);
+                    }
+                }
+            }
+            Action::Hover(None) if playing => self.hover_cell = None,
+            Action::EndDrag => {
+                if self.board_d && playing {
+                    self.finish_drag();
+                }
+                self.board_d = false;
+            }
+            Action::CancelDrag => self.board_d = false,
+            Action::HoldFill(true) if playing && !self.key_d[0] => {
+                self.key_d[0] = true;
+                if let Some(ind) = self.nonogram.selected_cell {
+                    self.start_action(ind, self.fill_value());
+                    self.nonogram.set(ind, self.target_action);
+                }
+            }
+            Action::HoldFill(false) => self.key_d[0] = false,
+            Action::HoldMark(true) if playing && !self.key_d[1] => {
+                self.key_d[1] = true;
+                if let Some(ind) = self.nonogram.selected_cell {
+                    self.start_action(ind, Cell::Marked);
+                    self.nonogram.set(ind, self.target_action);
+                }
+            }
+            Action::HoldMark(false) => self.key_d[1] = false,
+            Action::Move(direction) if playing => self.start_move(direction),
+            Action::StopMove(direction) => self.stop_move(direction),
+            Action::HoldJump(held) => self.modifier_d[0] = held,
+            Action::HoldSkip(held) => self.modifier_d[1] = held,
+            Action::Step(direction) if playing => self.move_selected(direction),
+            Action::Tick if playing => {
+                if let Some(direction) = self.due_repeat() {
+                    self.apply(Action::Step(direction));
+                }
+            }
+            Action::Deselect => self.nonogram.selected_cell = None,
+            Action::CrossOut([axis, line, index]) if playing => {
+                let exists = self
+                    .nonogram
+                    .goal_nums
+                    .get(axis)
+                    .and_then(|lines| lines.get(line))
+                    .is_some_and(|nums| index < nums.len());
+                if exists {
+                    self.nonogram.toggle_manual_crossout(axis, line, index);
+                }
+            }
+            Action::CommitPencil if playing => self.nonogram.commit_maybes(),
+            Action::DiscardPencil if playing => self.nonogram.discard_maybes(),
+            Action::NextTool if playing && !self.board_d => self.tool = self.tool.next(),
+            Action::TogglePencil if playing => self.pencil = !self.pencil,
+            Action::SelectColor(index) if playing && index < self.nonogram.colors => {
+                self.color = index;
+            }
+            Action::NextColor if playing => {
+                self.color = (self.color + 1) % self.nonogram.colors;
+            }
+            Action::NextShape if playing && self.nonogram.ruleset == Ruleset::Triangles => {
+                self.half = match self.half {
+                    None => Some(Corner::TopLeft),
+                    Some(Corner::BottomLeft) => None,
+                    Some(corner) => Some(corner.next()),
+                };
+            }
+            Action::SetDimensions(dimensions)
+                if playing && DIMENSIONS_CHOICES.contains(&dimensions) =>
+            {
+                self.nonogram.next_dimensions = dimensions;
+            }
+            Action::BiggerBoard if playing => {
+                let dimensions_index = DIMENSIONS_CHOICES
+                    .iter()
+                    .position(|&r| r == self.nonogram.next_dimensions)
+                    .unwrap();
+                if dimensions_index < DIMENSIONS_CHOICES.len() - 1 {
+                    self.nonogram.next_dimensions = DIMENSIONS_CHOICES[dimensions_index + 1];
+                }
+            }
+            Action::SmallerBoard if playing => {
+                let dimensions_index = DIMENSIONS_CHOICES
+                    .iter()
+                    .position(|&r| r == self.nonogram.next_dimensions)
+                    .unwrap();
+                if dimensions_index > 0 {
+                    self.nonogram.next_dimensions = DIMENSIONS_CHOICES[dimensions_index - 1];
+                }
+            }
+            Action::NextMode if playing => {
+                self.nonogram.next_mode = self.nonogram.next_mode.next();
+            }
+            Action::NextPalette if playing => {
+                self.nonogram.next_colors = self.nonogram.next_colors % PALETTE.len() + 1;
+            }
+            Action::NextRuleset if playing => {
+                self.nonogram.next_ruleset = self.nonogram.next_ruleset.next();
+            }
+            Action::Restart if !self.controls_screen => self.restart(),
+            Action::NextPanel if playing && !self.board_d => {
+                if let Some(index) = self
+                    .clip
+                    .as_ref()
+                    .and_then(|clip| clip.next_unsolved(&self.nonogram))
+                {
+                    self.switch_panel(index);
+                }
+            }
+            Action::SwitchPanel(index) if playing => {
+                // Solved panels can't be switched to.
+                let unsolved = self.clip.as_ref().is_some_and(|clip| {
+                    index < clip.panel_count() && !clip.is_solved(index, &self.nonogram)
+                });
+                if unsolved {
+                    self.switch_panel(index);
+                }
+            }
+            Action::TogglePause if !self.controls_screen => {
+                if self.nonogram.is_paused() {
+                    self.nonogram.resume();
+                } else {
+                    self.nonogram.pause();
+                }
+            }
+            Action::Pause => self.nonogram.pause(),
+            Action::Resume if !self.nonogram.end_game_screen => self.nonogram.resume(),
+            Action::ShowStats if playing => {
+                self.stats_screen = true;
+                self.nonogram.selected_cell = None;
+            }
+            Action::ShowControls if playing => {
+                self.controls_screen = true;
+                self.nonogram.selected_cell = None;
+            }
+            Action::CloseMenu => {
+                self.stats_screen = false;
+                self.controls_screen = false;
+                self.controls_hover = None;
+                self.controls_scroll = 0;
+                self.rebinding = None;
+            }
+            Action::NextEditPolicy if self.controls_screen => {
+                self.controls.edit_policy = self.controls.edit_policy.next();
+                self.controls.save(&mut *self.storage);
+            }
+            Action::Save => self.save(),
+            Action::SavePicture if won => self.save_picture = true,
+            Action::BiggerPicture if won => {
+                self.picture_scale = (self.picture_scale + 1).min(PICTURE_SCALES.len() - 1);
+            }
+            Action::SmallerPicture if won => {
+                self.picture_scale = self.picture_scale.saturating_sub(1);
+            }
+            Action::TogglePictureClues if won => self.picture_clues = !self.picture_clues,
+            _ => (),
+        }
+
+        // Solving a panel of a clip moves on to the next unsolved panel. The win screen only comes up once every
+        // panel is solved.
+        if self.nonogram.end_game_screen && !self.nonogram.time_up && !self.nonogram.reset_board {
+            if let Some(index) = self
+                .clip
+                .as_ref()
+                .and_then(|clip| clip.next_unsolved(&self.nonogram))
+            {
+                self.switch_panel(index);
+            }
+        }
+
+        // Won games are recorded as soon as the win screen comes up.
+        if self.nonogram.end_game_screen && !self.nonogram.reset_board {
+            self.record_game();
+        }
+    }
+
+    /// Save the board, along with the other panels of a clip.
+    fn save(&mut self) {
+        match self.nonogram.save(&mut *self.storage) {
+            Err(why) => panic!("{}", why),
+            Ok(_) => println!("Successfully wrote to {}", SAVE_FILE),
+        }
 
-    /// Current status of new game button.
-    pub new_game_button: ButtonInteraction,
-}
+        // The other panels of a clip are saved separately.
+        match &self.clip {
+            Some(clip) => {
+                if let Err(why) = clip.save(&mut *self.storage) {
+                    println!("{}", why);
+                }
+            }
+            None => self.storage.remove(CLIP_FILE),
+        }
 
-/// Implementation for NonogramController.
-impl NonogramController {
-    /// Creates a new nonogram controller.
-    pub fn new(nonogram: NonogramBoard) -> NonogramController {
-        NonogramController {
-            controls: Default::default(),
-            nonogram,
-            cursor_pos: [0.0; 2],
-            mouse_d: [false; 2],
-            key_d: [false; 2],
-            loop_back: false,
-            board_d: false,
-            current_action: Cell::Empty,
-            dimensions_dropdown_menu: ButtonInteraction::None,
-            dimensions_dropdown_options: (0, ButtonInteraction::None),
-            restart_button: ButtonInteraction::None,
-            new_game_button: ButtonInteraction::None,
+        if let Some(replay) = &self.replay {
+            match replay.save(&mut *self.storage, &self.replay_name) {
+                Err(why) => println!("{}", why),
+                Ok(_) => println!("Successfully wrote to {}", self.replay_name),
+            }
         }
     }
 
-    /// Handles events.
+    /// Turns Piston events into actions.
+    ///
+    /// Hovering over and clicking the buttons of the window is handled here, since only the window has buttons.
+    /// Everything else is passed on to `apply`.
     //
     // Refer to this documentation for event traits: https://docs.rs/piston/0.49.0/piston/index.html#traits
     //
     // This triggers a Clippy warning for cognitive complexity. There's nothing that can be done about this, because
     // it's caused by the `GenericEvent` trait.
     #[allow(clippy::cognitive_complexity)]
-    pub fn event<E: GenericEvent>(
-        &mut self,
-        board_pos: [f64; 2],
-        size: [f64; 2],
-        dimensions_dropdown_menu_box: [f64; 4],
-        restart_box: [f64; 4],
-        new_game_box: [f64; 4],
-        e: &E,
-    ) {
+    pub fn event<E: GenericEvent>(&mut self, layout: &WindowLayout, e: &E) {
+        let WindowLayout {
+            position: board_pos,
+            board_dimensions: size,
+            dimensions_dropdown_menu_box,
+            restart_box,
+            new_game_box,
+            save_picture_box,
+            stats_box,
+            mode_box,
+            tool_box,
+            palette_box,
+            clip_map_box,
+            controls_box,
+            menu_box_rect,
+            menu_back_box,
+        } = *layout;
+
         // Debug code for figuring out the ID of a particular event.
         //println!("{:?}", e.event_id());
 
-        if self.nonogram.end_game_screen {
-            //if true {
+        // Automatically pause the game when the window loses focus, so the board can't be studied
+        // while the timer isn't looking.
+        if let Some(false) = e.focus_args() {
+            self.apply(Action::Pause);
+        }
+
+        // Check if pause key has been pressed.
+        if pressed(&self.controls.pause, e) {
+            self.apply(Action::TogglePause);
+        }
+
+        if let Some(control) = self.rebinding {
+            // The next button pressed on the controls screen is bound to the action that was clicked.
+            //
+            // Backspace removes every keybinding from the action, and escape cancels.
+            if let Some(button) = e.press_args() {
+                match button {
+                    Button::Keyboard(Key::Escape) => (),
+                    Button::Keyboard(Key::Backspace) => self.controls.get_mut(control).clear(),
+                    _ => self.controls.bind(control, button),
+                }
+                self.controls.save(&mut *self.storage);
+                self.rebinding = None;
+            }
+
+            if e.release_args().is_some() {
+                self.mouse_d = [false; 2];
+            }
+        } else if self.stats_screen || self.controls_screen {
+            if let Some(pos) = e.mouse_cursor_args() {
+                self.cursor_pos = [pos[0], pos[1]];
+
+                // Check that coordinates are inside a row of the controls screen that isn't scrolled out of view.
+                let row_y = self.cursor_pos[1] - menu_box_rect[1] - CONTROLS_ROW_OFFSET;
+                let shown_row = (row_y / CONTROLS_ROW_HEIGHT) as usize;
+                if self.controls_screen
+                    && self.cursor_pos[0] >= menu_box_rect[0]
+                    && self.cursor_pos[0] <= (menu_box_rect[0] + menu_box_rect[2])
+                    && row_y >= 0.0
+                    && shown_row < CONTROLS_ROWS_SHOWN
+                    && self.controls_scroll + shown_row < self.controls_rows()
+                {
+                    self.controls_hover = Some(self.controls_scroll + shown_row);
+                } else {
+                    self.controls_hover = None;
+                }
+
+                // Check that coordinates are inside back button.
+                if self.cursor_pos[0] >= menu_back_box[0]
+                    && self.cursor_pos[0] <= (menu_back_box[0] + menu_back_box[2])
+                    && self.cursor_pos[1] >= menu_back_box[1]
+                    && self.cursor_pos[1] <= (menu_back_box[1] + menu_back_box[3])
+                {
+                    if self.menu_back_button == ButtonInteraction::None {
+                        self.menu_back_button = ButtonInteraction::Hover;
+                    }
+                } else if self.menu_back_button == ButtonInteraction::Hover
+                    || (self.menu_back_button == ButtonInteraction::Select && self.mouse_d[0])
+                {
+                    self.menu_back_button = ButtonInteraction::None;
+                }
+            }
+
+            // Check if left mouse button has been pressed.
+            if let Some(Button::Mouse(MouseButton::Left)) = e.press_args() {
+                self.mouse_d[0] = true;
+
+                match self.controls_hover {
+                    Some(row) if row < Control::ALL.len() => {
+                        self.rebinding = Some(Control::ALL[row])
+                    }
+                    Some(_) => self.apply(Action::NextEditPolicy),
+                    None => (),
+                }
+
+                match self.menu_back_button {
+                    ButtonInteraction::Select => {
+                        self.menu_back_button = ButtonInteraction::None;
+                    }
+                    ButtonInteraction::Hover => {
+                        self.menu_back_button = ButtonInteraction::Select;
+                    }
+                    _ => (),
+                }
+            }
+
+            // Check if left mouse button has been released.
+            if let Some(Button::Mouse(MouseButton::Left)) = e.release_args() {
+                self.mouse_d[0] = false;
+                self.apply(Action::CancelDrag);
+
+                // Check if left mouse button was released while interacting with back button.
+                if self.menu_back_button == ButtonInteraction::Select {
+                    self.apply(Action::CloseMenu);
+                    self.menu_back_button = ButtonInteraction::None;
+                }
+            }
+
+            // Check if delete key has been released on the controls screen, which restores the default keybindings.
+            if self.controls_screen {
+                if let Some(Button::Keyboard(Key::Delete)) = e.release_args() {
+                    self.controls = NonogramControls::default();
+                    self.controls.save(&mut *self.storage);
+                }
+
+                // The mouse wheel, the arrow keys, and page up and page down scroll through the rows.
+                match e.mouse_scroll_args() {
+                    Some(scroll) if scroll[1] > 0.0 => self.scroll_controls(-1),
+                    Some(scroll) if scroll[1] < 0.0 => self.scroll_controls(1),
+                    _ => (),
+                }
+                match e.press_args() {
+                    Some(Button::Keyboard(Key::Up)) => self.scroll_controls(-1),
+                    Some(Button::Keyboard(Key::Down)) => self.scroll_controls(1),
+                    Some(Button::Keyboard(Key::PageUp)) => {
+                        self.scroll_controls(-(CONTROLS_ROWS_SHOWN as isize))
+                    }
+                    Some(Button::Keyboard(Key::PageDown)) => {
+                        self.scroll_controls(CONTROLS_ROWS_SHOWN as isize)
+                    }
+                    _ => (),
+                }
+            }
+        } else if self.nonogram.end_game_screen {
             if let Some(pos) = e.mouse_cursor_args() {
                 self.cursor_pos = [pos[0], pos[1]];
 
@@ -162,6 +1603,21 @@ impl NonogramController {
                 {
                     self.new_game_button = ButtonInteraction::None;
                 }
+
+                // Check that coordinates are inside save picture button.
+                if self.cursor_pos[0] >= save_picture_box[0]
+                    && self.cursor_pos[0] <= (save_picture_box[0] + save_picture_box[2])
+                    && self.cursor_pos[1] >= save_picture_box[1]
+                    && self.cursor_pos[1] <= (save_picture_box[1] + save_picture_box[3])
+                {
+                    if self.save_picture_button == ButtonInteraction::None {
+                        self.save_picture_button = ButtonInteraction::Hover;
+                    }
+                } else if self.save_picture_button == ButtonInteraction::Hover
+                    || (self.save_picture_button == ButtonInteraction::Select && self.mouse_d[0])
+                {
+                    self.save_picture_button = ButtonInteraction::None;
+                }
             }
 
             // Check if left mouse button has been pressed.
@@ -177,18 +1633,58 @@ impl NonogramController {
                     }
                     _ => (),
                 }
+
+                match self.save_picture_button {
+                    ButtonInteraction::Select => {
+                        self.save_picture_button = ButtonInteraction::None;
+                    }
+                    ButtonInteraction::Hover => {
+                        self.save_picture_button = ButtonInteraction::Select;
+                    }
+                    _ => (),
+                }
             }
 
             // Check if left mouse button has been released.
             if let Some(Button::Mouse(MouseButton::Left)) = e.release_args() {
                 self.mouse_d[0] = false;
-                self.board_d = false;
+                self.apply(Action::CancelDrag);
 
                 // Check if left mouse button was released while interacting with new game button.
                 if self.new_game_button == ButtonInteraction::Select {
-                    self.nonogram.reset_board = true;
+                    self.apply(Action::Restart);
                     self.new_game_button = ButtonInteraction::None;
                 }
+
+                // Check if left mouse button was released while interacting with save picture button.
+                if self.save_picture_button == ButtonInteraction::Select {
+                    self.apply(Action::SavePicture);
+                    self.save_picture_button = ButtonInteraction::Hover;
+                }
+            }
+
+            // Pick the cell size of the saved picture, and whether or not it has hint numbers.
+            if released(&self.controls.smaller_picture, e) {
+                self.apply(Action::SmallerPicture);
+            }
+            if released(&self.controls.bigger_picture, e) {
+                self.apply(Action::BiggerPicture);
+            }
+            if released(&self.controls.toggle_picture_clues, e) {
+                self.apply(Action::TogglePictureClues);
+            }
+        } else if self.nonogram.is_paused() {
+            if let Some(pos) = e.mouse_cursor_args() {
+                self.cursor_pos = [pos[0], pos[1]];
+            }
+
+            // Check if left mouse button has been pressed on the hidden board.
+            if pressed(&self.controls.mouse_fill, e) {
+                let x = self.cursor_pos[0] - board_pos[0];
+                let y = self.cursor_pos[1] - board_pos[1];
+                if x >= 0.0 && x < size[0] && y >= 0.0 && y < size[1] {
+                    self.apply(Action::Resume);
+                }
             }
         } else {
             // Check if mouse button has been moved within window and save its location to pos: [f64; 2]
@@ -230,7 +1726,7 @@ impl NonogramController {
                         / (dimensions_dropdown_menu_box[3] + 5.0);
                     self.dimensions_dropdown_options =
                         (dimension_sub_index as usize, ButtonInteraction::Hover);
-                    self.nonogram.selected_cell = None;
+                    self.apply(Action::Deselect);
                 } else {
                     self.dimensions_dropdown_options = (0, ButtonInteraction::None);
 
@@ -239,16 +1735,9 @@ impl NonogramController {
                         // Compute the cell position.
                         let cell_x = (x / size[0] * self.nonogram.dimensions[0] as f64) as usize;
                         let cell_y = (y / size[1] * self.nonogram.dimensions[1] as f64) as usize;
-                        self.nonogram.selected_cell = Some([cell_x, cell_y]);
-                        if self.nonogram.get([cell_x, cell_y]) == self.current_action
-                            && self.board_d
-                        {
-                            if self.mouse_d[0] {
-                                self.nonogram.set([cell_x, cell_y], Cell::Filled);
-                            } else if self.mouse_d[1] {
-                                self.nonogram.set([cell_x, cell_y], Cell::Marked);
-                            }
-                        }
+                        self.apply(Action::Hover(Some([cell_x, cell_y])));
+                    } else {
+                        self.apply(Action::Hover(None));
                     }
                 }
 
@@ -266,23 +1755,121 @@ impl NonogramController {
                 {
                     self.restart_button = ButtonInteraction::None;
                 }
+
+                // Check that coordinates are inside stats button.
+                if self.cursor_pos[0] >= stats_box[0]
+                    && self.cursor_pos[0] <= (stats_box[0] + stats_box[2])
+                    && self.cursor_pos[1] >= stats_box[1]
+                    && self.cursor_pos[1] <= (stats_box[1] + stats_box[3])
+                {
+                    if self.stats_button == ButtonInteraction::None {
+                        self.stats_button = ButtonInteraction::Hover;
+                    }
+                } else if self.stats_button == ButtonInteraction::Hover
+                    || (self.stats_button == ButtonInteraction::Select && self.mouse_d[0])
+                {
+                    self.stats_button = ButtonInteraction::None;
+                }
+
+                // Check that coordinates are inside controls button.
+                if self.cursor_pos[0] >= controls_box[0]
+                    && self.cursor_pos[0] <= (controls_box[0] + controls_box[2])
+                    && self.cursor_pos[1] >= controls_box[1]
+                    && self.cursor_pos[1] <= (controls_box[1] + controls_box[3])
+                {
+                    if self.controls_button == ButtonInteraction::None {
+                        self.controls_button = ButtonInteraction::Hover;
+                    }
+                } else if self.controls_button == ButtonInteraction::Hover
+                    || (self.controls_button == ButtonInteraction::Select && self.mouse_d[0])
+                {
+                    self.controls_button = ButtonInteraction::None;
+                }
+
+                // Check that coordinates are inside game mode button.
+                if self.cursor_pos[0] >= mode_box[0]
+                    && self.cursor_pos[0] <= (mode_box[0] + mode_box[2])
+                    && self.cursor_pos[1] >= mode_box[1]
+                    && self.cursor_pos[1] <= (mode_box[1] + mode_box[3])
+                {
+                    if self.mode_button == ButtonInteraction::None {
+                        self.mode_button = ButtonInteraction::Hover;
+                    }
+                } else if self.mode_button == ButtonInteraction::Hover
+                    || (self.mode_button == ButtonInteraction::Select && self.mouse_d[0])
+                {
+                    self.mode_button = ButtonInteraction::None;
+                }
+
+                // Check that coordinates are inside tool button.
+                if self.cursor_pos[0] >= tool_box[0]
+                    && self.cursor_pos[0] <= (tool_box[0] + tool_box[2])
+                    && self.cursor_pos[1] >= tool_box[1]
+                    && self.cursor_pos[1] <= (tool_box[1] + tool_box[3])
+                {
+                    if self.tool_button == ButtonInteraction::None {
+                        self.tool_button = ButtonInteraction::Hover;
+                    }
+                } else if self.tool_button == ButtonInteraction::Hover
+                    || (self.tool_button == ButtonInteraction::Select && self.mouse_d[0])
+                {
+                    self.tool_button = ButtonInteraction::None;
+                }
             }
 
             // Check if left mouse button has been pressed.
-            if Some(self.controls.mouse_fill) == e.press_args() {
+            if pressed(&self.controls.mouse_fill, e) {
                 self.mouse_d[0] = true;
 
-                if let Some(ind) = self.nonogram.selected_cell {
-                    self.board_d = true;
-                    self.current_action = self.nonogram.get(ind);
+                if let Some(ind) = self.hover_cell {
+                    self.apply(Action::StartFill(ind));
+                } else if let Some(hint) = self.hint_at(board_pos, size) {
+                    self.apply(Action::CrossOut(hint));
+                }
+
+                // Check if a panel on the overview map of a clip has been clicked.
+                let clicked_panel = self.clip.as_ref().and_then(|clip| {
+                    clip.panel_rects(clip_map_box).iter().position(|rect| {
+                        self.cursor_pos[0] >= rect[0]
+                            && self.cursor_pos[0] < (rect[0] + rect[2])
+                            && self.cursor_pos[1] >= rect[1]
+                            && self.cursor_pos[1] < (rect[1] + rect[3])
+                    })
+                });
+                if let Some(index) = clicked_panel {
+                    self.apply(Action::SwitchPanel(index));
+                }
+
+                // Check if the palette selector has been clicked. Every palette color is a square as tall as the
+                // palette selector. Anything after the last color changes the palette size of the next board when
+                // clicking its top half, and the ruleset of the next board when clicking its bottom half.
+                if self.cursor_pos[0] >= palette_box[0]
+                    && self.cursor_pos[0] <= (palette_box[0] + palette_box[2])
+                    && self.cursor_pos[1] >= palette_box[1]
+                    && self.cursor_pos[1] <= (palette_box[1] + palette_box[3])
+                {
+                    let index =
+                        ((self.cursor_pos[0] - palette_box[0]) / (palette_box[3] + 5.0)) as usize;
+                    if index >= PALETTE.len() {
+                        if self.cursor_pos[1] < palette_box[1] + palette_box[3] / 2.0 {
+                            self.apply(Action::NextPalette);
+                        } else {
+                            self.apply(Action::NextRuleset);
+                        }
+                    } else {
+                        self.apply(Action::SelectColor(index));
+                    }
                 }
 
                 match self.dimensions_dropdown_menu {
                     ButtonInteraction::Select => {
                         self.dimensions_dropdown_menu = ButtonInteraction::None;
                         if self.dimensions_dropdown_options.1 == ButtonInteraction::Hover {
-                            self.nonogram.next_dimensions =
-                                DIMENSIONS_CHOICES[self.dimensions_dropdown_options.0];
+                            if let Some(&dimensions) =
+                                DIMENSIONS_CHOICES.get(self.dimensions_dropdown_options.0)
+                            {
+                                self.apply(Action::SetDimensions(dimensions));
+                            }
                             self.dimensions_dropdown_options = (0, ButtonInteraction::None);
                         }
                     }
@@ -301,34 +1888,106 @@ impl NonogramController {
                     }
                     _ => (),
                 }
+
+                match self.stats_button {
+                    ButtonInteraction::Select => {
+                        self.stats_button = ButtonInteraction::None;
+                    }
+                    ButtonInteraction::Hover => {
+                        self.stats_button = ButtonInteraction::Select;
+                    }
+                    _ => (),
+                }
+
+                match self.mode_button {
+                    ButtonInteraction::Select => {
+                        self.mode_button = ButtonInteraction::None;
+                    }
+                    ButtonInteraction::Hover => {
+                        self.mode_button = ButtonInteraction::Select;
+                    }
+                    _ => (),
+                }
+
+                match self.controls_button {
+                    ButtonInteraction::Select => {
+                        self.controls_button = ButtonInteraction::None;
+                    }
+                    ButtonInteraction::Hover => {
+                        self.controls_button = ButtonInteraction::Select;
+                    }
+                    _ => (),
+                }
+
+                match self.tool_button {
+                    ButtonInteraction::Select => {
+                        self.tool_button = ButtonInteraction::None;
+                    }
+                    ButtonInteraction::Hover => {
+                        self.tool_button = ButtonInteraction::Select;
+                    }
+                    _ => (),
+                }
             }
 
             // Check if right mouse button has been pressed.
-            if Some(self.controls.mouse_mark) == e.press_args() {
+            if pressed(&self.controls.mouse_mark, e) {
                 self.mouse_d[1] = true;
 
-                if let Some(ind) = self.nonogram.selected_cell {
-                    self.board_d = true;
-                    self.current_action = self.nonogram.get(ind);
+                if let Some(ind) = self.hover_cell {
+                    self.apply(Action::StartMark(ind));
                 }
             }
 
             // Check if left mouse button has been released.
-            if Some(self.controls.mouse_fill) == e.release_args() {
+            if released(&self.controls.mouse_fill, e) {
+                if self.mouse_d[0] {
+                    self.apply(Action::EndDrag);
+                } else {
+                    self.apply(Action::CancelDrag);
+                }
                 self.mouse_d[0] = false;
-                self.board_d = false;
 
                 // Check if left mouse button was released while interacting with restart button.
                 if self.restart_button == ButtonInteraction::Select {
-                    self.nonogram.reset_board = true;
+                    self.apply(Action::Restart);
                     self.restart_button = ButtonInteraction::None;
                 }
+
+                // Check if left mouse button was released while interacting with stats button.
+                if self.stats_button == ButtonInteraction::Select {
+                    self.apply(Action::ShowStats);
+                    self.stats_button = ButtonInteraction::None;
+                }
+
+                // Check if left mouse button was released while interacting with controls button.
+                if self.controls_button == ButtonInteraction::Select {
+                    self.apply(Action::ShowControls);
+                    self.controls_button = ButtonInteraction::None;
+                }
+
+                // Check if left mouse button was released while interacting with game mode button.
+                if self.mode_button == ButtonInteraction::Select {
+                    self.apply(Action::NextMode);
+                    self.mode_button = ButtonInteraction::Hover;
+                }
+
+                // Check if left mouse button was released while interacting with tool button.
+                if self.tool_button == ButtonInteraction::Select {
+                    self.apply(Action::NextTool);
+                    self.tool_button = ButtonInteraction::Hover;
+                }
             }
 
-            // Check if right mouse button has been released.
-            if Some(self.controls.mouse_mark) == e.release_args() {
+            // Check if right mouse button has been released. Letting go of it while the left mouse button is still
+            // held down drops the drag.
+            if released(&self.controls.mouse_mark, e) {
+                if self.mouse_d[1] && !self.mouse_d[0] {
+                    self.apply(Action::EndDrag);
+                } else {
+                    self.apply(Action::CancelDrag);
+                }
                 self.mouse_d[1] = false;
-                self.board_d = false;
             }
 
             // Check if ESC key has been released.
@@ -338,86 +1997,85 @@ impl NonogramController {
                 println!("Escape key pressed");
             }
 
-            // Check if key for increasing dimensions has been released.
-            if Some(self.controls.dim_up) == e.release_args() {
-                let dimensions_index = DIMENSIONS_CHOICES
-                    .iter()
-                    .position(|&r| r == self.nonogram.next_dimensions)
-                    .unwrap();
-                if dimensions_index < DIMENSIONS_CHOICES.len() - 1 {
-                    self.nonogram.next_dimensions = DIMENSIONS_CHOICES[dimensions_index + 1];
+            // Every keybinding that does something once it's released.
+            let release_actions = [
+                (Control::DimUp, Action::BiggerBoard),
+                (Control::DimDown, Action::SmallerBoard),
+                (Control::NextMode, Action::NextMode),
+                (Control::Pencil, Action::TogglePencil),
+                (Control::CommitPencil, Action::CommitPencil),
+                (Control::DiscardPencil, Action::DiscardPencil),
+                (Control::NextColor, Action::NextColor),
+                (Control::NextPalette, Action::NextPalette),
+                (Control::NextShape, Action::NextShape),
+                (Control::NextRuleset, Action::NextRuleset),
+                (Control::NextPanel, Action::NextPanel),
+                (Control::NextTool, Action::NextTool),
+            ];
+            for &(control, action) in release_actions.iter() {
+                if released(self.controls.get(control), e) {
+                    self.apply(action);
                 }
             }
 
-            // Check if key for decreasing dimensions has been released.
-            if Some(self.controls.dim_down) == e.release_args() {
-                let dimensions_index = DIMENSIONS_CHOICES
-                    .iter()
-                    .position(|&r| r == self.nonogram.next_dimensions)
-                    .unwrap();
-                if dimensions_index > 0 {
-                    self.nonogram.next_dimensions = DIMENSIONS_CHOICES[dimensions_index - 1];
+            // Check if movement keys have been pressed.
+            let moves = [
+                (Control::MoveUp, Directions::Up),
+                (Control::MoveDown, Directions::Down),
+                (Control::MoveLeft, Directions::Left),
+                (Control::MoveRight, Directions::Right),
+            ];
+            for &(control, direction) in moves.iter() {
+                if pressed(self.controls.get(control), e) {
+                    self.apply(Action::Move(direction));
                 }
             }
 
-            // Check if move up key has been pressed.
-            if Some(self.controls.move_up) == e.press_args() {
-                self.nonogram
-                    .change_selected(Directions::Up, self.loop_back);
-            }
-
-            // Check if move down key has been pressed.
-            if Some(self.controls.move_down) == e.press_args() {
-                self.nonogram
-                    .change_selected(Directions::Down, self.loop_back);
-            }
-
-            // Check if move left key has been pressed.
-            if Some(self.controls.move_left) == e.press_args() {
-                self.nonogram
-                    .change_selected(Directions::Left, self.loop_back);
-            }
-
-            // Check if move right key has been pressed
-            if Some(self.controls.move_right) == e.press_args() {
-                self.nonogram
-                    .change_selected(Directions::Right, self.loop_back);
-            }
-
-            // Detect keyboard movement keys
-            if e.press_args() == Some(self.controls.move_up)
-                || e.press_args() == Some(self.controls.move_down)
-                || e.press_args() == Some(self.controls.move_left)
-                || e.press_args() == Some(self.controls.move_right)
-            {
-                self.loop_back = false;
-                if let Some(ind) = self.nonogram.selected_cell {
-                    if self.nonogram.get(ind) == self.current_action {
-                        if self.key_d[0] {
-                            self.nonogram.set(ind, Cell::Filled);
-                        } else if self.key_d[1] {
-                            self.nonogram.set(ind, Cell::Marked);
+            // Check if a controller stick has been tilted past the deadzone. Tilting a stick works just like pressing
+            // a movement key, and letting it go back to the center works just like releasing one.
+            if let Some(args) = e.controller_axis_args() {
+                let axis = match args.axis {
+                    STICK_X_AXIS => Some(0),
+                    STICK_Y_AXIS => Some(1),
+                    _ => None,
+                };
+                if let Some(axis) = axis {
+                    let tilt = if args.position <= -STICK_DEADZONE {
+                        -1
+                    } else if args.position >= STICK_DEADZONE {
+                        1
+                    } else {
+                        0
+                    };
+                    if tilt != self.stick_d[axis] {
+                        self.stick_d[axis] = tilt;
+                        match (axis, tilt) {
+                            (0, -1) => self.apply(Action::Move(Directions::Left)),
+                            (0, 1) => self.apply(Action::Move(Directions::Right)),
+                            (1, -1) => self.apply(Action::Move(Directions::Up)),
+                            (1, 1) => self.apply(Action::Move(Directions::Down)),
+                            (0, _) => {
+                                self.apply(Action::StopMove(Directions::Left));
+                                self.apply(Action::StopMove(Directions::Right));
+                            }
+                            _ => {
+                                self.apply(Action::StopMove(Directions::Up));
+                                self.apply(Action::StopMove(Directions::Down));
+                            }
                         }
                     }
                 }
             }
 
-            // Check if fill key has been pressed.
-            if Some(self.controls.key_fill) == e.press_args() && !self.key_d[0] {
-                self.key_d[0] = true;
-                if let Some(ind) = self.nonogram.selected_cell {
-                    self.current_action = self.nonogram.get(ind);
-                    self.nonogram.set(ind, Cell::Filled);
-                }
-            }
+            // Keep moving the selected cell while a movement keybinding is held down.
+            self.apply(Action::Tick);
 
-            // Check if mark key has been pressed.
-            if Some(self.controls.key_mark) == e.press_args() && !self.key_d[1] {
-                self.key_d[1] = true;
-                if let Some(ind) = self.nonogram.selected_cell {
-                    self.current_action = self.nonogram.get(ind);
-                    self.nonogram.set(ind, Cell::Marked);
-                }
+            // Check if fill or mark keys have been pressed.
+            if pressed(&self.controls.key_fill, e) {
+                self.apply(Action::HoldFill(true));
+            }
+            if pressed(&self.controls.key_mark, e) {
+                self.apply(Action::HoldMark(true));
             }
         }
 
@@ -428,69 +2086,413 @@ impl NonogramController {
         // the option ".exit_on_esc(true)" enabled in main.rs during the window's initial creation though, so
         // that option isn't enabled.
         //
-        // This might be useful later if we intend to save any user progress. The program will run everything in
-        // this block before it actually closes the program.
+        // The program will run everything in this block before it actually closes the program.
         if let Some(_window_closed) = e.close_args() {
-            let path = Path::new("savedata.json");
-            let display = path.display();
-
-            let file = match File::create(&path) {
-                Err(why) => panic!("couldn't create {}: {}", display, why.description()),
-                Ok(file) => file,
-            };
-
-            // Serialize it to a JSON string.
-            //let j = serde_json::to_string(&self.nonogram.goal_nums);
-
-            //println!("{:?}", j);
-            let save_data = json!({
-                "dimensions": self.nonogram.dimensions,
-                "next_dimensions": self.nonogram.next_dimensions,
-                "data": self.nonogram.data,
-                "goal_nums": self.nonogram.goal_nums,
-                "count_black": self.nonogram.count_black,
-                "goal_black": self.nonogram.goal_black,
-                "duration": self.nonogram.duration,
-                "end_game_screen": self.nonogram.end_game_screen,
-            });
-
-            match serde_json::to_writer_pretty(file, &save_data) {
-                Err(why) => panic!("Couldn't write to {}: {}", display, why.description()),
-                Ok(_) => println!("Successfully wrote to {}", display),
-            }
-
+            self.apply(Action::Save);
             println!("Nonogram game closed.");
         }
 
         // Check if restart key has been released.
-        if Some(self.controls.restart) == e.release_args() {
-            self.nonogram.reset_board = true;
+        if released(&self.controls.restart, e) {
+            self.apply(Action::Restart);
         }
 
-        if e.release_args() == Some(self.controls.move_up)
-            || e.release_args() == Some(self.controls.move_down)
-            || e.release_args() == Some(self.controls.move_left)
-            || e.release_args() == Some(self.controls.move_right)
-        {
-            if let Some(ind) = self.nonogram.selected_cell {
-                if ind[1] == 0
-                    || ind[1] == self.nonogram.dimensions[1] - 1
-                    || ind[0] == 0
-                    || ind[0] == self.nonogram.dimensions[0] - 1
-                {
-                    self.loop_back = true;
-                }
+        // Check if movement, jump, skip, fill and mark keys have been released. These are checked no matter what's
+        // on screen, so nothing stays held down after leaving the board.
+        let moves = [
+            (Control::MoveUp, Directions::Up),
+            (Control::MoveDown, Directions::Down),
+            (Control::MoveLeft, Directions::Left),
+            (Control::MoveRight, Directions::Right),
+        ];
+        for &(control, direction) in moves.iter() {
+            if released(self.controls.get(control), e) {
+                self.apply(Action::StopMove(direction));
             }
         }
+        if pressed(&self.controls.jump, e) {
+            self.apply(Action::HoldJump(true));
+        }
+        if released(&self.controls.jump, e) {
+            self.apply(Action::HoldJump(false));
+        }
+        if pressed(&self.controls.skip, e) {
+            self.apply(Action::HoldSkip(true));
+        }
+        if released(&self.controls.skip, e) {
+            self.apply(Action::HoldSkip(false));
+        }
+        if released(&self.controls.key_fill, e) {
+            self.apply(Action::HoldFill(false));
+        }
+        if released(&self.controls.key_mark, e) {
+            self.apply(Action::HoldMark(false));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{column_hint_y, row_hint_x, STATS_FILE};
+
+    #[test]
+    fn test_line_cells() {
+        assert_eq!(line_cells([2, 2], [2, 2]), vec![[2, 2]]);
+        assert_eq!(
+            line_cells([0, 1], [3, 1]),
+            vec![[0, 1], [1, 1], [2, 1], [3, 1]]
+        );
+        assert_eq!(line_cells([1, 3], [1, 1]), vec![[1, 3], [1, 2], [1, 1]]);
+        assert_eq!(line_cells([0, 0], [2, 2]), vec![[0, 0], [1, 1], [2, 2]]);
+    }
+
+    #[test]
+    fn test_default_controls() {
+        let controls = NonogramControls::default();
+        assert!(controls.conflicts().is_empty(), "Defaults don't conflict.");
+        assert_eq!(
+            controls.get(Control::KeyFill),
+            &vec![
+                Button::Keyboard(Key::J),
+                Button::Controller(ControllerButton::new(0, PAD_A))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edit_policy() {
+        assert_eq!(
+            EditPolicy::Toggle.apply(Cell::Filled, Cell::Marked),
+            Cell::Empty,
+            "Toggling clears a filled cell no matter what."
+        );
+        assert_eq!(
+            EditPolicy::Overwrite.apply(Cell::Filled, Cell::Marked),
+            Cell::Marked,
+            "Overwriting marks a filled cell."
+        );
+        assert_eq!(
+            EditPolicy::Overwrite.apply(Cell::Marked, Cell::Marked),
+            Cell::Empty,
+            "Overwriting clears a cell with the same value."
+        );
+        for policy in [EditPolicy::Toggle, EditPolicy::Overwrite].iter() {
+            assert_eq!(
+                policy.apply(Cell::Empty, Cell::Filled),
+                Cell::Filled,
+                "Empty cells are always filled."
+            );
+        }
+    }
+
+    #[test]
+    fn test_bind() {
+        let mut controls = NonogramControls::default();
+        controls.bind(Control::KeyFill, Button::Keyboard(Key::Space));
+        controls.bind(Control::KeyFill, Button::Keyboard(Key::Space));
+        assert_eq!(
+            controls.get(Control::KeyFill).len(),
+            3,
+            "Multiple keys per action, without duplicates."
+        );
+        assert!(!controls.has_conflict(Control::KeyFill));
+
+        controls.bind(Control::Restart, Button::Keyboard(Key::J));
+        assert_eq!(
+            controls.conflicts(),
+            vec![(Button::Keyboard(Key::J), Control::KeyFill, Control::Restart)]
+        );
+        assert!(controls.has_conflict(Control::KeyFill));
+        assert!(controls.has_conflict(Control::Restart));
+        assert!(!controls.has_conflict(Control::KeyMark));
+    }
+
+    #[test]
+    fn test_controls_file() {
+        let controls: NonogramControls =
+            serde_json::from_str(r#"{ "key_fill": [{ "Keyboard": "Space" }] }"#).unwrap();
+        assert_eq!(
+            controls.get(Control::KeyFill),
+            &vec![Button::Keyboard(Key::Space)]
+        );
+        assert_eq!(
+            controls.get(Control::KeyMark),
+            NonogramControls::default().get(Control::KeyMark),
+            "Missing actions keep their defaults."
+        );
+        assert_eq!(
+            controls.get(Control::TogglePictureClues),
+            &vec![Button::Keyboard(Key::Tab)]
+        );
+        assert!(
+            NonogramControls::default().conflicts().is_empty(),
+            "No button is bound to two actions by default."
+        );
+    }
+
+    #[test]
+    fn test_any_controller() {
+        let mut controls = NonogramControls::default();
+        controls.bind(
+            Control::KeyFill,
+            Button::Controller(ControllerButton::new(3, PAD_B)),
+        );
+        assert!(
+            controls
+                .get(Control::KeyFill)
+                .contains(&Button::Controller(ControllerButton::new(0, PAD_B))),
+            "Controller buttons are bound regardless of which controller pressed them."
+        );
+        assert!(controls.has_conflict(Control::KeyMark));
+    }
+
+    /// A controller for a fresh 5x5 board that doesn't touch any save files.
+    fn test_controller() -> NonogramController {
+        NonogramController::new(
+            NonogramBoard::new([5, 5]),
+            Box::new(crate::nonogram_storage::MemoryStorage::new()),
+        )
+    }
 
-        // Check if fill key has been released.
-        if Some(self.controls.key_fill) == e.release_args() {
-            self.key_d[0] = false;
+    #[test]
+    fn test_apply_drag() {
+        let mut controller = test_controller();
+        controller.apply(Action::StartFill([0, 0]));
+        controller.apply(Action::Hover(Some([1, 0])));
+        controller.apply(Action::Hover(Some([2, 0])));
+        controller.apply(Action::EndDrag);
+        controller.apply(Action::Hover(Some([3, 0])));
+        for col in 0..3 {
+            assert_eq!(
+                controller.nonogram.data[col][0],
+                Cell::Filled,
+                "Every cell dragged across is filled."
+            );
         }
+        assert_eq!(
+            controller.nonogram.data[3][0],
+            Cell::Empty,
+            "Hovering after the drag ended doesn't fill."
+        );
+        assert_eq!(controller.nonogram.selected_cell, Some([3, 0]));
+
+        controller.apply(Action::StartMark([4, 4]));
+        controller.apply(Action::CancelDrag);
+        assert_eq!(controller.nonogram.data[4][4], Cell::Marked);
+    }
 
-        // Check if mark key has been released.
-        if Some(self.controls.key_mark) == e.release_args() {
-            self.key_d[1] = false;
+    #[test]
+    fn test_apply_keys() {
+        let mut controller = test_controller();
+        controller.apply(Action::Hover(Some([0, 0])));
+        controller.apply(Action::HoldFill(true));
+        controller.apply(Action::Move(Directions::Down));
+        controller.apply(Action::StopMove(Directions::Down));
+        controller.apply(Action::HoldFill(false));
+        controller.apply(Action::Move(Directions::Down));
+        assert_eq!(controller.nonogram.data[0][0], Cell::Filled);
+        assert_eq!(
+            controller.nonogram.data[0][1],
+            Cell::Filled,
+            "Moving while holding fill fills."
+        );
+        assert_eq!(controller.nonogram.data[0][2], Cell::Empty);
+        assert_eq!(controller.nonogram.selected_cell, Some([0, 2]));
+    }
+
+    #[test]
+    fn test_save_picture() {
+        let mut controller = test_controller();
+        controller.apply(Action::SavePicture);
+        controller.apply(Action::TogglePictureClues);
+        assert!(
+            !controller.save_picture && !controller.picture_clues,
+            "Pictures can only be saved from the win screen."
+        );
+
+        for col in 0..5 {
+            for row in 0..5 {
+                if controller.nonogram.goal_data[col][row] == Cell::Filled {
+                    controller.apply(Action::StartFill([col, row]));
+                    controller.apply(Action::EndDrag);
+                }
+            }
         }
+        assert!(controller.nonogram.end_game_screen);
+
+        controller.apply(Action::BiggerPicture);
+        controller.apply(Action::BiggerPicture);
+        controller.apply(Action::BiggerPicture);
+        controller.apply(Action::TogglePictureClues);
+        controller.apply(Action::SavePicture);
+        assert!(controller.save_picture);
+        let picture = controller.picture_to_save();
+        assert_eq!(
+            picture.scale,
+            PICTURE_SCALES[PICTURE_SCALES.len() - 1],
+            "Cells don't get bigger than the biggest choice."
+        );
+        assert!(picture.clues.is_some());
+        assert_eq!(picture.cells, controller.nonogram.data);
+
+        controller.apply(Action::Restart);
+        controller.start_next_board();
+        assert!(!controller.save_picture);
+    }
+
+    #[test]
+    fn test_scroll_controls() {
+        let mut controller = test_controller();
+        controller.apply(Action::ShowControls);
+        controller.controls_hover = Some(3);
+        controller.scroll_controls(-1);
+        assert_eq!(
+            controller.controls_scroll, 0,
+            "The first row can't scroll down."
+        );
+        assert_eq!(controller.controls_hover, Some(3));
+
+        controller.scroll_controls(CONTROLS_ROWS_SHOWN as isize * 4);
+        assert_eq!(
+            controller.controls_scroll + CONTROLS_ROWS_SHOWN,
+            controller.controls_rows(),
+            "Scrolling stops once the last row is shown."
+        );
+        assert_eq!(
+            controller.controls_hover,
+            Some(controller.controls_scroll + 3),
+            "The hovered row is whichever row is under the cursor after scrolling."
+        );
+
+        controller.apply(Action::CloseMenu);
+        assert_eq!(controller.controls_scroll, 0);
+    }
+
+    #[test]
+    fn test_next_edit_policy() {
+        let mut controller = test_controller();
+        controller.apply(Action::NextEditPolicy);
+        assert_eq!(
+            controller.controls.edit_policy,
+            EditPolicy::Toggle,
+            "The edit policy only changes on the controls screen."
+        );
+
+        controller.apply(Action::ShowControls);
+        controller.apply(Action::NextEditPolicy);
+        assert_eq!(controller.controls.edit_policy, EditPolicy::Overwrite);
+        assert_eq!(
+            NonogramControls::load(&mut *controller.storage).edit_policy,
+            EditPolicy::Overwrite,
+            "The new edit policy is saved along with the keybindings."
+        );
+    }
+
+    #[test]
+    fn test_unreadable_stats() {
+        let mut storage = crate::nonogram_storage::MemoryStorage::new();
+        storage.write(STATS_FILE, "not stats").unwrap();
+        let mut controller = NonogramController::new(NonogramBoard::new([5, 5]), Box::new(storage));
+        controller.apply(Action::StartFill([0, 0]));
+        controller.apply(Action::EndDrag);
+        controller.apply(Action::Restart);
+        assert_eq!(
+            controller.stats.sizes.len(),
+            1,
+            "The game is still counted."
+        );
+        assert_eq!(
+            controller.storage.read(STATS_FILE).as_deref(),
+            Some("not stats"),
+            "A stats file that can't be read isn't overwritten."
+        );
+    }
+
+    #[test]
+    fn test_hint_at() {
+        let mut controller = test_controller();
+        let board_pos = [300.0, 240.0];
+        let size = [250.0, 250.0];
+        let last = |nums: &Vec<i8>| (0..nums.len()).rev().find(|&index| nums[index] != 0);
+        let column_last = last(&controller.nonogram.goal_nums[0][1]);
+        let row_last = last(&controller.nonogram.goal_nums[1][2]);
+
+        // Put the cursor right on the hint numbers nearest the board, where they're drawn.
+        controller.cursor_pos = [board_pos[0] + 75.0, column_hint_y(board_pos, 0) - 5.0];
+        assert_eq!(
+            controller.hint_at(board_pos, size),
+            column_last.map(|index| [0, 1, index]),
+            "The column hint number nearest the board is its last one."
+        );
+        controller.cursor_pos = [row_hint_x(board_pos, 0) + 5.0, board_pos[1] + 125.0];
+        assert_eq!(
+            controller.hint_at(board_pos, size),
+            row_last.map(|index| [1, 2, index])
+        );
+        controller.cursor_pos = [board_pos[0] - 2.0, board_pos[1] + 125.0];
+        assert_eq!(
+            controller.hint_at(board_pos, size),
+            None,
+            "The gap between the row hint numbers and the board has no hint numbers."
+        );
+    }
+
+    #[test]
+    fn test_apply_ignored() {
+        let mut controller = test_controller();
+        controller.apply(Action::StartFill([5, 0]));
+        controller.apply(Action::CrossOut([0, 0, 99]));
+        controller.apply(Action::SetDimensions([7, 3]));
+        assert_eq!(controller.nonogram.next_dimensions, [5, 5]);
+
+        controller.apply(Action::Pause);
+        controller.apply(Action::StartFill([0, 0]));
+        controller.apply(Action::EndDrag);
+        assert_eq!(
+            controller.nonogram.data[0][0],
+            Cell::Empty,
+            "Nothing is filled while paused."
+        );
+        controller.apply(Action::TogglePause);
+        assert!(!controller.nonogram.is_paused());
+
+        controller.apply(Action::ShowControls);
+        controller.apply(Action::Restart);
+        assert!(
+            !controller.nonogram.reset_board,
+            "Restarting doesn't work on the controls screen."
+        );
+        controller.apply(Action::CloseMenu);
+        controller.apply(Action::Restart);
+        assert!(controller.nonogram.reset_board);
+    }
+
+    #[test]
+    fn test_record_replay() {
+        let mut controller = test_controller();
+        controller.start_recording("replay.json");
+        controller.apply(Action::StartFill([0, 0]));
+        controller.apply(Action::Hover(Some([0, 3])));
+        controller.apply(Action::EndDrag);
+        controller.apply(Action::CrossOut([0, 0, 0]));
+        controller.apply(Action::Tick);
+        controller.apply(Action::Save);
+
+        let replay = Replay::load(&*controller.storage, "replay.json").unwrap();
+        assert_eq!(
+            replay.games[0].actions.len(),
+            5,
+            "Everything but ticks is recorded."
+        );
+
+        let mut player = crate::nonogram_replay::ReplayPlayer::new(replay).unwrap();
+        let mut replayed = player.controller();
+        player.finish(&mut replayed);
+        assert_eq!(replayed.nonogram.data, controller.nonogram.data);
+        assert_eq!(
+            replayed.nonogram.manual_crossouts,
+            controller.nonogram.manual_crossouts
+        );
     }
 }
@@ -0,0 +1,478 @@
+//! Responsible for drawing the game without a window, into an image that can be saved as a PNG.
+//!
+//! `OffscreenGraphics` is a Piston graphics back-end that draws every triangle in software, so `NonogramView::draw`
+//! works on it just like it works on the window. It doesn't need OpenGL, or even a display. Used for screenshots of
+//! saved games and replays, for pictures saved from the win screen, and by tests that check what the view draws.
+
+use graphics::character::CharacterCache;
+use graphics::color::hex;
+use graphics::draw_state::DrawState;
+use graphics::glyph_cache::rusttype::GlyphCache;
+use graphics::{Context, Graphics, ImageSize};
+use image::{Rgba, RgbaImage};
+use std::fs;
+use std::path::Path;
+use texture::{CreateTexture, Format, TextureOp, TextureSettings, UpdateTexture};
+
+use nonogram::common::{PALETTE, PICTURE_BACKGROUND, PICTURE_FILE_PREFIX};
+use nonogram::{Cell, NonogramController, Picture};
+
+use crate::nonogram_board_view::{NonogramView, NonogramViewSettings};
+
+/// Fonts for drawing text offscreen.
+pub type OffscreenGlyphs = GlyphCache<'static, (), OffscreenTexture>;
+
+/// A texture kept in memory, like the glyphs of a font.
+pub struct OffscreenTexture {
+    /// Every pixel of the texture.
+    image: RgbaImage,
+}
+
+impl ImageSize for OffscreenTexture {
+    fn get_size(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+}
+
+impl TextureOp<()> for OffscreenTexture {
+    type Error = String;
+}
+
+impl CreateTexture<()> for OffscreenTexture {
+    fn create<S: Into<[u32; 2]>>(
+        _factory: &mut (),
+        _format: Format,
+        memory: &[u8],
+        size: S,
+        _settings: &TextureSettings,
+    ) -> Result<Self, Self::Error> {
+        let size = size.into();
+        RgbaImage::from_raw(size[0], size[1], memory.to_vec())
+            .map(|image| OffscreenTexture { image })
+            .ok_or_else(|| format!("Not enough memory for a {}x{} texture", size[0], size[1]))
+    }
+}
+
+impl UpdateTexture<()> for OffscreenTexture {
+    fn update<O, S>(
+        &mut self,
+        _factory: &mut (),
+        _format: Format,
+        memory: &[u8],
+        offset: O,
+        size: S,
+    ) -> Result<(), Self::Error>
+    where
+        O: Into<[u32; 2]>,
+        S: Into<[u32; 2]>,
+    {
+        let (offset, size) = (offset.into(), size.into());
+        if memory.len() < (size[0] * size[1] * 4) as usize {
+            return Err(format!(
+                "Not enough memory for a {}x{} texture",
+                size[0], size[1]
+            ));
+        }
+        for y in 0..size[1] {
+            for x in 0..size[0] {
+                let i = ((y * size[0] + x) * 4) as usize;
+                let pixel = Rgba([memory[i], memory[i + 1], memory[i + 2], memory[i + 3]]);
+                let (px, py) = (offset[0] + x, offset[1] + y);
+                if px < self.image.width() && py < self.image.height() {
+                    self.image.put_pixel(px, py, pixel);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Draws into an image in memory instead of a window.
+pub struct OffscreenGraphics {
+    /// Everything drawn so far.
+    pub image: RgbaImage,
+}
+
+impl OffscreenGraphics {
+    /// Start with a transparent image. `size` is `[width, height]` in pixels.
+    pub fn new(size: [u32; 2]) -> OffscreenGraphics {
+        OffscreenGraphics {
+            image: RgbaImage::new(size[0], size[1]),
+        }
+    }
+
+    /// A context that draws onto the whole image, using pixel coordinates just like the window.
+    pub fn context(&self) -> Context {
+        Context::new_abs(self.image.width() as f64, self.image.height() as f64)
+    }
+
+    /// Write the image to a PNG file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        self.image
+            .save(path)
+            .map_err(|why| format!("Couldn't write {}: {}", path.display(), why))
+    }
+
+    /// Turn a vertex, which goes from -1 to 1 across the image with y pointing up, into pixel coordinates.
+    fn to_pixel(&self, vertex: [f32; 2]) -> [f32; 2] {
+        [
+            (vertex[0] + 1.0) / 2.0 * self.image.width() as f32,
+            (1.0 - vertex[1]) / 2.0 * self.image.height() as f32,
+        ]
+    }
+
+    /// Fill every pixel whose center is inside a triangle. `shade` gets how close the pixel is to each corner, and
+    /// returns the color to blend onto the pixel.
+    fn fill_triangle<F>(&mut self, corners: [[f32; 2]; 3], mut shade: F)
+    where
+        F: FnMut([f32; 3]) -> [f32; 4],
+    {
+        let [a, b, c] = [
+            self.to_pixel(corners[0]),
+            self.to_pixel(corners[1]),
+            self.to_pixel(corners[2]),
+        ];
+        let edge = |p: [f32; 2], q: [f32; 2], r: [f32; 2]| {
+            (q[0] - p[0]) * (r[1] - p[1]) - (q[1] - p[1]) * (r[0] - p[0])
+        };
+        let area = edge(a, b, c);
+        if area == 0.0 {
+            return;
+        }
+
+        let (width, height) = (self.image.width() as f32, self.image.height() as f32);
+        let min_x = a[0].min(b[0]).min(c[0]).floor().max(0.0) as u32;
+        let max_x = a[0].max(b[0]).max(c[0]).ceil().min(width) as u32;
+        let min_y = a[1].min(b[1]).min(c[1]).floor().max(0.0) as u32;
+        let max_y = a[1].max(b[1]).max(c[1]).ceil().min(height) as u32;
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = [x as f32 + 0.5, y as f32 + 0.5];
+                let weights = [
+                    edge(b, c, p) / area,
+                    edge(c, a, p) / area,
+                    edge(a, b, p) / area,
+                ];
+                if weights.iter().all(|&weight| weight >= 0.0) {
+                    let color = shade(weights);
+                    blend(self.image.get_pixel_mut(x, y), color);
+                }
+            }
+        }
+    }
+}
+
+/// Blend a color onto a pixel, using the color's alpha.
+fn blend(pixel: &mut Rgba<u8>, color: [f32; 4]) {
+    let alpha = color[3].clamp(0.0, 1.0);
+    for i in 0..3 {
+        let src = color[i].clamp(0.0, 1.0) * 255.0;
+        pixel[i] = (src * alpha + pixel[i] as f32 * (1.0 - alpha)).round() as u8;
+    }
+    pixel[3] = (255.0 * alpha + pixel[3] as f32 * (1.0 - alpha)).round() as u8;
+}
+
+impl Graphics for OffscreenGraphics {
+    type Texture = OffscreenTexture;
+
+    fn clear_color(&mut self, color: [f32; 4]) {
+        let pixel = Rgba([
+            (color[0] * 255.0).round() as u8,
+            (color[1] * 255.0).round() as u8,
+            (color[2] * 255.0).round() as u8,
+            (color[3] * 255.0).round() as u8,
+        ]);
+        for p in self.image.pixels_mut() {
+            *p = pixel;
+        }
+    }
+
+    fn clear_stencil(&mut self, _value: u8) {}
+
+    fn tri_list<F>(&mut self, _draw_state: &DrawState, color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        let color = *color;
+        f(&mut |vertices: &[[f32; 2]]| {
+            for corners in vertices.chunks_exact(3) {
+                self.fill_triangle([corners[0], corners[1], corners[2]], |_| color);
+            }
+        });
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        _draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &OffscreenTexture,
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        let color = *color;
+        let (width, height) = texture.image.dimensions();
+        f(&mut |vertices: &[[f32; 2]], uvs: &[[f32; 2]]| {
+            for (corners, uv) in vertices.chunks_exact(3).zip(uvs.chunks_exact(3)) {
+                self.fill_triangle([corners[0], corners[1], corners[2]], |weights| {
+                    // The texture's color is multiplied with the color, using the texel nearest to the pixel.
+                    let u = weights[0] * uv[0][0] + weights[1] * uv[1][0] + weights[2] * uv[2][0];
+                    let v = weights[0] * uv[0][1] + weights[1] * uv[1][1] + weights[2] * uv[2][1];
+                    let x = ((u * width as f32) as u32).min(width - 1);
+                    let y = ((v * height as f32) as u32).min(height - 1);
+                    let texel = texture.image.get_pixel(x, y);
+                    [
+                        color[0] * texel[0] as f32 / 255.0,
+                        color[1] * texel[1] as f32 / 255.0,
+                        color[2] * texel[2] as f32 / 255.0,
+                        color[3] * texel[3] as f32 / 255.0,
+                    ]
+                });
+            }
+        });
+    }
+}
+
+/// Load one of the fonts in the assets folder for drawing offscreen.
+pub fn load_glyphs(assets: &Path, name: &str) -> Result<OffscreenGlyphs, String> {
+    OffscreenGlyphs::new(assets.join(name), (), TextureSettings::new())
+        .map_err(|why| format!("Could not load {}: {}", name, why))
+}
+
+/// Draw the game just like the window would, into an image of `size` pixels.
+pub fn screenshot(
+    controller: &NonogramController,
+    assets: &Path,
+    size: [u32; 2],
+) -> Result<OffscreenGraphics, String> {
+    let glyphs = &mut load_glyphs(assets, "FiraSans-Regular.ttf")?;
+    let mark_glyphs = &mut load_glyphs(assets, "Monoround.ttf")?;
+    let material_icons_glyphs = &mut load_glyphs(assets, "MaterialIcons-Regular.ttf")?;
+
+    let view = NonogramView::new(NonogramViewSettings::new(controller.nonogram.dimensions));
+    let mut g = OffscreenGraphics::new(size);
+    let c = g.context();
+    g.clear_color(hex("222222"));
+    view.draw(
+        controller,
+        glyphs,
+        mark_glyphs,
+        material_icons_glyphs,
+        &c,
+        &mut g,
+    );
+    Ok(g)
+}
+
+/// Draw a solved picture, laid out just like its SVG.
+pub fn draw_picture(picture: &Picture, glyphs: &mut OffscreenGlyphs) -> OffscreenGraphics {
+    use graphics::text::Text;
+    use graphics::{Polygon, Rectangle, Transformed};
+
+    let mut g = OffscreenGraphics::new(picture.size());
+    let c = g.context();
+    g.clear_color(hex(PICTURE_BACKGROUND));
+
+    for (col, cells) in picture.cells.iter().enumerate() {
+        for (row, &cell) in cells.iter().enumerate() {
+            let color = match cell.color() {
+                Some(color) => hex(PALETTE[color]),
+                None => continue,
+            };
+            let cell_rect = picture.cell_rect([col, row]);
+            match cell {
+                Cell::Half(_, corner) => {
+                    Polygon::new(color).draw(
+                        &corner.triangle(cell_rect),
+                        &c.draw_state,
+                        c.transform,
+                        &mut g,
+                    );
+                }
+                _ => Rectangle::new(color).draw(cell_rect, &c.draw_state, c.transform, &mut g),
+            }
+        }
+    }
+
+    // Hint numbers are centered on their spot. We calculate the height of text by multiplying font size by 0.75 in
+    // order to convert between pixels and points.
+    let label_size = picture.font_size().round() as u32;
+    for label in picture.labels() {
+        let label_width = glyphs.width(label_size, &label.text).unwrap_or(0.0);
+        let label_loc = [
+            label.center[0] - label_width / 2.0,
+            label.center[1] + (label_size as f64 * 0.75) / 2.0,
+        ];
+        let label_color = hex(PALETTE[label.color]);
+        for wedge in &label.wedges {
+            Polygon::new(label_color).draw(wedge, &c.draw_state, c.transform, &mut g);
+        }
+        if let Some(outline) = label.outline {
+            Rectangle::new_border(label_color, 0.5).draw(
+                outline,
+                &c.draw_state,
+                c.transform,
+                &mut g,
+            );
+        }
+        Text::new_color(label_color, label_size)
+            .draw(
+                &label.text,
+                glyphs,
+                &c.draw_state,
+                c.transform.trans(label_loc[0], label_loc[1]),
+                &mut g,
+            )
+            .unwrap_or_else(|_| panic!("text draw failed"));
+    }
+    g
+}
+
+/// Save a solved picture in `dir` as both a PNG and an SVG file. They're numbered after the pictures already there,
+/// so nothing is overwritten. Returns where they were saved.
+pub fn save_picture(picture: &Picture, assets: &Path, dir: &Path) -> Result<String, String> {
+    let name = (1..)
+        .map(|number| format!("{}-{}", PICTURE_FILE_PREFIX, number))
+        .find(|name| {
+            !dir.join(format!("{}.png", name)).exists()
+                && !dir.join(format!("{}.svg", name)).exists()
+        })
+        .unwrap();
+
+    let glyphs = &mut load_glyphs(assets, "FiraSans-Regular.ttf")?;
+    draw_picture(picture, glyphs).save(dir.join(format!("{}.png", name)))?;
+    let svg_path = dir.join(format!("{}.svg", name));
+    fs::write(&svg_path, picture.to_svg())
+        .map_err(|why| format!("Couldn't write {}: {}", svg_path.display(), why))?;
+    Ok(format!("Saved {0}.png and {0}.svg", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nonogram::common::INITIAL_WINDOW_SIZE;
+    use nonogram::{Cell, MemoryStorage, NonogramBoard, Ruleset};
+
+    /// Where the fonts are, no matter where the tests are run from.
+    fn assets() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("assets")
+    }
+
+    #[test]
+    fn test_save_picture() {
+        let cells = vec![
+            vec![Cell::Filled, Cell::Empty],
+            vec![Cell::Colored(1), Cell::Filled],
+        ];
+        let mut board = NonogramBoard::with_variant([2, 2], 2, Ruleset::Standard);
+        board.data = cells.clone();
+        let picture = Picture::new(cells, 10, Some(board.picture_clues()));
+        let glyphs = &mut load_glyphs(&assets(), "FiraSans-Regular.ttf").unwrap();
+        let g = draw_picture(&picture, glyphs);
+        assert_eq!(g.image.dimensions(), (40, 40));
+        let pixel = |ind: [usize; 2]| {
+            let rect = picture.cell_rect(ind);
+            *g.image.get_pixel(
+                (rect[0] + rect[2] / 2.0) as u32,
+                (rect[1] + rect[3] / 2.0) as u32,
+            )
+        };
+        assert_eq!(pixel([0, 0]), Rgba([0x35, 0x32, 0x35, 255]));
+        assert_eq!(
+            pixel([1, 0]),
+            Rgba([0xd6, 0x45, 0x41, 255]),
+            "Colored cells keep their color."
+        );
+        assert_eq!(pixel([0, 1]), Rgba([0xf7, 0xf5, 0xf6, 255]));
+
+        let dir = std::env::temp_dir().join(format!("nonogram-pictures-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let first = save_picture(&picture, &assets(), &dir);
+        let second = save_picture(&picture, &assets(), &dir);
+        let saved = [
+            dir.join("picture-1.png").exists(),
+            dir.join("picture-1.svg").exists(),
+            dir.join("picture-2.png").exists(),
+        ];
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(first.unwrap(), "Saved picture-1.png and picture-1.svg");
+        assert_eq!(
+            second.unwrap(),
+            "Saved picture-2.png and picture-2.svg",
+            "Older pictures aren't overwritten."
+        );
+        assert_eq!(saved, [true, true, true]);
+    }
+
+    #[test]
+    fn test_triangle() {
+        let mut g = OffscreenGraphics::new([4, 4]);
+        g.clear_color([0.0, 0.0, 0.0, 1.0]);
+        let c = g.context();
+        graphics::Rectangle::new([1.0, 0.0, 0.0, 0.5]).draw(
+            [1.0, 1.0, 2.0, 2.0],
+            &c.draw_state,
+            c.transform,
+            &mut g,
+        );
+        assert_eq!(g.image.get_pixel(0, 0), &Rgba([0, 0, 0, 255]));
+        assert_eq!(
+            g.image.get_pixel(1, 1),
+            &Rgba([128, 0, 0, 255]),
+            "Half transparent red is blended onto black."
+        );
+        assert_eq!(g.image.get_pixel(2, 2), &Rgba([128, 0, 0, 255]));
+        assert_eq!(g.image.get_pixel(3, 3), &Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_screenshot() {
+        let mut board = NonogramBoard::new([5, 5]);
+        board.set([0, 0], Cell::Filled);
+        board.set([4, 4], Cell::Marked);
+        let controller = NonogramController::new(board, Box::new(MemoryStorage::new()));
+        let g = screenshot(&controller, &assets(), INITIAL_WINDOW_SIZE).unwrap();
+        assert_eq!(
+            g.image.dimensions(),
+            (INITIAL_WINDOW_SIZE[0], INITIAL_WINDOW_SIZE[1])
+        );
+
+        let settings = NonogramViewSettings::new([5, 5]);
+        let center = |ind: [usize; 2]| {
+            (
+                (settings.layout.position[0] + (ind[0] as f64 + 0.5) * settings.cell_size) as u32,
+                (settings.layout.position[1] + (ind[1] as f64 + 0.5) * settings.cell_size) as u32,
+            )
+        };
+        let color = |color: [f32; 4]| {
+            Rgba([
+                (color[0] * 255.0).round() as u8,
+                (color[1] * 255.0).round() as u8,
+                (color[2] * 255.0).round() as u8,
+                255,
+            ])
+        };
+        let (x, y) = center([0, 0]);
+        assert_eq!(
+            g.image.get_pixel(x, y),
+            &color(settings.filled_cell_background_color),
+            "Filled cells are drawn filled."
+        );
+        let (x, y) = center([2, 2]);
+        assert_eq!(
+            g.image.get_pixel(x, y),
+            &color(settings.background_color),
+            "Empty cells are drawn empty."
+        );
+        let (x, y) = center([4, 4]);
+        let half = (settings.cell_size / 2.0) as u32 - 2;
+        let background = color(settings.background_color);
+        assert!(
+            (x - half..x + half)
+                .flat_map(|x| (y - half..y + half).map(move |y| (x, y)))
+                .any(|(x, y)| g.image.get_pixel(x, y) != &background),
+            "Marked cells have a mark drawn on them."
+        );
+    }
+}
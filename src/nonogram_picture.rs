@@ -0,0 +1,423 @@
+//! Responsible for laying out a solved picture so it can be saved as an image.
+//!
+//! A [Picture] is every cell of a solved board or clip, drawn `scale` pixels wide, optionally with the hint numbers
+//! of every column above it and of every row to its left. It's written as SVG here. The window also draws it into
+//! PNG files, using the same layout.
+//!
+//! [Picture]: struct.Picture.html
+
+use crate::common::{
+    Cell, CLUE_HALF_END, CLUE_HALF_START, CLUE_MEGA, CLUE_SECOND, PALETTE, PICTURE_BACKGROUND,
+};
+
+/// Length, palette index, and ends of every grouping in every column or row of a picture. Ends are the `CLUE_*`
+/// flags. A line is `None` if its groupings are part of the line before it, like the second line of a merged pair
+/// in a mega puzzle.
+pub type PictureClues = Vec<Option<Vec<(usize, usize, u8)>>>;
+
+/// A hint number of a picture, along with where it goes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PictureLabel {
+    /// The hint number.
+    pub text: String,
+
+    /// Center of the hint number in pixels, from the top left of the picture.
+    pub center: [f64; 2],
+
+    /// Palette index of the grouping the hint number belongs to.
+    pub color: usize,
+
+    /// Small wedges drawn next to the hint number when its grouping starts or ends with a half filled cell in a
+    /// triangle puzzle, as the corners of each wedge in pixels.
+    pub wedges: Vec<[[f64; 2]; 3]>,
+
+    /// Outline drawn around the hint number when its grouping covers both lines of a merged pair in a mega puzzle,
+    /// as `[x, y, width, height]` in pixels.
+    pub outline: Option<[f64; 4]>,
+}
+
+/// A solved picture, ready to be saved as an image.
+pub struct Picture {
+    /// Every cell of the picture, laid out just like `NonogramBoard::data`.
+    pub cells: Vec<Vec<Cell>>,
+
+    /// Width and height of every cell in pixels.
+    pub scale: u32,
+
+    /// Every grouping in every column (index 0) and row (index 1). `None` if the picture is saved without hint
+    /// numbers.
+    pub clues: Option<[PictureClues; 2]>,
+}
+
+impl Picture {
+    /// Lay out `cells` with every cell `scale` pixels wide. With `clues`, hint numbers are drawn along the top and
+    /// left. They come from the board, see `NonogramBoard::picture_clues`, so they read just like the game's.
+    pub fn new(cells: Vec<Vec<Cell>>, scale: u32, clues: Option<[PictureClues; 2]>) -> Picture {
+        Picture {
+            cells,
+            scale,
+            clues,
+        }
+    }
+
+    /// Columns and rows of the picture.
+    pub fn dimensions(&self) -> [usize; 2] {
+        [self.cells.len(), self.cells.first().map_or(0, Vec::len)]
+    }
+
+    /// Room taken up by the hint numbers in pixels, left of the cells and above them. Every hint number gets a
+    /// square as big as a cell, and empty lines get a 0.
+    pub fn margin(&self) -> [f64; 2] {
+        let scale = self.scale as f64;
+        match &self.clues {
+            Some([columns, rows]) => {
+                let most = |lines: &PictureClues| {
+                    lines
+                        .iter()
+                        .flatten()
+                        .map(Vec::len)
+                        .max()
+                        .unwrap_or(0)
+                        .max(1) as f64
+                };
+                [most(rows) * scale, most(columns) * scale]
+            }
+            None => [0.0, 0.0],
+        }
+    }
+
+    /// Width and height of the whole picture in pixels.
+    pub fn size(&self) -> [u32; 2] {
+        let margin = self.margin();
+        let dimensions = self.dimensions();
+        [
+            margin[0] as u32 + dimensions[0] as u32 * self.scale,
+            margin[1] as u32 + dimensions[1] as u32 * self.scale,
+        ]
+    }
+
+    /// Where a cell goes, as `[x, y, width, height]` in pixels.
+    pub fn cell_rect(&self, ind: [usize; 2]) -> [f64; 4] {
+        let margin = self.margin();
+        let scale = self.scale as f64;
+        [
+            margin[0] + ind[0] as f64 * scale,
+            margin[1] + ind[1] as f64 * scale,
+            scale,
+            scale,
+        ]
+    }
+
+    /// Font size of the hint numbers in pixels.
+    pub fn font_size(&self) -> f64 {
+        self.scale as f64 * 0.6
+    }
+
+    /// Every hint number, along with where it goes. Column hint numbers end right above their column, and row hint
+    /// numbers end right next to their row. Hint numbers of a merged pair are moved along just like in the game.
+    pub fn labels(&self) -> Vec<PictureLabel> {
+        let mut labels = vec![];
+        let [columns, rows] = match &self.clues {
+            Some(clues) => clues,
+            None => return labels,
+        };
+        let margin = self.margin();
+        let scale = self.scale as f64;
+        let font_size = self.font_size();
+        for (axis, lines) in [columns, rows].iter().enumerate() {
+            for (line, groupings) in lines.iter().enumerate() {
+                let shown: Vec<(usize, usize, u8)> = match groupings {
+                    Some(groupings) if groupings.is_empty() => vec![(0, 0, 0)],
+                    Some(groupings) => groupings.clone(),
+                    None => continue,
+                };
+                let first = shown.len();
+                for (it, (size, color, ends)) in shown.into_iter().enumerate() {
+                    // Distance from the edge of the cells to the far side of this hint number's square.
+                    let away = (first - it) as f64 * scale;
+                    let shift = if ends & CLUE_MEGA != 0 {
+                        scale / 2.0
+                    } else if ends & CLUE_SECOND != 0 {
+                        scale
+                    } else {
+                        0.0
+                    };
+                    let along = (line as f64 + 0.5) * scale + shift;
+                    let center = if axis == 0 {
+                        [margin[0] + along, margin[1] - away + scale / 2.0]
+                    } else {
+                        [margin[0] - away + scale / 2.0, margin[1] + along]
+                    };
+                    let text = size.to_string();
+
+                    // Wedges sit on the baseline, just past either side of the text. Digits are about 0.55 of the
+                    // font size wide.
+                    let half_width = text.len() as f64 * font_size * 0.55 / 2.0;
+                    let base = center[1] + font_size * 0.35;
+                    let rise = font_size * 0.35;
+                    let mut wedges = vec![];
+                    if ends & CLUE_HALF_START != 0 {
+                        let x = center[0] - half_width - scale * 0.05;
+                        wedges.push([[x - rise, base], [x, base], [x, base - rise]]);
+                    }
+                    if ends & CLUE_HALF_END != 0 {
+                        let x = center[0] + half_width + scale * 0.05;
+                        wedges.push([[x, base], [x + rise, base], [x, base - rise]]);
+                    }
+                    let outline = if ends & CLUE_MEGA != 0 {
+                        let inset = scale * 0.1;
+                        Some(if axis == 0 {
+                            [
+                                center[0] - scale + inset,
+                                center[1] - scale / 2.0 + inset,
+                                2.0 * (scale - inset),
+                                scale - 2.0 * inset,
+                            ]
+                        } else {
+                            [
+                                center[0] - scale / 2.0 + inset,
+                                center[1] - scale + inset,
+                                scale - 2.0 * inset,
+                                2.0 * (scale - inset),
+                            ]
+                        })
+                    } else {
+                        None
+                    };
+                    labels.push(PictureLabel {
+                        text,
+                        center,
+                        color,
+                        wedges,
+                        outline,
+                    });
+                }
+            }
+        }
+        labels
+    }
+
+    /// The whole picture as an SVG document.
+    pub fn to_svg(&self) -> String {
+        let size = self.size();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" \
+             shape-rendering=\"crispEdges\">\n",
+            size[0], size[1]
+        );
+        svg += &format!(
+            "<rect width=\"{}\" height=\"{}\" fill=\"#{}\"/>\n",
+            size[0], size[1], PICTURE_BACKGROUND
+        );
+        for (col, cells) in self.cells.iter().enumerate() {
+            for (row, &cell) in cells.iter().enumerate() {
+                let color = match cell.color() {
+                    Some(color) => PALETTE[color],
+                    None => continue,
+                };
+                let [x, y, w, h] = self.cell_rect([col, row]);
+                svg += &match cell {
+                    Cell::Half(_, corner) => {
+                        let points: Vec<String> = corner
+                            .triangle([x, y, w, h])
+                            .iter()
+                            .map(|point| format!("{},{}", point[0], point[1]))
+                            .collect();
+                        format!(
+                            "<polygon points=\"{}\" fill=\"#{}\"/>\n",
+                            points.join(" "),
+                            color
+                        )
+                    }
+                    _ => format!(
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#{}\"/>\n",
+                        x, y, w, h, color
+                    ),
+                };
+            }
+        }
+        for label in self.labels() {
+            for wedge in &label.wedges {
+                let points: Vec<String> = wedge
+                    .iter()
+                    .map(|point| format!("{},{}", point[0], point[1]))
+                    .collect();
+                svg += &format!(
+                    "<polygon points=\"{}\" fill=\"#{}\"/>\n",
+                    points.join(" "),
+                    PALETTE[label.color]
+                );
+            }
+            if let Some([x, y, w, h]) = label.outline {
+                svg += &format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"#{}\"/>\n",
+                    x, y, w, h, PALETTE[label.color]
+                );
+            }
+            svg += &format!(
+                "<text x=\"{}\" y=\"{}\" fill=\"#{}\" font-family=\"sans-serif\" font-size=\"{}\" \
+                 text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+                label.center[0],
+                label.center[1],
+                PALETTE[label.color],
+                self.font_size(),
+                label.text
+            );
+        }
+        svg += "</svg>\n";
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Corner, Ruleset};
+    use crate::nonogram_board::NonogramBoard;
+
+    /// A 3x2 picture with a filled top row, except for a red cell on the right.
+    fn cells() -> Vec<Vec<Cell>> {
+        vec![
+            vec![Cell::Filled, Cell::Empty],
+            vec![Cell::Filled, Cell::Marked],
+            vec![Cell::Colored(1), Cell::Half(0, Corner::TopLeft)],
+        ]
+    }
+
+    /// Every grouping of `cells`, read by a triangle board.
+    fn clues(cells: Vec<Vec<Cell>>) -> Option<[PictureClues; 2]> {
+        let mut board =
+            NonogramBoard::with_variant([cells.len(), cells[0].len()], 2, Ruleset::Triangles);
+        board.data = cells;
+        Some(board.picture_clues())
+    }
+
+    #[test]
+    fn test_layout() {
+        let picture = Picture::new(cells(), 10, None);
+        assert_eq!(picture.size(), [30, 20]);
+        assert_eq!(picture.cell_rect([2, 1]), [20.0, 10.0, 10.0, 10.0]);
+        assert!(picture.labels().is_empty());
+
+        let picture = Picture::new(cells(), 10, clues(cells()));
+        let both = CLUE_HALF_START | CLUE_HALF_END;
+        assert_eq!(
+            picture.clues,
+            Some([
+                vec![
+                    Some(vec![(1, 0, 0)]),
+                    Some(vec![(1, 0, 0)]),
+                    Some(vec![(1, 1, 0), (1, 0, both)])
+                ],
+                vec![Some(vec![(2, 0, 0), (1, 1, 0)]), Some(vec![(1, 0, both)])],
+            ]),
+            "Different colors are different groupings, and marked cells don't count."
+        );
+        assert_eq!(
+            picture.margin(),
+            [20.0, 20.0],
+            "Room is made for the most hint numbers of any row and any column."
+        );
+        assert_eq!(picture.size(), [50, 40]);
+        assert_eq!(picture.cell_rect([0, 0]), [20.0, 20.0, 10.0, 10.0]);
+
+        let labels = picture.labels();
+        assert_eq!(labels.len(), 7);
+        assert_eq!(
+            labels[0],
+            PictureLabel {
+                text: "1".to_string(),
+                center: [25.0, 15.0],
+                color: 0,
+                wedges: vec![],
+                outline: None,
+            },
+            "A lone column hint number is right above its column."
+        );
+        assert_eq!(
+            labels[2].center,
+            [45.0, 5.0],
+            "The first of two column hint numbers is furthest from the cells."
+        );
+        assert_eq!(
+            labels[3].wedges.len(),
+            2,
+            "A half filled cell on its own starts and ends its grouping."
+        );
+        assert!(labels[3].wedges[0][1][0] < labels[3].center[0]);
+        assert!(labels[3].wedges[1][0][0] > labels[3].center[0]);
+        assert_eq!(labels[4].text, "2");
+        assert_eq!(labels[4].center, [5.0, 25.0]);
+    }
+
+    #[test]
+    fn test_mega_labels() {
+        // The first two rows are merged, with a grouping covering both of them and one only in the second row.
+        let mut board = NonogramBoard::with_variant([3, 3], 1, Ruleset::Mega);
+        board.mega_lines = vec![vec![false; 3], vec![true, false, false]];
+        board.data = vec![
+            vec![Cell::Filled, Cell::Filled, Cell::Empty],
+            vec![Cell::Empty, Cell::Empty, Cell::Empty],
+            vec![Cell::Empty, Cell::Filled, Cell::Empty],
+        ];
+        let picture = Picture::new(board.data.clone(), 10, Some(board.picture_clues()));
+        let rows = &picture.clues.as_ref().unwrap()[1];
+        assert_eq!(
+            rows[0],
+            Some(vec![(2, 0, CLUE_MEGA), (1, 0, CLUE_SECOND)]),
+            "Hint numbers of a merged pair come from the board."
+        );
+        assert_eq!(rows[1], None);
+
+        let labels: Vec<PictureLabel> = picture
+            .labels()
+            .into_iter()
+            .filter(|label| label.center[0] < picture.margin()[0])
+            .collect();
+        assert_eq!(
+            labels.len(),
+            3,
+            "The second row of a merged pair doesn't get a 0."
+        );
+        assert_eq!(
+            labels[0].center[1],
+            picture.margin()[1] + 10.0,
+            "A merged hint number sits between both rows."
+        );
+        assert_eq!(
+            labels[0].outline,
+            Some([1.0, 11.0, 8.0, 18.0]),
+            "A merged hint number is outlined across both rows."
+        );
+        assert_eq!(labels[1].center[1], picture.margin()[1] + 15.0);
+        assert_eq!(labels[1].outline, None);
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = Picture::new(cells(), 10, clues(cells())).to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("width=\"50\" height=\"40\""));
+        assert!(
+            svg.contains("<rect x=\"20\" y=\"20\" width=\"10\" height=\"10\" fill=\"#353235\"/>")
+        );
+        assert!(
+            svg.contains("fill=\"#d64541\""),
+            "Colored cells keep their color."
+        );
+        assert!(
+            svg.contains("<polygon points=\"40,30 50,30 40,40\""),
+            "Half filled cells only fill their corner."
+        );
+        assert_eq!(svg.matches("<text").count(), 7);
+        assert_eq!(
+            svg.matches("<polygon").count(),
+            5,
+            "Hint numbers of half filled groupings get their wedges."
+        );
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        let empty = Picture::new(vec![vec![Cell::Empty]], 10, clues(vec![vec![Cell::Empty]]));
+        assert!(empty.to_svg().contains(">0</text>"), "Empty lines get a 0.");
+    }
+}
@@ -0,0 +1,399 @@
+//! Responsible for recording every action taken during a session, and playing it back later.
+//!
+//! A replay starts from the board as it was when recording started. Every action is kept along with when it was
+//! taken, so playing them back in order ends up with the same board, hint numbers and crossed out numbers.
+//! Starting a new board or switching to another panel of a clip starts a new game in the replay, beginning from
+//! the new board.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "input")]
+use std::time::{Duration, Instant};
+
+use crate::common::EditPolicy;
+#[cfg(feature = "input")]
+use crate::common::REPLAY_SPEEDS;
+use crate::nonogram_action::Action;
+use crate::nonogram_board::{NonogramBoard, SavedBoard};
+#[cfg(feature = "input")]
+use crate::nonogram_controller::NonogramController;
+#[cfg(feature = "input")]
+use crate::nonogram_storage::MemoryStorage;
+use crate::nonogram_storage::Storage;
+
+/// An action, along with when it was taken.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimedAction {
+    /// Seconds since the recording started.
+    pub time: f64,
+
+    /// What was done.
+    pub action: Action,
+}
+
+/// A board played during a recording, along with everything done to it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReplayGame {
+    /// Seconds since the recording started when this board came up.
+    pub start: f64,
+
+    /// The board as it was when it came up.
+    pub board: SavedBoard,
+
+    /// Every action taken on this board, oldest first.
+    pub actions: Vec<TimedAction>,
+}
+
+/// A recorded session. This is what gets written to a replay file.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+    /// Every board played during the session, oldest first.
+    pub games: Vec<ReplayGame>,
+
+    /// What filling or marking a cell that isn't empty did when the recording started. Replays from before this
+    /// was kept were recorded with the default.
+    #[serde(default)]
+    pub edit_policy: EditPolicy,
+
+    /// Whether or not the selected cell wrapped around the edges of the board when the recording started.
+    #[serde(default)]
+    pub wrap_around: bool,
+}
+
+impl Replay {
+    /// Start a recording without any games.
+    pub fn new() -> Replay {
+        Replay::default()
+    }
+
+    /// Start recording a new game on `board`. Every action recorded afterwards belongs to this game.
+    pub fn start_game(&mut self, time: f64, board: &NonogramBoard) {
+        self.games.push(ReplayGame {
+            start: time,
+            board: board.saved(),
+            actions: vec![],
+        });
+    }
+
+    /// Record an action taken in the current game. Does nothing if no game has been started.
+    pub fn record(&mut self, time: f64, action: Action) {
+        if let Some(game) = self.games.last_mut() {
+            game.actions.push(TimedAction { time, action });
+        }
+    }
+
+    /// Seconds from the start of the recording until the last thing that happened in it.
+    pub fn length(&self) -> f64 {
+        self.games
+            .iter()
+            .map(|game| game.actions.last().map_or(game.start, |action| action.time))
+            .fold(0.0, f64::max)
+    }
+
+    /// Load a replay written by `save`.
+    pub fn load<S: Storage + ?Sized>(storage: &S, name: &str) -> Result<Replay, String> {
+        let replay_data = storage
+            .read(name)
+            .ok_or_else(|| format!("Couldn't read {}", name))?;
+        serde_json::from_str(&replay_data).map_err(|why| format!("Couldn't read {}: {}", name, why))
+    }
+
+    /// Write this replay, so it can be played back by `ReplayPlayer`.
+    pub fn save<S: Storage + ?Sized>(&self, storage: &mut S, name: &str) -> Result<(), String> {
+        let replay_data = serde_json::to_string(self)
+            .map_err(|why| format!("Couldn't write {}: {}", name, why))?;
+        storage.write(name, &replay_data)
+    }
+}
+
+/// Plays a replay back on a controller created by `controller`.
+#[cfg(feature = "input")]
+pub struct ReplayPlayer {
+    /// The replay being played back.
+    replay: Replay,
+
+    /// Index of the game being played back.
+    game: usize,
+
+    /// Index of the next action to apply in the game being played back.
+    next: usize,
+
+    /// Seconds of the replay played back so far.
+    pub time: f64,
+
+    /// Index of the playback speed in `REPLAY_SPEEDS`.
+    speed: usize,
+
+    /// Whether or not the playback is paused.
+    pub paused: bool,
+
+    /// What the board's timer reads as the current time at the start of the game being played back. The timer
+    /// follows the replay from there, see `NonogramBoard::clock`.
+    game_clock: Instant,
+}
+
+#[cfg(feature = "input")]
+impl ReplayPlayer {
+    /// Start playing back a replay from its first game, at the speed it was recorded. Returns `None` if the
+    /// replay doesn't have any games.
+    pub fn new(replay: Replay) -> Option<ReplayPlayer> {
+        replay.games.first()?;
+        Some(ReplayPlayer {
+            replay,
+            game: 0,
+            next: 0,
+            time: 0.0,
+            speed: REPLAY_SPEEDS
+                .iter()
+                .position(|&speed| speed == 1.0)
+                .unwrap_or(0),
+            paused: false,
+            game_clock: Instant::now(),
+        })
+    }
+
+    /// A controller to play the replay back on, starting from its first board, with the controls that were used
+    /// to record it. Nothing it does is saved, since it keeps its save files and stats in memory.
+    pub fn controller(&mut self) -> NonogramController {
+        let board = self.start_board();
+        let mut controller = NonogramController::new(board, Box::new(MemoryStorage::new()));
+        controller.controls.edit_policy = self.replay.edit_policy;
+        controller.controls.wrap_around = self.replay.wrap_around;
+        controller
+    }
+
+    /// The board of the game being played back, as it was when it came up. Its timer only moves along with the
+    /// replay, so playing back slowly or pausing doesn't run a countdown out early.
+    fn start_board(&mut self) -> NonogramBoard {
+        let mut board = NonogramBoard::from_saved(self.replay.games[self.game].board.clone());
+        self.game_clock = board.now();
+        board.clock = Some(self.game_clock);
+        board
+    }
+
+    /// Set the board's timer to `time` seconds into the replay.
+    fn sync_clock(&self, time: f64, controller: &mut NonogramController) {
+        let since_start = time - self.replay.games[self.game].start;
+        controller.nonogram.clock =
+            Some(self.game_clock + Duration::from_secs_f64(since_start.max(0.0)));
+    }
+
+    /// Play back `seconds` of real time, at the current speed. Does nothing while paused.
+    pub fn advance(&mut self, seconds: f64, controller: &mut NonogramController) {
+        if !self.paused {
+            self.skip(seconds * self.speed(), controller);
+        }
+    }
+
+    /// Play back everything up to `seconds` further into the replay, no matter the speed or whether it's paused.
+    pub fn skip(&mut self, seconds: f64, controller: &mut NonogramController) {
+        self.time += seconds;
+        loop {
+            let game = &self.replay.games[self.game];
+            if let Some(timed) = game.actions.get(self.next) {
+                if timed.time > self.time {
+                    break;
+                }
+                self.next += 1;
+                self.sync_clock(timed.time, controller);
+                controller.apply(timed.action);
+                continue;
+            }
+
+            // The next game starts from its own board once every action of this one has been played back.
+            match self.replay.games.get(self.game + 1) {
+                Some(next_game) if next_game.start <= self.time => {
+                    self.game += 1;
+                    self.next = 0;
+                    let board = self.start_board();
+                    controller.load_board(board);
+                }
+                _ => break,
+            }
+        }
+        self.time = self.time.min(self.length());
+        self.sync_clock(self.time, controller);
+    }
+
+    /// Play back everything that's left right away.
+    pub fn finish(&mut self, controller: &mut NonogramController) {
+        self.skip(f64::INFINITY, controller);
+    }
+
+    /// Whether or not everything has been played back.
+    pub fn is_finished(&self) -> bool {
+        self.game + 1 == self.replay.games.len()
+            && self.next == self.replay.games[self.game].actions.len()
+    }
+
+    /// Seconds from the start of the replay until the last thing that happened in it.
+    pub fn length(&self) -> f64 {
+        self.replay.length()
+    }
+
+    /// How many times faster than it was recorded the replay is played back.
+    pub fn speed(&self) -> f64 {
+        REPLAY_SPEEDS[self.speed]
+    }
+
+    /// Play back faster, up to the fastest speed in `REPLAY_SPEEDS`.
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(REPLAY_SPEEDS.len() - 1);
+    }
+
+    /// Play back slower, down to the slowest speed in `REPLAY_SPEEDS`.
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nonogram_storage::MemoryStorage;
+
+    #[test]
+    fn test_save_and_load() {
+        let mut replay = Replay::new();
+        replay.start_game(0.0, &NonogramBoard::new([5, 5]));
+        replay.record(0.5, Action::StartFill([1, 2]));
+        replay.record(0.75, Action::EndDrag);
+
+        let mut storage = MemoryStorage::new();
+        replay.save(&mut storage, "replay.json").unwrap();
+        let loaded = Replay::load(&storage, "replay.json").unwrap();
+        assert_eq!(loaded.games.len(), 1);
+        assert_eq!(loaded.games[0].actions, replay.games[0].actions);
+        assert_eq!(loaded.length(), 0.75);
+        assert!(Replay::load(&storage, "missing.json").is_err());
+    }
+
+    #[cfg(feature = "input")]
+    #[test]
+    fn test_player() {
+        use crate::common::Cell;
+
+        let mut replay = Replay::new();
+        replay.start_game(0.0, &NonogramBoard::new([5, 5]));
+        replay.record(1.0, Action::StartFill([0, 0]));
+        replay.record(2.0, Action::Hover(Some([1, 0])));
+        replay.record(3.0, Action::EndDrag);
+        replay.start_game(4.0, &NonogramBoard::new([10, 5]));
+        replay.record(5.0, Action::StartMark([9, 4]));
+        replay.record(5.0, Action::EndDrag);
+
+        let mut player = ReplayPlayer::new(replay).unwrap();
+        let mut controller = player.controller();
+        player.advance(1.5, &mut controller);
+        assert_eq!(controller.nonogram.data[0][0], Cell::Filled);
+        assert_eq!(
+            controller.nonogram.data[1][0],
+            Cell::Empty,
+            "Actions aren't applied before their time."
+        );
+
+        player.faster();
+        assert_eq!(player.speed(), 2.0);
+        player.advance(0.5, &mut controller);
+        assert_eq!(controller.nonogram.data[1][0], Cell::Filled);
+
+        player.paused = true;
+        player.advance(100.0, &mut controller);
+        assert!(
+            !player.is_finished(),
+            "Nothing is played back while paused."
+        );
+
+        player.finish(&mut controller);
+        assert!(player.is_finished());
+        assert_eq!(
+            controller.nonogram.dimensions,
+            [10, 5],
+            "The second game starts from its own board."
+        );
+        assert_eq!(controller.nonogram.data[9][4], Cell::Marked);
+        assert_eq!(player.time, 5.0);
+    }
+
+    #[cfg(feature = "input")]
+    #[test]
+    fn test_recorded_controls() {
+        use crate::common::Cell;
+
+        // Marking a filled cell only marks it when overwriting. Toggling would clear it instead.
+        let mut recorder =
+            NonogramController::new(NonogramBoard::new([5, 5]), Box::new(MemoryStorage::new()));
+        recorder.controls.edit_policy = EditPolicy::Overwrite;
+        recorder.controls.wrap_around = true;
+        recorder.start_recording("replay.json");
+        recorder.apply(Action::StartFill([0, 0]));
+        recorder.apply(Action::EndDrag);
+        recorder.apply(Action::StartMark([0, 0]));
+        recorder.apply(Action::EndDrag);
+        assert_eq!(recorder.nonogram.data[0][0], Cell::Marked);
+
+        let mut player = ReplayPlayer::new(recorder.replay().unwrap().clone()).unwrap();
+        let mut controller = player.controller();
+        assert_eq!(controller.controls.edit_policy, EditPolicy::Overwrite);
+        assert!(controller.controls.wrap_around);
+        player.finish(&mut controller);
+        assert_eq!(
+            controller.nonogram.data, recorder.nonogram.data,
+            "The replay is played back with the controls it was recorded with."
+        );
+    }
+
+    #[cfg(feature = "input")]
+    #[test]
+    fn test_slow_countdown() {
+        use crate::common::{Cell, GameMode};
+
+        // A countdown game solved a second before time runs out.
+        let mut board = NonogramBoard::new([5, 5]);
+        board.mode = GameMode::Countdown;
+        let time_limit = board.time_limit().unwrap().as_secs_f64();
+        let mut replay = Replay::new();
+        replay.start_game(0.0, &board);
+        for col in 0..5 {
+            for row in 0..5 {
+                if board.goal_data[col][row] == Cell::Filled {
+                    replay.record(time_limit - 1.0, Action::StartFill([col, row]));
+                    replay.record(time_limit - 1.0, Action::EndDrag);
+                }
+            }
+        }
+
+        // Played back as slowly as possible and paused along the way, but the timer only moves with the replay.
+        let mut player = ReplayPlayer::new(replay).unwrap();
+        let mut controller = player.controller();
+        for _ in 0..REPLAY_SPEEDS.len() {
+            player.slower();
+        }
+        player.advance(time_limit / 2.0 / player.speed(), &mut controller);
+        controller.nonogram.update_duration();
+        assert_eq!(
+            controller.nonogram.duration.as_secs_f64().round(),
+            (time_limit / 2.0).round(),
+            "The timer follows the replay, not the real time."
+        );
+        player.paused = true;
+        player.advance(time_limit * 10.0, &mut controller);
+        controller.nonogram.update_duration();
+        assert!(!controller.nonogram.time_up);
+
+        player.paused = false;
+        while !player.is_finished() {
+            player.advance(1.0 / player.speed(), &mut controller);
+            controller.nonogram.update_duration();
+        }
+        assert!(controller.nonogram.end_game_screen);
+        assert!(
+            !controller.nonogram.time_up,
+            "The replay is won just like the recording was."
+        );
+        assert_eq!(controller.nonogram.data, board.goal_data);
+        assert_eq!(
+            controller.nonogram.duration.as_secs_f64().round(),
+            time_limit - 1.0
+        );
+    }
+}
@@ -0,0 +1,477 @@
+//! Responsible for scripts of synthetic input events, like clicking a cell or pressing a key.
+//!
+//! Every event is handed to `NonogramController::event`, just like the events of the window, so scripts test the
+//! whole path from a mouse button or key to the board. Scripts can check the board along the way. The board is laid
+//! out like it is in the window, 20 pixels per cell, with no buttons or menus around it.
+//!
+//! A script has one command per line. Blank lines and lines starting with `#` are skipped.
+//!
+//! ```text
+//! board 5x5 42          start over on a 5x5 board generated from seed 42
+//! hover 1 2             move the mouse over column 1, row 2
+//! hover off             move the mouse off the board
+//! press left            press a mouse button (left or right) or a key (J, Up, LShift, ...)
+//! release left          let go of a mouse button or a key
+//! tap J                 press and let go of a mouse button or a key
+//! click 1 2             move the mouse over a cell, and press and let go of the left mouse button
+//! rclick 1 2            the same with the right mouse button
+//! drag 0 0 4 0          press the left mouse button over a cell, move over every cell up to the other one,
+//!                       and let it go
+//! rdrag 0 0 4 0         the same with the right mouse button
+//! wait 300              let 300 milliseconds pass
+//! expect cell 1 2 filled
+//! expect row 0 ##x..
+//! expect selected 1 2
+//! expect selected none
+//! ```
+//!
+//! A cell is expected to be `empty`, `filled`, `colored`, `marked`, `maybe` or `half`. A row is expected to look
+//! like a string with a character for every cell: `.` for empty, `#` for filled, `x` for marked, `?` for a
+//! tentative fill, and `o` for anything else.
+
+use piston::input::{
+    Button, ButtonArgs, ButtonState, Event, Input, Key, Loop, Motion, MouseButton, UpdateArgs,
+};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::thread;
+use std::time::Duration;
+
+use crate::common::Cell;
+use crate::nonogram_board::NonogramBoard;
+use crate::nonogram_controller::{line_cells, NonogramController, WindowLayout};
+
+/// Width and height of a cell in pixels.
+const CELL_SIZE: f64 = 20.0;
+
+/// Where the top left corner of the board is, in pixels. Leaves room for hint numbers above and left of it.
+const BOARD_POSITION: [f64; 2] = [300.0, 240.0];
+
+/// Where the mouse is moved to by `hover off`.
+const OFF_BOARD: [f64; 2] = [10.0, 10.0];
+
+/// A single line of a script.
+#[derive(Clone, Debug, PartialEq)]
+enum Command {
+    Board([usize; 2], u64),
+    Hover(Option<[usize; 2]>),
+    Press(Button),
+    Release(Button),
+    Tap(Button),
+    Click(Button, [usize; 2]),
+    Drag(Button, [usize; 2], [usize; 2]),
+    Wait(u64),
+    ExpectCell([usize; 2], String),
+    ExpectRow(usize, String),
+    ExpectSelected(Option<[usize; 2]>),
+}
+
+/// A parsed script, ready to be run on a controller.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Script {
+    /// Every command along with the line it's on, counting from 1.
+    commands: Vec<(usize, Command)>,
+}
+
+impl Script {
+    /// Read a script. The error says which line couldn't be read.
+    pub fn parse(text: &str) -> Result<Script, String> {
+        let mut commands = vec![];
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let command =
+                parse_command(line).map_err(|why| format!("line {}: {}", index + 1, why))?;
+            commands.push((index + 1, command));
+        }
+        Ok(Script { commands })
+    }
+
+    /// Run every command on a controller, stopping at the first expectation that isn't met. The error says which
+    /// line it was on, and what the board looked like instead.
+    pub fn run(&self, controller: &mut NonogramController) -> Result<(), String> {
+        for (line, command) in self.commands.iter() {
+            run_command(command, controller).map_err(|why| format!("line {}: {}", line, why))?;
+        }
+        Ok(())
+    }
+}
+
+/// Read a single line of a script.
+fn parse_command(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let number = |index: usize| -> Result<usize, String> {
+        let word = words
+            .get(index)
+            .ok_or_else(|| format!("missing a number in \"{}\"", line))?;
+        word.parse()
+            .map_err(|_| format!("\"{}\" isn't a number", word))
+    };
+    let cell =
+        |index: usize| -> Result<[usize; 2], String> { Ok([number(index)?, number(index + 1)?]) };
+    let argument = |index: usize| -> Result<&str, String> {
+        words
+            .get(index)
+            .copied()
+            .ok_or_else(|| format!("missing an argument in \"{}\"", line))
+    };
+
+    let (command, expected_len) = match words[0] {
+        "board" => {
+            let size: Vec<usize> = argument(1)?
+                .split('x')
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("\"{}\" isn't a board size like 5x5", words[1]))?;
+            if size.len() != 2 || size.contains(&0) {
+                return Err(format!("\"{}\" isn't a board size like 5x5", words[1]));
+            }
+            match words.get(2) {
+                Some(_) => (Command::Board([size[0], size[1]], number(2)? as u64), 3),
+                None => (Command::Board([size[0], size[1]], 0), 2),
+            }
+        }
+        "hover" if words.get(1) == Some(&"off") => (Command::Hover(None), 2),
+        "hover" => (Command::Hover(Some(cell(1)?)), 3),
+        "press" => (Command::Press(parse_button(argument(1)?)?), 2),
+        "release" => (Command::Release(parse_button(argument(1)?)?), 2),
+        "tap" => (Command::Tap(parse_button(argument(1)?)?), 2),
+        "click" => (
+            Command::Click(Button::Mouse(MouseButton::Left), cell(1)?),
+            3,
+        ),
+        "rclick" => (
+            Command::Click(Button::Mouse(MouseButton::Right), cell(1)?),
+            3,
+        ),
+        "drag" => (
+            Command::Drag(Button::Mouse(MouseButton::Left), cell(1)?, cell(3)?),
+            5,
+        ),
+        "rdrag" => (
+            Command::Drag(Button::Mouse(MouseButton::Right), cell(1)?, cell(3)?),
+            5,
+        ),
+        "wait" => (Command::Wait(number(1)? as u64), 2),
+        "expect" => match argument(1)? {
+            "cell" => (Command::ExpectCell(cell(2)?, argument(4)?.to_string()), 5),
+            "row" => (Command::ExpectRow(number(2)?, argument(3)?.to_string()), 4),
+            "selected" if words.get(2) == Some(&"none") => (Command::ExpectSelected(None), 3),
+            "selected" => (Command::ExpectSelected(Some(cell(2)?)), 4),
+            what => return Err(format!("can't expect \"{}\"", what)),
+        },
+        _ => return Err(format!("unknown command \"{}\"", words[0])),
+    };
+
+    if words.len() != expected_len {
+        return Err(format!("too many arguments in \"{}\"", line));
+    }
+    Ok(command)
+}
+
+/// Read a mouse button (`left` or `right`) or a keyboard key, named just like in controls.json.
+fn parse_button(name: &str) -> Result<Button, String> {
+    match name {
+        "left" => Ok(Button::Mouse(MouseButton::Left)),
+        "right" => Ok(Button::Mouse(MouseButton::Right)),
+        _ => serde_json::from_str::<Key>(&format!("\"{}\"", name))
+            .map(Button::Keyboard)
+            .map_err(|_| format!("unknown button \"{}\"", name)),
+    }
+}
+
+/// Run a single line of a script.
+fn run_command(command: &Command, controller: &mut NonogramController) -> Result<(), String> {
+    match command {
+        Command::Board(dimensions, seed) => {
+            let mut board = NonogramBoard::new(*dimensions);
+            board.generate_from(&mut StdRng::seed_from_u64(*seed));
+            controller.load_board(board);
+        }
+        Command::Hover(cell) => hover(controller, *cell),
+        Command::Press(button) => send_button(controller, *button, ButtonState::Press),
+        Command::Release(button) => send_button(controller, *button, ButtonState::Release),
+        Command::Tap(button) => {
+            send_button(controller, *button, ButtonState::Press);
+            send_button(controller, *button, ButtonState::Release);
+        }
+        Command::Click(button, cell) => {
+            hover(controller, Some(*cell));
+            send_button(controller, *button, ButtonState::Press);
+            send_button(controller, *button, ButtonState::Release);
+        }
+        Command::Drag(button, from, to) => {
+            hover(controller, Some(*from));
+            send_button(controller, *button, ButtonState::Press);
+            for &cell in line_cells(*from, *to).iter().skip(1) {
+                hover(controller, Some(cell));
+            }
+            send_button(controller, *button, ButtonState::Release);
+        }
+        Command::Wait(millis) => {
+            thread::sleep(Duration::from_millis(*millis));
+            send(
+                controller,
+                &Event::Loop(Loop::Update(UpdateArgs {
+                    dt: *millis as f64 / 1000.0,
+                })),
+            );
+        }
+        Command::ExpectCell(ind, expected) => {
+            let cell = *board_cell(controller, *ind)?;
+            let actual = cell_name(cell);
+            if actual != expected {
+                return Err(format!(
+                    "expected cell {} {} to be {}, but it's {}",
+                    ind[0], ind[1], expected, actual
+                ));
+            }
+        }
+        Command::ExpectRow(row, expected) => {
+            let dimensions = controller.nonogram.dimensions;
+            if *row >= dimensions[1] {
+                return Err(format!("row {} isn't on the board", row));
+            }
+            let actual: String = (0..dimensions[0])
+                .map(|col| cell_char(controller.nonogram.data[col][*row]))
+                .collect();
+            if actual != *expected {
+                return Err(format!(
+                    "expected row {} to be {}, but it's {}",
+                    row, expected, actual
+                ));
+            }
+        }
+        Command::ExpectSelected(expected) => {
+            let actual = controller.nonogram.selected_cell;
+            if actual != *expected {
+                let describe = |cell: Option<[usize; 2]>| match cell {
+                    Some(ind) => format!("{} {}", ind[0], ind[1]),
+                    None => "none".to_string(),
+                };
+                return Err(format!(
+                    "expected {} to be selected, but it's {}",
+                    describe(*expected),
+                    describe(actual)
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A cell of the board, or an error if it isn't on the board.
+fn board_cell(controller: &NonogramController, ind: [usize; 2]) -> Result<&Cell, String> {
+    controller
+        .nonogram
+        .data
+        .get(ind[0])
+        .and_then(|col| col.get(ind[1]))
+        .ok_or_else(|| format!("cell {} {} isn't on the board", ind[0], ind[1]))
+}
+
+/// Name of a cell as it's written in `expect cell`.
+fn cell_name(cell: Cell) -> &'static str {
+    match cell {
+        Cell::Empty => "empty",
+        Cell::Filled => "filled",
+        Cell::Colored(_) => "colored",
+        Cell::Marked => "marked",
+        Cell::Maybe => "maybe",
+        Cell::Half(..) => "half",
+    }
+}
+
+/// Character of a cell as it's written in `expect row`.
+fn cell_char(cell: Cell) -> char {
+    match cell {
+        Cell::Empty => '.',
+        Cell::Filled => '#',
+        Cell::Marked => 'x',
+        Cell::Maybe => '?',
+        _ => 'o',
+    }
+}
+
+/// Size of the board in pixels.
+fn board_size(controller: &NonogramController) -> [f64; 2] {
+    [
+        controller.nonogram.dimensions[0] as f64 * CELL_SIZE,
+        controller.nonogram.dimensions[1] as f64 * CELL_SIZE,
+    ]
+}
+
+/// Hand a single event to the controller.
+fn send(controller: &mut NonogramController, e: &Event) {
+    let layout = WindowLayout::board_only(BOARD_POSITION, board_size(controller));
+    controller.event(&layout, e);
+}
+
+/// Move the mouse over the middle of a cell, or off the board.
+fn hover(controller: &mut NonogramController, cell: Option<[usize; 2]>) {
+    let pos = match cell {
+        Some(ind) => [
+            BOARD_POSITION[0] + (ind[0] as f64 + 0.5) * CELL_SIZE,
+            BOARD_POSITION[1] + (ind[1] as f64 + 0.5) * CELL_SIZE,
+        ],
+        None => OFF_BOARD,
+    };
+    send(
+        controller,
+        &Event::Input(Input::Move(Motion::MouseCursor(pos)), None),
+    );
+}
+
+/// Press or let go of a button.
+fn send_button(controller: &mut NonogramController, button: Button, state: ButtonState) {
+    let args = ButtonArgs {
+        state,
+        button,
+        scancode: None,
+    };
+    send(controller, &Event::Input(Input::Button(args), None));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nonogram_storage::MemoryStorage;
+
+    /// Run a script on a fresh controller, panicking with the script's error if it fails.
+    fn run(script: &str) -> NonogramController {
+        let mut controller =
+            NonogramController::new(NonogramBoard::new([5, 5]), Box::new(MemoryStorage::new()));
+        Script::parse(script)
+            .and_then(|script| script.run(&mut controller))
+            .unwrap_or_else(|why| panic!("{}", why));
+        controller
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(Script::parse("# comment\n\nclick 1 2\nexpect row 0 .....").is_ok());
+        assert_eq!(
+            Script::parse("click 1\n").unwrap_err(),
+            "line 1: missing a number in \"click 1\""
+        );
+        assert_eq!(
+            Script::parse("\nhover 1 2 3").unwrap_err(),
+            "line 2: too many arguments in \"hover 1 2 3\""
+        );
+        assert!(Script::parse("tap NotAKey").is_err());
+        assert!(Script::parse("board 0x5").is_err());
+    }
+
+    #[test]
+    fn test_drag() {
+        run("
+            board 5x5 1
+            drag 0 0 3 0
+            expect row 0 ####.
+            expect selected 3 0
+            rdrag 4 4 4 2
+            expect cell 4 2 marked
+            expect cell 4 4 marked
+            expect cell 4 1 empty
+        ");
+    }
+
+    #[test]
+    fn test_drag_lock() {
+        run("
+            # Drifting off the row a drag started on keeps filling that row.
+            board 5x5 1
+            hover 0 1
+            press left
+            hover 1 1
+            hover 2 2
+            hover 3 2
+            release left
+            expect row 1 ####.
+            expect row 2 .....
+        ");
+    }
+
+    #[test]
+    fn test_drag_same_state() {
+        run("
+            # Dragging only changes cells that started out like the first one.
+            board 5x5 1
+            rclick 2 0
+            drag 0 0 4 0
+            expect row 0 ##x##
+            drag 0 0 4 0
+            expect row 0 ..x..
+        ");
+    }
+
+    #[test]
+    fn test_hover() {
+        run("
+            board 5x5 1
+            hover 2 3
+            expect selected 2 3
+            hover off
+            expect selected 2 3
+            tap J
+            expect cell 2 3 filled
+        ");
+    }
+
+    #[test]
+    fn test_keys() {
+        run("
+            board 5x5 1
+            hover 0 0
+            press J
+            tap D
+            tap D
+            release J
+            tap D
+            expect row 0 ###..
+            expect selected 3 0
+            tap K
+            expect cell 3 0 marked
+        ");
+    }
+
+    #[test]
+    fn test_repeat() {
+        run("
+            # Holding a movement key down moves again once the repeat delay has passed.
+            board 5x5 1
+            hover 0 0
+            press D
+            expect selected 1 0
+            wait 350
+            release D
+            expect selected 2 0
+        ");
+    }
+
+    #[test]
+    fn test_paused() {
+        run("
+            board 5x5 1
+            tap P
+            # The first click only resumes the game.
+            click 1 1
+            expect cell 1 1 empty
+            click 1 1
+            expect cell 1 1 filled
+        ");
+    }
+
+    #[test]
+    fn test_failed_expectation() {
+        let mut controller =
+            NonogramController::new(NonogramBoard::new([5, 5]), Box::new(MemoryStorage::new()));
+        let script = Script::parse("board 5x5\nclick 0 0\nexpect cell 0 0 marked").unwrap();
+        assert_eq!(
+            script.run(&mut controller).unwrap_err(),
+            "line 3: expected cell 0 0 to be marked, but it's filled"
+        );
+    }
+}
@@ -0,0 +1,601 @@
+//! Responsible for solving black and white puzzles from their hint numbers alone, without a window.
+//!
+//! Every column and row is solved on its own by finding which of its cells are filled or empty in every
+//! arrangement of its hint numbers that fits what's already known. That's repeated until nothing changes. Then
+//! every unknown cell is tried both ways to see what follows from each, and only after that does the solver
+//! guess a cell and try both possibilities.
+
+use std::fmt;
+
+use crate::common::Ruleset;
+
+/// The hint numbers of a black and white puzzle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Puzzle {
+    /// Hint numbers of every column, from left to right. Each column's hint numbers go from top to bottom.
+    pub columns: Vec<Vec<usize>>,
+
+    /// Hint numbers of every row, from top to bottom. Each row's hint numbers go from left to right.
+    pub rows: Vec<Vec<usize>>,
+}
+
+/// A filled in board, laid out just like `NonogramBoard::data`. True means filled.
+/// ```text
+/// grid[which_column][which_row];
+/// ```
+pub type Grid = Vec<Vec<bool>>;
+
+/// Every cell of a column or row, where `None` means it isn't known yet.
+type Line = Vec<Option<bool>>;
+
+impl Puzzle {
+    /// Create a puzzle from hint numbers laid out just like `NonogramBoard::goal_nums`. Zeros are padding, and
+    /// crossed out hint numbers are negative.
+    pub fn from_goal_nums(goal_nums: &[Vec<Vec<i8>>]) -> Result<Puzzle, String> {
+        if goal_nums.len() != 2 {
+            return Err("goal_nums needs a list of columns and a list of rows".to_string());
+        }
+        let lines = |axis: usize| -> Vec<Vec<usize>> {
+            goal_nums[axis]
+                .iter()
+                .map(|line| {
+                    line.iter()
+                        .filter(|&&num| num != 0)
+                        .map(|num| num.unsigned_abs() as usize)
+                        .collect()
+                })
+                .collect()
+        };
+        Ok(Puzzle {
+            columns: lines(0),
+            rows: lines(1),
+        })
+    }
+
+    /// Read a puzzle from the contents of a puzzle file.
+    ///
+    /// JSON files can either be the `goal_nums` list on its own, or any object with a `goal_nums` field, like
+    /// savedata.json. Saves of color puzzles or puzzles with another ruleset can't be solved, since their hint
+    /// numbers mean something else. Anything else is read as a `.non` file, which has a `width` and `height` line followed by
+    /// a `rows` section and a `columns` section, with one line of comma separated hint numbers per column or row.
+    /// Any other line starting with a word, like `title` or `goal`, ends a section and is skipped.
+    pub fn parse(contents: &str) -> Result<Puzzle, String> {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(contents) {
+            // Saves from before color puzzles and rulesets existed are black and white standard puzzles.
+            let colors = value
+                .get("colors")
+                .and_then(|colors| colors.as_u64())
+                .unwrap_or(1);
+            let ruleset: Ruleset = match value.get("ruleset") {
+                Some(ruleset) => serde_json::from_value(ruleset.clone())
+                    .map_err(|why| format!("Couldn't read ruleset: {}", why))?,
+                None => Ruleset::Standard,
+            };
+            if colors > 1 || ruleset != Ruleset::Standard {
+                return Err("only black and white standard puzzles can be solved".to_string());
+            }
+
+            let goal_nums = match value.get("goal_nums") {
+                Some(goal_nums) => goal_nums.clone(),
+                None => value,
+            };
+            let goal_nums: Vec<Vec<Vec<i8>>> = serde_json::from_value(goal_nums)
+                .map_err(|why| format!("Couldn't read goal_nums: {}", why))?;
+            return Puzzle::from_goal_nums(&goal_nums);
+        }
+
+        let mut dimensions = [None, None];
+        let mut puzzle = Puzzle {
+            columns: vec![],
+            rows: vec![],
+        };
+        let mut section = None;
+        for line in contents.lines().map(str::trim) {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                None => continue,
+                Some("width") | Some("height") => {
+                    let axis = if line.starts_with("width") { 0 } else { 1 };
+                    let value = words
+                        .next()
+                        .and_then(|value| value.parse::<usize>().ok())
+                        .ok_or_else(|| format!("Bad line: {}", line))?;
+                    dimensions[axis] = Some(value);
+                    section = None;
+                }
+                Some("columns") => section = Some(0),
+                Some("rows") => section = Some(1),
+                // Anything else in a .non file, like its title or author, doesn't matter here.
+                Some(word) if word.starts_with(|c: char| c.is_ascii_alphabetic()) => section = None,
+                Some(_) => match section {
+                    Some(axis) => {
+                        let nums = line
+                            .split(',')
+                            .map(|num| num.trim().parse::<usize>())
+                            .collect::<Result<Vec<usize>, _>>()
+                            .map_err(|_| format!("Bad hint numbers: {}", line))?;
+                        let nums = nums.into_iter().filter(|&num| num != 0).collect();
+                        if axis == 0 {
+                            puzzle.columns.push(nums);
+                        } else {
+                            puzzle.rows.push(nums);
+                        }
+                    }
+                    None => return Err(format!("Hint numbers outside a section: {}", line)),
+                },
+            }
+        }
+
+        let lengths = [puzzle.columns.len(), puzzle.rows.len()];
+        for axis in 0..2 {
+            if let Some(expected) = dimensions[axis] {
+                if expected != lengths[axis] {
+                    return Err(format!(
+                        "Expected {} {}, found {}",
+                        expected,
+                        ["columns", "rows"][axis],
+                        lengths[axis]
+                    ));
+                }
+            }
+        }
+        if lengths[0] == 0 || lengths[1] == 0 {
+            return Err("Puzzle has no columns or rows".to_string());
+        }
+        Ok(puzzle)
+    }
+
+    /// Columns and rows of the puzzle.
+    pub fn dimensions(&self) -> [usize; 2] {
+        [self.columns.len(), self.rows.len()]
+    }
+}
+
+/// Every solution found by `solve`, up to the limit it was given.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solutions {
+    /// The solutions found, in the order they were found.
+    pub grids: Vec<Grid>,
+
+    /// True if the search stopped early because the limit was reached, so there might be more solutions.
+    pub stopped_early: bool,
+}
+
+impl Solutions {
+    /// Whether or not the puzzle has exactly one solution. Only known for sure when searching for at least 2.
+    pub fn is_unique(&self) -> bool {
+        self.grids.len() == 1 && !self.stopped_early
+    }
+}
+
+impl fmt::Display for Solutions {
+    /// A short verdict about how many solutions there are.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.grids.len(), self.stopped_early) {
+            (0, _) => write!(f, "no solution"),
+            (1, false) => write!(f, "unique"),
+            (count, false) => write!(f, "{} solutions", count),
+            (1, true) => write!(f, "at least 1 solution"),
+            (count, true) => write!(f, "at least {} solutions", count),
+        }
+    }
+}
+
+/// How hard a puzzle with a unique solution is for a person to solve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    /// Every cell can be found by looking at one column or row at a time.
+    Easy,
+
+    /// Some cells can only be found by trying both possibilities and seeing what follows from each.
+    Medium,
+
+    /// Some cells have to be guessed, or the puzzle has more than one solution.
+    Hard,
+}
+
+impl fmt::Display for Difficulty {
+    /// The name of the difficulty, as written in puzzle files.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "easy"),
+            Difficulty::Medium => write!(f, "medium"),
+            Difficulty::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+/// Rate how hard a puzzle is by how far the solver gets without guessing.
+pub fn rate(puzzle: &Puzzle) -> Difficulty {
+    let [cols, rows] = puzzle.dimensions();
+    let mut grid = vec![vec![None; rows]; cols];
+    if !propagate(puzzle, &mut grid, all_lines(puzzle)) {
+        return Difficulty::Hard;
+    }
+    if grid.iter().flatten().all(Option::is_some) {
+        return Difficulty::Easy;
+    }
+    if probe(puzzle, &mut grid) && grid.iter().flatten().all(Option::is_some) {
+        Difficulty::Medium
+    } else {
+        Difficulty::Hard
+    }
+}
+
+/// Find up to `limit` solutions of a puzzle. Searching for 2 is enough to tell whether a solution is unique.
+pub fn solve(puzzle: &Puzzle, limit: usize) -> Solutions {
+    let [cols, rows] = puzzle.dimensions();
+    let mut solutions = Solutions {
+        grids: vec![],
+        stopped_early: false,
+    };
+    if limit > 0 {
+        search(puzzle, vec![vec![None; rows]; cols], limit, &mut solutions);
+    }
+    solutions
+}
+
+/// Whether or not a puzzle can be solved without guessing, one column or row at a time. Puzzles like this can
+/// always be solved by a person using logic alone.
+pub fn is_line_solvable(puzzle: &Puzzle) -> bool {
+    let [cols, rows] = puzzle.dimensions();
+    let mut grid = vec![vec![None; rows]; cols];
+    propagate(puzzle, &mut grid, all_lines(puzzle)) && grid.iter().flatten().all(Option::is_some)
+}
+
+/// Solve as much as possible without guessing, then guess the first unknown cell and try both possibilities.
+fn search(puzzle: &Puzzle, mut grid: Vec<Line>, limit: usize, solutions: &mut Solutions) {
+    if !propagate(puzzle, &mut grid, all_lines(puzzle)) || !probe(puzzle, &mut grid) {
+        return;
+    }
+
+    let unknown = grid
+        .iter()
+        .enumerate()
+        .find_map(|(col, cells)| cells.iter().position(Option::is_none).map(|row| [col, row]));
+    match unknown {
+        None => solutions.grids.push(
+            grid.into_iter()
+                .map(|cells| cells.into_iter().map(|cell| cell == Some(true)).collect())
+                .collect(),
+        ),
+        Some([col, row]) => {
+            for guess in [true, false].iter() {
+                if solutions.grids.len() >= limit {
+                    solutions.stopped_early = true;
+                    return;
+                }
+                let mut guessed = grid.clone();
+                guessed[col][row] = Some(*guess);
+                search(puzzle, guessed, limit, solutions);
+            }
+        }
+    }
+}
+
+/// Every column (`axis` 0) and row (`axis` 1) of a puzzle.
+fn all_lines(puzzle: &Puzzle) -> Vec<[usize; 2]> {
+    let [cols, rows] = puzzle.dimensions();
+    (0..cols)
+        .map(|col| [0, col])
+        .chain((0..rows).map(|row| [1, row]))
+        .collect()
+}
+
+/// Try both possibilities for every unknown cell, solving one line at a time after each. If one of them can't be
+/// solved, the cell has to be the other one, and any cell that ends up the same either way is known too. Repeats
+/// until nothing changes. Returns false if the grid has no solution.
+fn probe(puzzle: &Puzzle, grid: &mut Vec<Line>) -> bool {
+    let [cols, rows] = puzzle.dimensions();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for col in 0..cols {
+            for row in 0..rows {
+                if grid[col][row].is_some() {
+                    continue;
+                }
+                let outcomes: Vec<Option<Vec<Line>>> = [true, false]
+                    .iter()
+                    .map(|&guess| {
+                        let mut guessed = grid.clone();
+                        guessed[col][row] = Some(guess);
+                        if propagate(puzzle, &mut guessed, vec![[0, col], [1, row]]) {
+                            Some(guessed)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                match (&outcomes[0], &outcomes[1]) {
+                    (None, None) => return false,
+                    (Some(only), None) | (None, Some(only)) => {
+                        *grid = only.clone();
+                        changed = true;
+                    }
+                    (Some(filled), Some(empty)) => {
+                        for (k, cells) in grid.iter_mut().enumerate() {
+                            for (j, cell) in cells.iter_mut().enumerate() {
+                                if cell.is_none()
+                                    && filled[k][j].is_some()
+                                    && filled[k][j] == empty[k][j]
+                                {
+                                    *cell = filled[k][j];
+                                    changed = true;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Solve lines one at a time until nothing changes, starting with `queue`. Whenever a line learns something new,
+/// every line crossing it is solved again. Returns false if some line can't be solved, which means the grid has no
+/// solution.
+fn propagate(puzzle: &Puzzle, grid: &mut [Line], mut queue: Vec<[usize; 2]>) -> bool {
+    let [cols, rows] = puzzle.dimensions();
+    let mut queued = [vec![false; cols], vec![false; rows]];
+    for &[axis, index] in queue.iter() {
+        queued[axis][index] = true;
+    }
+
+    while let Some([axis, index]) = queue.pop() {
+        queued[axis][index] = false;
+        let (nums, line): (&[usize], Line) = if axis == 0 {
+            (&puzzle.columns[index], grid[index].clone())
+        } else {
+            (
+                &puzzle.rows[index],
+                (0..cols).map(|col| grid[col][index]).collect(),
+            )
+        };
+        let solved = match solve_line(nums, &line) {
+            None => return false,
+            Some(solved) => solved,
+        };
+        for (k, cell) in solved.into_iter().enumerate() {
+            if cell == line[k] {
+                continue;
+            }
+            if axis == 0 {
+                grid[index][k] = cell;
+            } else {
+                grid[k][index] = cell;
+            }
+            let crossing = [1 - axis, k];
+            if !queued[crossing[0]][crossing[1]] {
+                queued[crossing[0]][crossing[1]] = true;
+                queue.push(crossing);
+            }
+        }
+    }
+    true
+}
+
+/// Find every cell of a line that's filled in every arrangement of its hint numbers, and every cell that's empty
+/// in every arrangement, keeping whatever was already known. Returns `None` if no arrangement fits.
+fn solve_line(nums: &[usize], line: &[Option<bool>]) -> Option<Line> {
+    let len = line.len();
+    let count = nums.len();
+
+    // fits[i][j] is whether the cells from i onwards can hold hint numbers j onwards, with a gap or the start of the
+    // line right before cell i.
+    let mut fits = vec![vec![false; count + 1]; len + 2];
+    for i in (0..=len).rev() {
+        for j in (0..=count).rev() {
+            fits[i][j] = if j == count {
+                line[i..].iter().all(|&cell| cell != Some(true))
+            } else {
+                let gap = i < len && line[i] != Some(true) && fits[i + 1][j];
+                gap || block_fits(nums[j], line, i) && fits[(i + nums[j] + 1).min(len)][j + 1]
+            };
+        }
+    }
+    if !fits[0][0] {
+        return None;
+    }
+
+    // Walk through every arrangement that fits, remembering what each cell can be.
+    let mut can_fill = vec![false; len];
+    let mut can_empty = vec![false; len];
+    let mut reached = vec![vec![false; count + 1]; len + 2];
+    reached[0][0] = true;
+    for i in 0..=len {
+        for j in 0..=count {
+            if !reached[i][j] || !fits[i][j] {
+                continue;
+            }
+            if j == count {
+                can_empty[i..].iter_mut().for_each(|cell| *cell = true);
+                continue;
+            }
+            if i < len && line[i] != Some(true) && fits[i + 1][j] {
+                can_empty[i] = true;
+                reached[i + 1][j] = true;
+            }
+            let end = i + nums[j];
+            if block_fits(nums[j], line, i) && fits[(end + 1).min(len)][j + 1] {
+                can_fill[i..end].iter_mut().for_each(|cell| *cell = true);
+                if end < len {
+                    can_empty[end] = true;
+                }
+                reached[(end + 1).min(len)][j + 1] = true;
+            }
+        }
+    }
+
+    Some(
+        (0..len)
+            .map(|k| match (can_fill[k], can_empty[k]) {
+                (true, false) => Some(true),
+                (false, true) => Some(false),
+                _ => line[k],
+            })
+            .collect(),
+    )
+}
+
+/// Whether or not a block of `size` filled cells can start at cell `start` of a line, followed by a gap or the end
+/// of the line.
+fn block_fits(size: usize, line: &[Option<bool>], start: usize) -> bool {
+    let end = start + size;
+    end <= line.len()
+        && line[start..end].iter().all(|&cell| cell != Some(false))
+        && (end == line.len() || line[end] != Some(true))
+}
+
+/// Draw a grid as text, one row per line, with `#` for filled cells and `.` for empty ones.
+pub fn format_grid(grid: &Grid) -> String {
+    let rows = grid.first().map_or(0, Vec::len);
+    (0..rows)
+        .map(|row| {
+            grid.iter()
+                .map(|cells| if cells[row] { '#' } else { '.' })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NonogramBoard;
+
+    #[test]
+    fn test_solve_line() {
+        assert_eq!(
+            solve_line(&[3], &[None; 4]),
+            Some(vec![None, Some(true), Some(true), None]),
+            "The middle of a long block is always filled."
+        );
+        assert_eq!(
+            solve_line(&[1, 1], &[None; 3]),
+            Some(vec![Some(true), Some(false), Some(true)]),
+            "A line that's exactly full has one arrangement."
+        );
+        assert_eq!(
+            solve_line(&[], &[None, Some(true)]),
+            None,
+            "An empty line can't have filled cells."
+        );
+        assert_eq!(
+            solve_line(&[2], &[None, Some(false), None]),
+            None,
+            "A block can't cross an empty cell."
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let non = "title \"test\"\nwidth 2\nheight 2\n\nrows\n2\n1\n\ncolumns\n2\n1\n";
+        let puzzle = Puzzle::parse(non).expect("valid .non file");
+        assert_eq!(puzzle.rows, vec![vec![2], vec![1]], "Rows are read.");
+        assert_eq!(puzzle.columns, vec![vec![2], vec![1]], "Columns are read.");
+
+        let json = "{\"goal_nums\": [[[2, 0], [-1, 0]], [[2, 0], [1, 0]]]}";
+        assert_eq!(
+            Puzzle::parse(json),
+            Ok(puzzle),
+            "Save data reads the same, without padding or crossouts."
+        );
+        assert!(
+            Puzzle::parse("width 3\nrows\n1\ncolumns\n1\n").is_err(),
+            "The width has to match the columns."
+        );
+        assert_eq!(
+            Puzzle::parse(&format!("{}difficulty easy\ngoal \"1110\"\n", non)),
+            Puzzle::parse(non),
+            "Words after the hint numbers end the section."
+        );
+    }
+
+    #[test]
+    fn test_parse_variants() {
+        let color_save = serde_json::to_string(
+            &NonogramBoard::with_variant([5, 5], 3, Ruleset::Standard).saved(),
+        )
+        .unwrap();
+        assert_eq!(
+            Puzzle::parse(&color_save),
+            Err("only black and white standard puzzles can be solved".to_string()),
+            "Color hint numbers can't be solved as black and white ones."
+        );
+
+        let triangle_save = serde_json::to_string(
+            &NonogramBoard::with_variant([5, 5], 1, Ruleset::Triangles).saved(),
+        )
+        .unwrap();
+        assert!(Puzzle::parse(&triangle_save).is_err());
+
+        let save = serde_json::to_string(&NonogramBoard::new([5, 5]).saved()).unwrap();
+        assert!(
+            Puzzle::parse(&save).is_ok(),
+            "Black and white standard saves can be solved."
+        );
+    }
+
+    #[test]
+    fn test_solve() {
+        let puzzle = Puzzle {
+            columns: vec![vec![2], vec![1]],
+            rows: vec![vec![2], vec![1]],
+        };
+        let solutions = solve(&puzzle, 2);
+        assert!(solutions.is_unique(), "Puzzle has one solution.");
+        assert_eq!(
+            format_grid(&solutions.grids[0]),
+            "##\n#.",
+            "Solution is drawn row by row."
+        );
+        assert!(is_line_solvable(&puzzle), "No guessing needed.");
+
+        let puzzle = Puzzle {
+            columns: vec![vec![1], vec![1]],
+            rows: vec![vec![1], vec![1]],
+        };
+        let solutions = solve(&puzzle, 2);
+        assert_eq!(solutions.grids.len(), 2, "Both diagonals fit.");
+        assert_eq!(
+            solutions.to_string(),
+            "2 solutions",
+            "Every solution was found."
+        );
+        assert_eq!(
+            solve(&puzzle, 1).to_string(),
+            "at least 1 solution",
+            "The search stops at the limit."
+        );
+        assert!(!is_line_solvable(&puzzle), "Guessing is needed.");
+
+        let puzzle = Puzzle {
+            columns: vec![vec![2], vec![]],
+            rows: vec![vec![], vec![]],
+        };
+        assert_eq!(solve(&puzzle, 2).to_string(), "no solution");
+    }
+
+    #[test]
+    fn test_rate() {
+        let easy = Puzzle {
+            columns: vec![vec![2], vec![1]],
+            rows: vec![vec![2], vec![1]],
+        };
+        assert_eq!(rate(&easy), Difficulty::Easy, "No guessing needed.");
+
+        let hard = Puzzle {
+            columns: vec![vec![1], vec![1]],
+            rows: vec![vec![1], vec![1]],
+        };
+        assert_eq!(rate(&hard), Difficulty::Hard, "Either diagonal fits.");
+        assert_eq!(
+            Difficulty::Hard.to_string(),
+            "hard",
+            "Difficulties are written in lowercase."
+        );
+    }
+}
@@ -0,0 +1,360 @@
+//! Responsible for keeping track of statistics and personal bests in between sessions.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::common::{GameMode, Ruleset, STATS_FILE, STATS_HISTORY_LENGTH};
+use crate::nonogram_storage::Storage;
+
+/// A single finished or abandoned game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    /// Columns and rows of the board that was played.
+    pub dimensions: [usize; 2],
+
+    /// Game mode the board was played in.
+    #[serde(default)]
+    pub mode: GameMode,
+
+    /// Number of palette colors of the board that was played.
+    #[serde(default)]
+    pub colors: usize,
+
+    /// Ruleset of the board that was played.
+    #[serde(default)]
+    pub ruleset: Ruleset,
+
+    /// How long the game lasted.
+    pub duration: Duration,
+
+    /// Whether or not the board was solved.
+    pub won: bool,
+}
+
+/// Accumulated statistics for a single board size, game mode, number of colors and ruleset.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SizeStats {
+    /// Columns and rows these statistics belong to.
+    pub dimensions: [usize; 2],
+
+    /// Game mode these statistics belong to. Times in different game modes can't be compared.
+    #[serde(default)]
+    pub mode: GameMode,
+
+    /// Number of palette colors these statistics belong to. Stats saved before color puzzles existed are all
+    /// black and white.
+    #[serde(default)]
+    pub colors: usize,
+
+    /// Ruleset these statistics belong to.
+    #[serde(default)]
+    pub ruleset: Ruleset,
+
+    /// Number of games that were either won or abandoned with this board size.
+    pub games_played: u64,
+
+    /// Number of games that were won with this board size.
+    pub games_won: u64,
+
+    /// Fastest winning time with this board size.
+    pub best_time: Option<Duration>,
+
+    /// Sum of every winning time with this board size. Used to calculate the average time.
+    pub total_time: Duration,
+}
+
+impl SizeStats {
+    /// Creates empty statistics for a board size, game mode, number of colors and ruleset.
+    pub fn new(
+        dimensions: [usize; 2],
+        mode: GameMode,
+        colors: usize,
+        ruleset: Ruleset,
+    ) -> SizeStats {
+        SizeStats {
+            dimensions,
+            mode,
+            colors,
+            ruleset,
+            games_played: 0,
+            games_won: 0,
+            best_time: None,
+            total_time: Duration::from_secs(0),
+        }
+    }
+
+    /// Average winning time with this board size. Returns `None` if no games have been won.
+    pub fn average_time(&self) -> Option<Duration> {
+        if self.games_won == 0 {
+            None
+        } else {
+            Some(self.total_time / self.games_won as u32)
+        }
+    }
+}
+
+/// Contains every statistic we're going to save in between each session.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NonogramStats {
+    /// Statistics for every combination of board size, game mode, number of colors and ruleset that has been played
+    /// at least once.
+    pub sizes: Vec<SizeStats>,
+
+    /// The most recent games, oldest first. Limited to `STATS_HISTORY_LENGTH` entries.
+    pub history: Vec<GameRecord>,
+}
+
+/// NonogramStats functionality.
+impl NonogramStats {
+    /// Load statistics from statsdata.json. If it doesn't exist, we start from scratch. Returns an error if it
+    /// exists but can't be read.
+    pub fn load<S: Storage + ?Sized>(storage: &S) -> Result<NonogramStats, String> {
+        let mut stats: NonogramStats = match storage.read(STATS_FILE) {
+            Some(stats_data) => serde_json::from_str(&stats_data)
+                .map_err(|why| format!("Couldn't read {}: {}", STATS_FILE, why))?,
+            None => return Ok(NonogramStats::default()),
+        };
+
+        // Stats from before color puzzles existed don't have a number of colors.
+        for size_stats in stats.sizes.iter_mut() {
+            size_stats.colors = size_stats.colors.max(1);
+        }
+        for record in stats.history.iter_mut() {
+            record.colors = record.colors.max(1);
+        }
+        Ok(stats)
+    }
+
+    /// Write statistics to statsdata.json.
+    pub fn save<S: Storage + ?Sized>(&self, storage: &mut S) {
+        let result = serde_json::to_string_pretty(self)
+            .map_err(|why| format!("Couldn't write to {}: {}", STATS_FILE, why))
+            .and_then(|contents| storage.write(STATS_FILE, &contents));
+        if let Err(why) = result {
+            println!("{}", why);
+        }
+    }
+
+    /// Get statistics for a board size, game mode, number of colors and ruleset, if that combination has ever
+    /// been played.
+    pub fn get(
+        &self,
+        dimensions: [usize; 2],
+        mode: GameMode,
+        colors: usize,
+        ruleset: Ruleset,
+    ) -> Option<&SizeStats> {
+        self.sizes.iter().find(|s| {
+            s.dimensions == dimensions
+                && s.mode == mode
+                && s.colors == colors
+                && s.ruleset == ruleset
+        })
+    }
+
+    /// Record the result of a game. Returns true if the game was won with a new personal best time.
+    pub fn record_game(
+        &mut self,
+        dimensions: [usize; 2],
+        mode: GameMode,
+        colors: usize,
+        ruleset: Ruleset,
+        duration: Duration,
+        won: bool,
+    ) -> bool {
+        let index = match self.sizes.iter().position(|s| {
+            s.dimensions == dimensions
+                && s.mode == mode
+                && s.colors == colors
+                && s.ruleset == ruleset
+        }) {
+            Some(index) => index,
+            None => {
+                self.sizes
+                    .push(SizeStats::new(dimensions, mode, colors, ruleset));
+                self.sizes.len() - 1
+            }
+        };
+        let size_stats = &mut self.sizes[index];
+
+        size_stats.games_played += 1;
+        let mut personal_best = false;
+        if won {
+            size_stats.games_won += 1;
+            size_stats.total_time += duration;
+            personal_best = match size_stats.best_time {
+                Some(best_time) => duration < best_time,
+                None => true,
+            };
+            if personal_best {
+                size_stats.best_time = Some(duration);
+            }
+        }
+
+        self.history.push(GameRecord {
+            dimensions,
+            mode,
+            colors,
+            ruleset,
+            duration,
+            won,
+        });
+        if self.history.len() > STATS_HISTORY_LENGTH {
+            let overflow = self.history.len() - STATS_HISTORY_LENGTH;
+            self.history.drain(..overflow);
+        }
+
+        personal_best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nonogram_storage::MemoryStorage;
+
+    #[test]
+    fn test_record_game() {
+        let mut stats = NonogramStats::default();
+        assert!(
+            stats.record_game(
+                [5, 5],
+                GameMode::Classic,
+                1,
+                Ruleset::Standard,
+                Duration::from_secs(60),
+                true
+            ),
+            "First win is a personal best."
+        );
+        assert!(
+            !stats.record_game(
+                [5, 5],
+                GameMode::Classic,
+                1,
+                Ruleset::Standard,
+                Duration::from_secs(90),
+                true
+            ),
+            "Slower win isn't a personal best."
+        );
+        assert!(
+            !stats.record_game(
+                [5, 5],
+                GameMode::Classic,
+                1,
+                Ruleset::Standard,
+                Duration::from_secs(10),
+                false
+            ),
+            "Abandoned game isn't a personal best."
+        );
+        assert!(
+            stats.record_game(
+                [5, 5],
+                GameMode::Classic,
+                1,
+                Ruleset::Standard,
+                Duration::from_secs(30),
+                true
+            ),
+            "Faster win is a personal best."
+        );
+
+        let size_stats = stats
+            .get([5, 5], GameMode::Classic, 1, Ruleset::Standard)
+            .unwrap();
+        assert_eq!(size_stats.games_played, 4, "Every game was counted.");
+        assert_eq!(size_stats.games_won, 3, "Only won games were counted.");
+        assert_eq!(size_stats.best_time, Some(Duration::from_secs(30)));
+        assert_eq!(size_stats.average_time(), Some(Duration::from_secs(60)));
+        assert_eq!(stats.history.len(), 4, "Every game was added to history.");
+        assert!(
+            stats
+                .get([10, 10], GameMode::Classic, 1, Ruleset::Standard)
+                .is_none(),
+            "Unplayed size has no stats."
+        );
+        assert!(
+            stats
+                .get([5, 5], GameMode::Countdown, 1, Ruleset::Standard)
+                .is_none(),
+            "Unplayed mode has no stats."
+        );
+        assert!(
+            stats
+                .get([5, 5], GameMode::Classic, 3, Ruleset::Standard)
+                .is_none(),
+            "Color puzzles have their own stats."
+        );
+        assert!(
+            stats
+                .get([5, 5], GameMode::Classic, 1, Ruleset::Mega)
+                .is_none(),
+            "Every ruleset has its own stats."
+        );
+    }
+
+    #[test]
+    fn test_load() {
+        let mut storage = MemoryStorage::new();
+        assert_eq!(
+            NonogramStats::load(&storage),
+            Ok(NonogramStats::default()),
+            "No stats file means no games have been played."
+        );
+
+        let mut stats = NonogramStats::default();
+        stats.record_game(
+            [5, 5],
+            GameMode::Classic,
+            1,
+            Ruleset::Standard,
+            Duration::from_secs(60),
+            true,
+        );
+        stats.save(&mut storage);
+        assert_eq!(NonogramStats::load(&storage), Ok(stats));
+
+        // Stats from before color puzzles and rulesets existed are black and white standard puzzles.
+        storage
+            .write(
+                STATS_FILE,
+                r#"{"sizes": [{"dimensions": [5, 5], "games_played": 1, "games_won": 0, "best_time": null,
+                    "total_time": {"secs": 0, "nanos": 0}}]}"#,
+            )
+            .unwrap();
+        let old_stats = NonogramStats::load(&storage).unwrap();
+        assert!(old_stats
+            .get([5, 5], GameMode::Classic, 1, Ruleset::Standard)
+            .is_some());
+
+        storage.write(STATS_FILE, "not stats").unwrap();
+        assert!(
+            NonogramStats::load(&storage).is_err(),
+            "A stats file that can't be read is an error."
+        );
+    }
+
+    #[test]
+    fn test_history_length() {
+        let mut stats = NonogramStats::default();
+        for i in 0..(STATS_HISTORY_LENGTH + 5) {
+            stats.record_game(
+                [5, 5],
+                GameMode::Classic,
+                1,
+                Ruleset::Standard,
+                Duration::from_secs(i as u64),
+                true,
+            );
+        }
+        assert_eq!(stats.history.len(), STATS_HISTORY_LENGTH);
+        assert_eq!(
+            stats.history[0].duration,
+            Duration::from_secs(5),
+            "Oldest games were dropped first."
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! Responsible for where save data is kept in between sessions.
+//!
+//! Save data is a handful of named JSON files, like savedata.json and clipdata.json. The game keeps them in the
+//! current directory, while tests keep them in memory so they don't depend on where they're run from.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Somewhere save files can be read from and written to.
+pub trait Storage {
+    /// Contents of a save file. `None` if it hasn't been written yet.
+    fn read(&self, name: &str) -> Option<String>;
+
+    /// Replace the contents of a save file.
+    fn write(&mut self, name: &str, contents: &str) -> Result<(), String>;
+
+    /// Delete a save file. Does nothing if it hasn't been written.
+    fn remove(&mut self, name: &str);
+}
+
+/// Keeps save files in a directory. This is what the game uses.
+pub struct FileStorage {
+    /// Directory that every save file is kept in.
+    pub dir: PathBuf,
+}
+
+impl FileStorage {
+    /// Keep save files in a directory.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> FileStorage {
+        FileStorage { dir: dir.into() }
+    }
+}
+
+impl Default for FileStorage {
+    /// Keep save files in the current directory.
+    fn default() -> Self {
+        FileStorage::new(".")
+    }
+}
+
+impl Storage for FileStorage {
+    fn read(&self, name: &str) -> Option<String> {
+        fs::read_to_string(self.dir.join(name))
+            .ok()
+            .filter(|contents| !contents.is_empty())
+    }
+
+    fn write(&mut self, name: &str, contents: &str) -> Result<(), String> {
+        let path = self.dir.join(name);
+        fs::write(&path, contents)
+            .map_err(|why| format!("Couldn't write to {}: {}", path.display(), why))
+    }
+
+    fn remove(&mut self, name: &str) {
+        let _ = fs::remove_file(self.dir.join(name));
+    }
+}
+
+/// Keeps save files in memory, so nothing touches the disk. Used by tests.
+#[derive(Default)]
+pub struct MemoryStorage {
+    /// Contents of every save file, by name.
+    files: HashMap<String, String>,
+}
+
+impl MemoryStorage {
+    /// Start without any save files.
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn read(&self, name: &str) -> Option<String> {
+        self.files.get(name).cloned()
+    }
+
+    fn write(&mut self, name: &str, contents: &str) -> Result<(), String> {
+        self.files.insert(name.to_string(), contents.to_string());
+        Ok(())
+    }
+
+    fn remove(&mut self, name: &str) {
+        self.files.remove(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_storage() {
+        let mut storage = MemoryStorage::new();
+        assert_eq!(storage.read("a.json"), None, "Nothing is saved yet.");
+        storage.write("a.json", "{}").unwrap();
+        assert_eq!(storage.read("a.json"), Some("{}".to_string()));
+        storage.remove("a.json");
+        assert_eq!(storage.read("a.json"), None, "Removed files are gone.");
+    }
+
+    #[test]
+    fn test_file_storage() {
+        let dir = std::env::temp_dir().join(format!("nonogram-storage-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut storage = FileStorage::new(&dir);
+        storage.write("a.json", "{}").unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("a.json")).unwrap(),
+            "{}",
+            "Files are written to the directory."
+        );
+        assert_eq!(storage.read("a.json"), Some("{}".to_string()));
+        storage.remove("a.json");
+        assert_eq!(storage.read("a.json"), None, "Removed files are gone.");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}